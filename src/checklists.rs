@@ -0,0 +1,102 @@
+//! Rendering Notes checklists as checkboxes.
+
+use std::fs;
+use std::path::Path;
+
+use scraper::{CaseSensitivity, Html, Node, Selector};
+
+use crate::Result;
+
+/// Adds a checkbox to each checklist item in `html`, checked for completed items.
+///
+/// Notes marks checklists as `<ul class="checklist">` (any class containing
+/// `checklist` is recognized) and completed items as `<li class="checked">`.
+/// Each item gets a disabled `<input type="checkbox">` as its first child, so
+/// the exported HTML shows which items are done. Items that already start
+/// with a checkbox are left alone.
+///
+/// [`ExportOptions::export_checklists_as_inputs`](crate::ExportOptions::export_checklists_as_inputs)
+/// applies this to every exported note.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::checklists_to_inputs;
+///
+/// let html = r#"<ul class="checklist"><li class="checked">Milk</li><li>Eggs</li></ul>"#;
+/// let html = checklists_to_inputs(html);
+///
+/// assert!(html.contains(r#"<li class="checked"><input checked="" disabled="" type="checkbox">Milk</li>"#));
+/// assert!(html.contains(r#"<li><input disabled="" type="checkbox">Eggs</li>"#));
+/// ```
+pub fn checklists_to_inputs(html: &str) -> String {
+    convert(html).unwrap_or_else(|| html.to_string())
+}
+
+/// Converts the checklists in every HTML file under `dir`, rewriting only
+/// the files that contain checklists.
+pub(crate) fn convert_directory(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            convert_directory(&path)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read_to_string(&path)?;
+            if let Some(converted) = convert(&html) {
+                fs::write(&path, converted)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the converted document, or `None` if it has no checklist items.
+fn convert(html: &str) -> Option<String> {
+    let item_selector = Selector::parse(r#"ul[class*="checklist" i] > li"#).unwrap();
+    let input_selector = Selector::parse("input").unwrap();
+
+    let mut document = Html::parse_document(html);
+    let items: Vec<_> = document
+        .select(&item_selector)
+        .filter(|item| {
+            let first_child = item
+                .first_child()
+                .and_then(|child| child.value().as_element());
+            !first_child.is_some_and(|element| {
+                element.name() == "input" && element.attr("type") == Some("checkbox")
+            })
+        })
+        .map(|item| {
+            let checked = item
+                .value()
+                .has_class("checked", CaseSensitivity::AsciiCaseInsensitive);
+            (item.id(), checked)
+        })
+        .collect();
+
+    if items.is_empty() {
+        return None;
+    }
+
+    let checkbox = |checked: bool| {
+        let markup = if checked {
+            r#"<input type="checkbox" disabled checked>"#
+        } else {
+            r#"<input type="checkbox" disabled>"#
+        };
+        let fragment = Html::parse_fragment(markup);
+        let input = fragment.select(&input_selector).next()?.value().clone();
+        Some(Node::Element(input))
+    };
+
+    for (id, checked) in items {
+        if let Some(mut item) = document.tree.get_mut(id) {
+            item.prepend(checkbox(checked)?);
+        }
+    }
+
+    Some(document.html())
+}
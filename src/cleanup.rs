@@ -0,0 +1,117 @@
+//! Removing Apple-specific presentation from exported notes.
+
+use std::fs;
+use std::path::Path;
+
+use scraper::{Html, Node};
+
+use crate::Result;
+
+/// Elements that only carry presentation, replaced by their contents.
+const UNWRAPPED_ELEMENTS: &[&str] = &["font"];
+
+/// Elements removed when cleaning leaves them with no contents.
+const REMOVED_WHEN_EMPTY: &[&str] = &[
+    "a", "b", "code", "div", "em", "font", "h1", "h2", "h3", "h4", "h5", "h6", "i", "p", "s",
+    "span", "strike", "strong", "sub", "sup", "tt", "u",
+];
+
+/// Removes the presentation Notes adds to a note's HTML, for publishing it
+/// or converting it to another format.
+///
+/// Inline `style` attributes and `Apple-*` classes are removed, `<font>`
+/// elements and `<span>`s left without attributes are replaced by their
+/// contents, and formatting elements with nothing in them are dropped.
+/// Headings, lists, tables, links, images, and other classes (such as those
+/// of checklists) are kept.
+///
+/// [`ExportOptions::clean_html`](crate::ExportOptions::clean_html) applies
+/// this to every exported note.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::clean_html;
+///
+/// let html = r#"<div style="font-family: Helvetica"><h1>Pancakes</h1></div>
+/// <div><font face="Helvetica" color="black"><span style="font-size: 12px">Flour</span></font><b></b></div>
+/// <div><a href="https://example.com" class="Apple-link">Recipe</a></div>"#;
+///
+/// assert_eq!(
+///     clean_html(html),
+///     "<html><head></head><body><div><h1>Pancakes</h1></div>\n\
+///      <div>Flour</div>\n\
+///      <div><a href=\"https://example.com\">Recipe</a></div></body></html>"
+/// );
+/// ```
+pub fn clean_html(html: &str) -> String {
+    let mut document = Html::parse_document(html);
+
+    // Children before their parents, so a parent emptied by cleaning is removed too
+    let ids: Vec<_> = document
+        .root_element()
+        .descendants()
+        .map(|node| node.id())
+        .collect();
+    for id in ids.into_iter().rev() {
+        let Some(mut node) = document.tree.get_mut(id) else {
+            continue;
+        };
+        let Node::Element(element) = node.value() else {
+            continue;
+        };
+
+        element.attrs.retain(|(name, _)| &*name.local != "style");
+        for (name, value) in element.attrs.iter_mut() {
+            if &*name.local == "class" {
+                let classes: Vec<&str> = value
+                    .split_whitespace()
+                    .filter(|class| !class.to_ascii_lowercase().starts_with("apple-"))
+                    .collect();
+                *value = classes.join(" ").into();
+            }
+        }
+        element
+            .attrs
+            .retain(|(name, value)| &*name.local != "class" || !value.is_empty());
+
+        let name = element.name().to_string();
+        let unwrap = UNWRAPPED_ELEMENTS.contains(&name.as_str())
+            || (name == "span" && element.attrs.is_empty());
+        let empty = document.tree.get(id).is_some_and(|node| {
+            node.children()
+                .all(|child| matches!(child.value(), Node::Text(text) if text.is_empty()))
+        });
+
+        let Some(mut node) = document.tree.get_mut(id) else {
+            continue;
+        };
+        if REMOVED_WHEN_EMPTY.contains(&name.as_str()) && empty {
+            node.detach();
+        } else if unwrap {
+            while let Some(child) = node.first_child().map(|child| child.id()) {
+                node.insert_id_before(child);
+            }
+            node.detach();
+        }
+    }
+
+    document.html()
+}
+
+/// Cleans every HTML file under `dir` with [`clean_html`].
+pub(crate) fn clean_directory(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            clean_directory(&path)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read_to_string(&path)?;
+            fs::write(&path, clean_html(&html))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Merging per-note attachment folders into one shared folder.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use scraper::{Html, Node, Selector};
+
+use crate::naming::{fnv1a, unique_name};
+use crate::{Result, set_attribute};
+
+/// The files already in the shared folder, for deduplication.
+struct SharedFolder {
+    dir: PathBuf,
+    /// Shared file names by content hash and length.
+    by_content: HashMap<(u64, usize), Vec<String>>,
+    /// Shared file names that are taken.
+    names: HashSet<String>,
+    /// Where each moved attachment ended up.
+    moved: HashMap<PathBuf, String>,
+}
+
+/// Moves every note's extracted attachments into one shared folder, `dir/shared`.
+///
+/// This converts an export that already has per-note `-attachments` folders
+/// without exporting it again. Identical files are stored once, files are
+/// renamed with a `-2`, `-3`, ... suffix if their names collide, and the
+/// `src` of every image in the HTML files under `dir` is rewritten to point
+/// into the shared folder. Attachment folders left empty are removed.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use apple_notes_exporter_rs::consolidate_attachments;
+///
+/// consolidate_attachments(Path::new("./exports"), "attachments")
+///     .expect("Failed to consolidate attachments");
+/// ```
+pub fn consolidate_attachments(dir: &Path, shared: &str) -> Result<()> {
+    let mut shared = SharedFolder {
+        dir: dir.join(shared),
+        by_content: HashMap::new(),
+        names: HashSet::new(),
+        moved: HashMap::new(),
+    };
+    fs::create_dir_all(&shared.dir)?;
+
+    for entry in fs::read_dir(&shared.dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let data = fs::read(entry.path())?;
+            shared
+                .by_content
+                .entry((fnv1a(&data), data.len()))
+                .or_default()
+                .push(name.clone());
+            shared.names.insert(name);
+        }
+    }
+
+    let mut attachment_dirs = Vec::new();
+    consolidate_recursive(dir, 0, &mut shared, &mut attachment_dirs)?;
+
+    for attachment_dir in attachment_dirs {
+        if fs::read_dir(&attachment_dir)?.next().is_none() {
+            fs::remove_dir(&attachment_dir)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn consolidate_recursive(
+    dir: &Path,
+    depth: usize,
+    shared: &mut SharedFolder,
+    attachment_dirs: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            if path == shared.dir {
+                continue;
+            }
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with("-attachments")
+            {
+                attachment_dirs.push(path);
+            } else {
+                consolidate_recursive(&path, depth + 1, shared, attachment_dirs)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            rewrite_note(&path, depth, shared)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves the attachments referenced by the note at `html_path`, `depth`
+/// directories below the export root, and points its images at them.
+fn rewrite_note(html_path: &Path, depth: usize, shared: &mut SharedFolder) -> Result<()> {
+    let img_selector = Selector::parse("img").unwrap();
+    let note_dir = html_path.parent().unwrap_or(Path::new("."));
+    let shared_dir_name = shared
+        .dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let html = fs::read_to_string(html_path)?;
+    let mut document = Html::parse_document(&html);
+
+    // Only local files inside a per-note attachment folder are moved
+    let images: Vec<_> = document
+        .select(&img_selector)
+        .filter_map(|element| {
+            let src = element.value().attr("src")?;
+            let (folder, _) = src.split_once('/')?;
+            if src.contains(':') || !folder.ends_with("-attachments") {
+                return None;
+            }
+            Some((element.id(), note_dir.join(src)))
+        })
+        .collect();
+
+    let mut modified = false;
+    for (node_id, source) in images {
+        let Some(name) = shared.take(&source)? else {
+            continue;
+        };
+
+        let src = format!("{}{shared_dir_name}/{name}", "../".repeat(depth));
+        if let Some(mut node) = document.tree.get_mut(node_id)
+            && let Node::Element(element) = node.value()
+        {
+            set_attribute(element, "src", &src);
+            modified = true;
+        }
+    }
+
+    if modified {
+        fs::write(html_path, document.html())?;
+    }
+
+    Ok(())
+}
+
+impl SharedFolder {
+    /// Moves `source` into the shared folder, or deletes it if an identical
+    /// file is already there, and returns its name in the shared folder.
+    ///
+    /// Returns `None` if `source` does not exist.
+    fn take(&mut self, source: &Path) -> Result<Option<String>> {
+        if let Some(name) = self.moved.get(source) {
+            return Ok(Some(name.clone()));
+        }
+        if !source.is_file() {
+            return Ok(None);
+        }
+
+        let data = fs::read(source)?;
+        let key = (fnv1a(&data), data.len());
+
+        for name in self.by_content.get(&key).into_iter().flatten() {
+            if fs::read(self.dir.join(name))? == data {
+                fs::remove_file(source)?;
+                self.moved.insert(source.to_path_buf(), name.clone());
+                return Ok(Some(name.clone()));
+            }
+        }
+
+        let file_name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "attachment".to_string());
+        let name = unique_name(file_name, &mut self.names);
+
+        fs::rename(source, self.dir.join(&name))?;
+        self.by_content.entry(key).or_default().push(name.clone());
+        self.moved.insert(source.to_path_buf(), name.clone());
+
+        Ok(Some(name))
+    }
+}
@@ -0,0 +1,134 @@
+//! Writing a CSV index of an export directory.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+
+use crate::Result;
+use crate::export_tree::note_title_from_stem;
+
+const HEADER: &str = "account,folder_path,title,file_path,created,modified,attachment_count";
+
+/// The index of the `file_path` column.
+const FILE_PATH: usize = 3;
+
+/// Writes a CSV index of every note in the export directory `dir` to `out`.
+///
+/// The CSV has one row per exported note with the columns `account`,
+/// `folder_path`, `title`, `file_path`, `created`, `modified`, and
+/// `attachment_count`:
+///
+/// - `account` is the first directory under `dir` and `folder_path` the rest,
+///   separated by `/`, matching the layout of [`Exporter::export_all`](crate::Exporter::export_all).
+///   For a single exported folder, `account` is that folder's name.
+/// - `file_path` is relative to `dir`.
+/// - `created` and `modified` are the RFC 3339 timestamps of the exported
+///   file, and are empty where the file system does not record them.
+/// - `attachment_count` is the number of files in the note's attachment folder.
+///
+/// Rows are sorted by file path.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use apple_notes_exporter_rs::generate_csv_index;
+///
+/// generate_csv_index(Path::new("./exports"), Path::new("./exports/notes.csv"))
+///     .expect("Failed to write CSV index");
+/// ```
+pub fn generate_csv_index(dir: &Path, out: &Path) -> Result<()> {
+    let mut rows = Vec::new();
+    collect_rows(dir, dir, &mut rows)?;
+    rows.sort_by(|a, b| a[FILE_PATH].cmp(&b[FILE_PATH]));
+
+    let mut csv = String::new();
+    let _ = writeln!(csv, "{HEADER}");
+    for row in rows {
+        let _ = writeln!(
+            csv,
+            "{}",
+            row.iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+    }
+
+    if let Some(parent) = out.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(out, csv)?;
+
+    Ok(())
+}
+
+/// Adds a row for each note under `current` to `rows`.
+fn collect_rows(root: &Path, current: &Path, rows: &mut Vec<[String; 7]>) -> Result<()> {
+    for entry in fs::read_dir(current)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            if !entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with("-attachments")
+            {
+                collect_rows(root, &path, rows)?;
+            }
+            continue;
+        }
+
+        if path.extension().is_none_or(|ext| ext != "html") {
+            continue;
+        }
+
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let mut folders = relative
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .map(|component| component.as_os_str().to_string_lossy().into_owned());
+        let account = folders.next().unwrap_or_default();
+        let folder_path = folders.collect::<Vec<_>>().join("/");
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("note");
+        let metadata = entry.metadata()?;
+        let attachments_dir = path.with_file_name(format!("{stem}-attachments"));
+        let attachment_count = match fs::read_dir(&attachments_dir) {
+            Ok(entries) => entries.count(),
+            Err(_) => 0,
+        };
+
+        rows.push([
+            account,
+            folder_path,
+            note_title_from_stem(stem).to_string(),
+            relative.to_string_lossy().replace('\\', "/"),
+            timestamp(metadata.created().ok()),
+            timestamp(metadata.modified().ok()),
+            attachment_count.to_string(),
+        ]);
+    }
+
+    Ok(())
+}
+
+fn timestamp(time: Option<SystemTime>) -> String {
+    time.map(|time| DateTime::<Utc>::from(time).to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote, or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,118 @@
+//! Setup diagnostics for the `doctor` command.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::Exporter;
+
+/// Locations where the Notes app is installed.
+const NOTES_APP_PATHS: &[&str] = &["/System/Applications/Notes.app", "/Applications/Notes.app"];
+
+/// The outcome of one setup check run by [`Exporter::diagnose`].
+#[derive(Debug, Clone)]
+pub struct DiagnosticCheck {
+    /// What was checked (e.g. `"osascript"`).
+    pub name: &'static str,
+    /// Whether the check passed.
+    pub passed: bool,
+    /// Details such as the path that was found or the error encountered.
+    pub detail: String,
+}
+
+impl DiagnosticCheck {
+    fn new(name: &'static str, passed: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            passed,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl Exporter {
+    /// Checks that everything needed for exporting is set up.
+    ///
+    /// This verifies that the platform is macOS, that `osascript` is on the
+    /// `PATH`, that the Notes app is installed, and that Automation permission
+    /// for Notes has been granted (by running a trivial read against Notes),
+    /// and reports the version of the Notes app.
+    /// Checks that depend on an earlier failed check are reported as failed
+    /// without being run.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// for check in Exporter::new().diagnose() {
+    ///     let status = if check.passed { "PASS" } else { "FAIL" };
+    ///     println!("[{status}] {}: {}", check.name, check.detail);
+    /// }
+    /// ```
+    pub fn diagnose(&self) -> Vec<DiagnosticCheck> {
+        let mut checks = Vec::new();
+
+        let is_macos = cfg!(target_os = "macos");
+        checks.push(DiagnosticCheck::new("Platform", is_macos, env::consts::OS));
+
+        let osascript = find_in_path("osascript");
+        checks.push(match &osascript {
+            Some(path) => DiagnosticCheck::new("osascript", true, path.display().to_string()),
+            None => DiagnosticCheck::new("osascript", false, "not found on PATH"),
+        });
+
+        let notes_app = NOTES_APP_PATHS
+            .iter()
+            .map(Path::new)
+            .find(|path| path.exists());
+        checks.push(match notes_app {
+            Some(path) => DiagnosticCheck::new("Notes app", true, path.display().to_string()),
+            None => DiagnosticCheck::new("Notes app", false, "Notes.app not found"),
+        });
+
+        let permission = if !is_macos || osascript.is_none() || notes_app.is_none() {
+            DiagnosticCheck::new("Automation permission", false, "skipped")
+        } else {
+            match self.osascript_eval(r#"tell application "Notes" to count of accounts"#) {
+                Ok(output) if output.status.success() => DiagnosticCheck::new(
+                    "Automation permission",
+                    true,
+                    format!(
+                        "granted ({} accounts)",
+                        String::from_utf8_lossy(&output.stdout).trim()
+                    ),
+                ),
+                Ok(output) => DiagnosticCheck::new(
+                    "Automation permission",
+                    false,
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                ),
+                Err(error) => {
+                    DiagnosticCheck::new("Automation permission", false, error.to_string())
+                }
+            }
+        };
+
+        // Different Notes versions produce different HTML, so report it
+        let version = if permission.passed {
+            match self.notes_app_version() {
+                Ok(version) => DiagnosticCheck::new("Notes version", true, version),
+                Err(error) => DiagnosticCheck::new("Notes version", false, error.to_string()),
+            }
+        } else {
+            DiagnosticCheck::new("Notes version", false, "skipped")
+        };
+        checks.push(permission);
+        checks.push(version);
+
+        checks
+    }
+}
+
+/// Returns the first executable called `name` on the `PATH`.
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
@@ -0,0 +1,85 @@
+//! Downloading remote images (requires the `download` feature).
+
+use std::fs;
+use std::process::{Command, Stdio};
+
+/// How long to wait for a single image, in seconds.
+const TIMEOUT_SECS: &str = "60";
+
+/// Returns whether `src` is an `http` or `https` URL.
+pub(crate) fn is_remote(src: &str) -> bool {
+    let src = src.trim();
+    ["http://", "https://"].iter().any(|scheme| {
+        src.get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// Downloads the image at `url` with `curl`, returning its data and MIME type.
+///
+/// Responses larger than `max_bytes` and responses that are not images fail.
+pub(crate) fn fetch_image(
+    url: &str,
+    max_bytes: Option<usize>,
+) -> Result<(Vec<u8>, String), String> {
+    let file = tempfile::NamedTempFile::new().map_err(|error| error.to_string())?;
+
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--proto", "=http,https", "--max-time", TIMEOUT_SECS])
+        .args(["--write-out", "%{content_type}", "--output"])
+        .arg(file.path());
+    if let Some(max_bytes) = max_bytes {
+        command.arg("--max-filesize").arg(max_bytes.to_string());
+    }
+
+    let output = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|error| format!("could not run curl: {error}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.trim().trim_start_matches("curl: ").to_string());
+    }
+
+    let data = fs::read(file.path()).map_err(|error| error.to_string())?;
+    if let Some(max_bytes) = max_bytes
+        && data.len() > max_bytes
+    {
+        return Err(format!("larger than {max_bytes} bytes"));
+    }
+
+    let content_type = String::from_utf8_lossy(&output.stdout);
+    let mime_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    // Servers often send images as application/octet-stream
+    if mime_type.starts_with("image/") {
+        return Ok((data, mime_type));
+    }
+    match sniff_image_type(&data) {
+        Some(sniffed) => Ok((data, sniffed.to_string())),
+        None => Err(format!("not an image ({mime_type})")),
+    }
+}
+
+/// Recognizes common image formats by their leading bytes.
+fn sniff_image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,103 @@
+//! Marking exported notes as UTF-8 for tools that don't assume it, and
+//! normalizing their line endings.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+use crate::Result;
+
+/// The UTF-8 byte order mark.
+pub(crate) const BOM: char = '\u{feff}';
+
+/// Gives every note under `dir` a `<meta charset="utf-8">` if it has no
+/// charset declaration, and starts it with a [`BOM`].
+pub(crate) fn mark_directory(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            mark_directory(&path)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read_to_string(&path)?;
+            fs::write(&path, mark(&html))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn mark(html: &str) -> String {
+    let html = html.strip_prefix(BOM).unwrap_or(html);
+    let lowercase = html.to_ascii_lowercase();
+    let has_charset = lowercase.match_indices("<meta").any(|(position, _)| {
+        let tag = &lowercase[position..];
+        tag[..tag.find('>').unwrap_or(tag.len())].contains("charset")
+    });
+
+    let mut marked = String::with_capacity(html.len() + 32);
+    marked.push(BOM);
+    if has_charset {
+        marked.push_str(html);
+        return marked;
+    }
+
+    let meta = r#"<meta charset="utf-8">"#;
+    let head = lowercase
+        .match_indices("<head")
+        .find(|&(position, _)| {
+            lowercase[position + "<head".len()..].starts_with(['>', ' ', '\t', '\n', '\r'])
+        })
+        .and_then(|(position, _)| {
+            lowercase[position..]
+                .find('>')
+                .map(|end| position + end + 1)
+        });
+    match (head, lowercase.find("<body")) {
+        (Some(position), _) => {
+            marked.push_str(&html[..position]);
+            marked.push_str(meta);
+            marked.push_str(&html[position..]);
+        }
+        (None, Some(position)) => {
+            marked.push_str(&html[..position]);
+            marked.push_str(&format!("<head>{meta}</head>"));
+            marked.push_str(&html[position..]);
+        }
+        (None, None) => {
+            marked.push_str(meta);
+            marked.push('\n');
+            marked.push_str(html);
+        }
+    }
+    marked
+}
+
+/// Rewrites every note under `dir` that has CRLF or CR line endings with LF
+/// ones instead, leaving the others untouched.
+pub(crate) fn normalize_directory_line_endings(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            normalize_directory_line_endings(&path)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read_to_string(&path)?;
+            if let Cow::Owned(normalized) = normalize_line_endings(&html) {
+                fs::write(&path, normalized)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns `text` with CRLF and lone CR line endings replaced by LF.
+pub(crate) fn normalize_line_endings(text: &str) -> Cow<'_, str> {
+    if !text.contains('\r') {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+}
@@ -0,0 +1,395 @@
+//! Assembling an exported folder into an EPUB book.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use scraper::{ElementRef, Html, Node, Selector};
+use zip::CompressionMethod;
+use zip::write::SimpleFileOptions;
+
+use crate::Result;
+use crate::export_tree::{TreeFolder, read_export_tree};
+use crate::single_html::escape_html;
+
+/// Elements that must be written as self-closing tags in XHTML.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// A note converted to an XHTML chapter.
+struct Chapter {
+    xhtml: String,
+}
+
+/// An image file referenced by one or more chapters.
+struct Image {
+    source: PathBuf,
+    href: String,
+    media_type: &'static str,
+}
+
+/// A table of contents entry pointing at the chapter with index `chapter`.
+struct NavEntry {
+    title: String,
+    chapter: usize,
+    children: Vec<NavEntry>,
+}
+
+#[derive(Default)]
+struct Book {
+    chapters: Vec<Chapter>,
+    images: Vec<Image>,
+    image_hrefs: HashMap<PathBuf, String>,
+}
+
+/// Writes the export in `export_dir` (with attachments already extracted) as
+/// an EPUB book at `epub_path`.
+///
+/// Each note becomes a chapter in folder order, subfolders become nested
+/// navigation entries, and extracted images are embedded in the book.
+pub(crate) fn write_epub(export_dir: &Path, title: &str, epub_path: &Path) -> Result<()> {
+    let root = read_export_tree(export_dir, title)?;
+
+    let mut book = Book::default();
+    let nav = book.add_folder(&root)?;
+
+    let now = chrono::Utc::now();
+    let modified = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let identifier = format!("urn:apple-notes-exporter:{}", now.format("%Y%m%d%H%M%S%f"));
+
+    if let Some(parent) = epub_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut zip = zip::ZipWriter::new(File::create(epub_path)?);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype entry must come first and be stored uncompressed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER_XML.as_bytes())?;
+
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(
+        book.render_opf(&root.name, &identifier, &modified)
+            .as_bytes(),
+    )?;
+
+    zip.start_file("OEBPS/toc.ncx", deflated)?;
+    zip.write_all(book.render_ncx(&root.name, &identifier, &nav).as_bytes())?;
+
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(book.render_nav(&root.name, &nav).as_bytes())?;
+
+    for (index, chapter) in book.chapters.iter().enumerate() {
+        zip.start_file(format!("OEBPS/{}", chapter_href(index)), deflated)?;
+        zip.write_all(chapter.xhtml.as_bytes())?;
+    }
+
+    for image in &book.images {
+        zip.start_file(format!("OEBPS/{}", image.href), stored)?;
+        zip.write_all(&fs::read(&image.source)?)?;
+    }
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn chapter_href(index: usize) -> String {
+    format!("text/note-{:03}.xhtml", index + 1)
+}
+
+/// Returns the EPUB media type for an extracted attachment's file extension.
+fn media_type_for_extension(extension: &str) -> Option<&'static str> {
+    match extension {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "svg" => Some("image/svg+xml"),
+        "bmp" => Some("image/bmp"),
+        "tiff" => Some("image/tiff"),
+        _ => None,
+    }
+}
+
+impl Book {
+    /// Adds the notes of `folder` and its subfolders as chapters, returning
+    /// their navigation entries. Folders without any notes are left out.
+    fn add_folder(&mut self, folder: &TreeFolder) -> Result<Vec<NavEntry>> {
+        let body_selector = Selector::parse("body").unwrap();
+        let mut entries = Vec::new();
+
+        for note in &folder.notes {
+            let html = fs::read_to_string(&note.path)?;
+            let document = Html::parse_document(&html);
+            let note_dir = note.path.parent().unwrap_or(Path::new("."));
+
+            let mut body = String::new();
+            match document.select(&body_selector).next() {
+                Some(element) => self.write_children(element, note_dir, &mut body),
+                None => self.write_children(document.root_element(), note_dir, &mut body),
+            }
+
+            let title = escape_html(&note.title);
+            let xhtml = format!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+                 <!DOCTYPE html>\n\
+                 <html xmlns=\"http://www.w3.org/1999/xhtml\">\n\
+                 <head>\n\
+                 <meta charset=\"utf-8\"/>\n\
+                 <title>{title}</title>\n\
+                 </head>\n\
+                 <body>\n{body}\n</body>\n\
+                 </html>\n"
+            );
+
+            entries.push(NavEntry {
+                title: note.title.clone(),
+                chapter: self.chapters.len(),
+                children: Vec::new(),
+            });
+            self.chapters.push(Chapter { xhtml });
+        }
+
+        for child in &folder.children {
+            let children = self.add_folder(child)?;
+            if let Some(first) = children.first() {
+                entries.push(NavEntry {
+                    title: child.name.clone(),
+                    chapter: first.chapter,
+                    children,
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Serializes the children of `element` as well-formed XHTML, embedding
+    /// any local images relative to `note_dir`.
+    fn write_children(&mut self, element: ElementRef, note_dir: &Path, out: &mut String) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(&escape_html(text)),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.write_element(child, note_dir, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write_element(&mut self, element: ElementRef, note_dir: &Path, out: &mut String) {
+        let name = element.value().name();
+        let _ = write!(out, "<{name}");
+
+        for (attr, value) in element.value().attrs() {
+            // XML is stricter than HTML about attribute names
+            if !attr
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'))
+            {
+                continue;
+            }
+
+            let value = if name == "img" && attr == "src" {
+                self.embed_image(value, note_dir)
+                    .map_or_else(|| value.to_string(), |href| format!("../{href}"))
+            } else {
+                value.to_string()
+            };
+            let _ = write!(out, " {attr}=\"{}\"", escape_html(&value));
+        }
+
+        if VOID_ELEMENTS.contains(&name) {
+            out.push_str("/>");
+            return;
+        }
+
+        out.push('>');
+        self.write_children(element, note_dir, out);
+        let _ = write!(out, "</{name}>");
+    }
+
+    /// Adds the local image at `src` to the book, returning its href.
+    fn embed_image(&mut self, src: &str, note_dir: &Path) -> Option<String> {
+        if src.contains(':') {
+            return None;
+        }
+
+        let source = note_dir.join(src);
+        if let Some(href) = self.image_hrefs.get(&source) {
+            return Some(href.clone());
+        }
+
+        let extension = source.extension()?.to_str()?.to_ascii_lowercase();
+        let media_type = media_type_for_extension(&extension)?;
+        if !source.is_file() {
+            return None;
+        }
+
+        let href = format!("images/image-{:03}.{extension}", self.images.len() + 1);
+        self.image_hrefs.insert(source.clone(), href.clone());
+        self.images.push(Image {
+            source,
+            href: href.clone(),
+            media_type,
+        });
+
+        Some(href)
+    }
+
+    fn render_opf(&self, title: &str, identifier: &str, modified: &str) -> String {
+        let mut manifest = String::new();
+        let mut spine = String::new();
+
+        for index in 0..self.chapters.len() {
+            let _ = writeln!(
+                manifest,
+                "    <item id=\"note-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+                index + 1,
+                chapter_href(index)
+            );
+            let _ = writeln!(spine, "    <itemref idref=\"note-{}\"/>", index + 1);
+        }
+
+        for (index, image) in self.images.iter().enumerate() {
+            let _ = writeln!(
+                manifest,
+                "    <item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>",
+                index + 1,
+                image.href,
+                image.media_type
+            );
+        }
+
+        let title = escape_html(title);
+        let identifier = escape_html(identifier);
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+             \x20 <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+             \x20   <dc:identifier id=\"book-id\">{identifier}</dc:identifier>\n\
+             \x20   <dc:title>{title}</dc:title>\n\
+             \x20   <dc:language>en</dc:language>\n\
+             \x20   <meta property=\"dcterms:modified\">{modified}</meta>\n\
+             \x20 </metadata>\n\
+             \x20 <manifest>\n\
+             \x20   <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+             \x20   <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n\
+             {manifest}\
+             \x20 </manifest>\n\
+             \x20 <spine toc=\"ncx\">\n\
+             {spine}\
+             \x20 </spine>\n\
+             </package>\n"
+        )
+    }
+
+    fn render_ncx(&self, title: &str, identifier: &str, nav: &[NavEntry]) -> String {
+        fn depth(entries: &[NavEntry]) -> usize {
+            entries
+                .iter()
+                .map(|entry| 1 + depth(&entry.children))
+                .max()
+                .unwrap_or(0)
+        }
+
+        fn nav_points(entries: &[NavEntry], next_id: &mut usize, out: &mut String) {
+            for entry in entries {
+                *next_id += 1;
+                let _ = writeln!(
+                    out,
+                    "<navPoint id=\"nav-{next_id}\" playOrder=\"{}\">\n\
+                     <navLabel><text>{}</text></navLabel>\n\
+                     <content src=\"{}\"/>",
+                    entry.chapter + 1,
+                    escape_html(&entry.title),
+                    chapter_href(entry.chapter)
+                );
+                nav_points(&entry.children, next_id, out);
+                let _ = writeln!(out, "</navPoint>");
+            }
+        }
+
+        let mut points = String::new();
+        nav_points(nav, &mut 0, &mut points);
+
+        let title = escape_html(title);
+        let identifier = escape_html(identifier);
+        let depth = depth(nav).max(1);
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\">\n\
+             <head>\n\
+             <meta name=\"dtb:uid\" content=\"{identifier}\"/>\n\
+             <meta name=\"dtb:depth\" content=\"{depth}\"/>\n\
+             <meta name=\"dtb:totalPageCount\" content=\"0\"/>\n\
+             <meta name=\"dtb:maxPageNumber\" content=\"0\"/>\n\
+             </head>\n\
+             <docTitle><text>{title}</text></docTitle>\n\
+             <navMap>\n{points}</navMap>\n\
+             </ncx>\n"
+        )
+    }
+
+    fn render_nav(&self, title: &str, nav: &[NavEntry]) -> String {
+        fn list(entries: &[NavEntry], out: &mut String) {
+            let _ = writeln!(out, "<ol>");
+            for entry in entries {
+                let _ = write!(
+                    out,
+                    "<li><a href=\"{}\">{}</a>",
+                    chapter_href(entry.chapter),
+                    escape_html(&entry.title)
+                );
+                if !entry.children.is_empty() {
+                    out.push('\n');
+                    list(&entry.children, out);
+                }
+                let _ = writeln!(out, "</li>");
+            }
+            let _ = writeln!(out, "</ol>");
+        }
+
+        let mut items = String::new();
+        list(nav, &mut items);
+
+        let title = escape_html(title);
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <!DOCTYPE html>\n\
+             <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\n\
+             <head>\n\
+             <meta charset=\"utf-8\"/>\n\
+             <title>{title}</title>\n\
+             </head>\n\
+             <body>\n\
+             <nav epub:type=\"toc\" id=\"toc\">\n\
+             <h1>{title}</h1>\n\
+             {items}\
+             </nav>\n\
+             </body>\n\
+             </html>\n"
+        )
+    }
+}
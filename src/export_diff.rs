@@ -0,0 +1,110 @@
+//! Comparing two exports of the same notes.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Result;
+use crate::naming::fnv1a;
+
+/// The notes that differ between two exports; see [`diff_exports`].
+///
+/// Paths are relative to the export directories and sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportDiff {
+    /// Notes only in the new export.
+    pub added: Vec<PathBuf>,
+    /// Notes only in the old export.
+    pub removed: Vec<PathBuf>,
+    /// Notes in both exports whose HTML differs.
+    pub changed: Vec<PathBuf>,
+}
+
+impl ExportDiff {
+    /// Returns whether the two exports have the same notes with the same HTML.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares the notes of two export directories, such as a previous backup
+/// and a fresh export of the same folder.
+///
+/// Notes are matched by their path relative to each directory and compared
+/// by a hash of their HTML, so a re-export that changed nothing comes out
+/// empty even though every file was rewritten. Only `.html` files are
+/// compared; attachments and other files are ignored.
+///
+/// # Example
+///
+/// ```
+/// use std::fs;
+/// use std::path::PathBuf;
+///
+/// use apple_notes_exporter_rs::diff_exports;
+///
+/// let old = tempfile::tempdir().unwrap();
+/// let new = tempfile::tempdir().unwrap();
+/// for dir in [&old, &new] {
+///     fs::create_dir(dir.path().join("Recipes")).unwrap();
+///     fs::write(dir.path().join("Recipes/Pancakes.html"), "<p>Flour</p>").unwrap();
+/// }
+/// fs::write(old.path().join("Recipes/Waffles.html"), "<p>Eggs</p>").unwrap();
+/// fs::write(old.path().join("Recipes/Soup.html"), "<p>Leeks</p>").unwrap();
+/// fs::write(new.path().join("Recipes/Soup.html"), "<p>Leeks, potatoes</p>").unwrap();
+/// fs::write(new.path().join("Recipes/Brownies.html"), "<p>Cocoa</p>").unwrap();
+///
+/// let diff = diff_exports(old.path(), new.path()).unwrap();
+/// assert_eq!(diff.added, [PathBuf::from("Recipes/Brownies.html")]);
+/// assert_eq!(diff.removed, [PathBuf::from("Recipes/Waffles.html")]);
+/// assert_eq!(diff.changed, [PathBuf::from("Recipes/Soup.html")]);
+///
+/// assert!(diff_exports(old.path(), old.path()).unwrap().is_empty());
+/// ```
+pub fn diff_exports(old: &Path, new: &Path) -> Result<ExportDiff> {
+    let mut old_notes = HashMap::new();
+    hash_notes(old, Path::new(""), &mut old_notes)?;
+    let mut new_notes = HashMap::new();
+    hash_notes(new, Path::new(""), &mut new_notes)?;
+
+    let mut diff = ExportDiff::default();
+    for (path, hash) in &new_notes {
+        match old_notes.get(path) {
+            None => diff.added.push(path.clone()),
+            Some(old_hash) if old_hash != hash => diff.changed.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old_notes
+        .into_keys()
+        .filter(|path| !new_notes.contains_key(path))
+        .collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort();
+    Ok(diff)
+}
+
+/// Records the content hash and length of every HTML file under `dir`,
+/// keyed by its path relative to the export root.
+fn hash_notes(
+    dir: &Path,
+    relative: &Path,
+    notes: &mut HashMap<PathBuf, (u64, usize)>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            hash_notes(&path, &relative, notes)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read(&path)?;
+            notes.insert(relative, (fnv1a(&html), html.len()));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Reading an export directory back as a tree of folders and notes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Result;
+
+/// A folder read back from an export directory.
+pub(crate) struct TreeFolder {
+    pub(crate) name: String,
+    pub(crate) notes: Vec<TreeNote>,
+    pub(crate) children: Vec<TreeFolder>,
+}
+
+/// A note read back from an export directory.
+pub(crate) struct TreeNote {
+    pub(crate) title: String,
+    pub(crate) path: PathBuf,
+}
+
+/// Returns the note title for an exported file stem like `Title -- abc123`.
+pub(crate) fn note_title_from_stem(stem: &str) -> &str {
+    stem.rsplit_once(" -- ").map_or(stem, |(title, _)| title)
+}
+
+/// Reads the export in `export_dir`, sorted by name, as a folder called `name`.
+///
+/// The script exports into a directory named after the folder, so if that is
+/// the only thing in `export_dir` it becomes the root instead.
+pub(crate) fn read_export_tree(export_dir: &Path, name: &str) -> Result<TreeFolder> {
+    let mut root = read_folder(export_dir, name.to_string())?;
+
+    if root.notes.is_empty() && root.children.len() == 1 {
+        root = root.children.remove(0);
+    }
+
+    Ok(root)
+}
+
+fn read_folder(dir: &Path, name: String) -> Result<TreeFolder> {
+    let mut entries = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut folder = TreeFolder {
+        name,
+        notes: Vec::new(),
+        children: Vec::new(),
+    };
+
+    for entry in entries {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+
+        if path.is_dir() {
+            if !file_name.ends_with("-attachments") {
+                folder.children.push(read_folder(&path, file_name)?);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("note");
+            folder.notes.push(TreeNote {
+                title: note_title_from_stem(stem).to_string(),
+                path,
+            });
+        }
+    }
+
+    Ok(folder)
+}
@@ -0,0 +1,138 @@
+//! Extracting attachments from many notes with reused buffers.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::{
+    ExtractOptions, ExtractionResult, Result, extract_attachments_in_place,
+    extract_attachments_recursive,
+};
+
+/// Buffers reused from one note to the next, so extracting thousands of
+/// notes doesn't allocate them afresh for each one.
+#[derive(Debug, Default)]
+pub(crate) struct ExtractBuffers {
+    /// The HTML of the note being extracted.
+    pub(crate) html: String,
+    /// Buffers for the note's images.
+    pub(crate) decode: DecodeBuffers,
+    /// What has been extracted so far.
+    pub(crate) stats: ExtractionStats,
+}
+
+/// Buffers for decoding a note's images, reused from one image to the next.
+#[derive(Debug, Default)]
+pub(crate) struct DecodeBuffers {
+    /// A data URL's payload with percent escapes and whitespace removed.
+    pub(crate) payload: Vec<u8>,
+    /// The decoded image being extracted.
+    pub(crate) decoded: Vec<u8>,
+}
+
+/// Totals for the notes an [`AttachmentExtractor`] has processed, for
+/// measuring extraction throughput.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExtractionStats {
+    /// The number of HTML files processed.
+    pub files: usize,
+    /// The total size of the HTML files read, in bytes.
+    pub html_bytes: u64,
+    /// The number of attachments written.
+    pub attachments: usize,
+    /// The total size of the attachments written, in bytes.
+    pub attachment_bytes: u64,
+    /// The time spent processing the files, including reading and writing
+    /// them, but not walking directories.
+    pub elapsed: Duration,
+}
+
+impl ExtractionStats {
+    /// Returns how many bytes of HTML were processed per second, or `None`
+    /// if no time has been measured yet.
+    pub fn html_bytes_per_second(&self) -> Option<f64> {
+        let seconds = self.elapsed.as_secs_f64();
+        (seconds > 0.0).then(|| self.html_bytes as f64 / seconds)
+    }
+}
+
+/// Extracts attachments like [`extract_attachments_from_html_with_options`](crate::extract_attachments_from_html_with_options),
+/// reusing its buffers from one note to the next, and keeps
+/// [statistics](ExtractionStats) of what it processed.
+///
+/// Create one per thread and use it for every note, e.g. to extract large
+/// exports or to benchmark extraction. The `extract_attachments_from_*`
+/// functions allocate everything afresh for each call.
+///
+/// # Example
+///
+/// ```
+/// use std::fs;
+///
+/// use apple_notes_exporter_rs::{AttachmentExtractor, ExtractOptions};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// for name in ["One -- a.html", "Two -- b.html"] {
+///     fs::write(
+///         dir.path().join(name),
+///         r#"<img src="data:image/png;base64,iVBORw0KGgo=">"#,
+///     )
+///     .unwrap();
+/// }
+///
+/// let mut extractor = AttachmentExtractor::new(ExtractOptions::default());
+/// let results = extractor.extract_directory(dir.path()).unwrap();
+/// assert_eq!(results.len(), 2);
+///
+/// let stats = extractor.stats();
+/// assert_eq!(stats.files, 2);
+/// assert_eq!(stats.attachments, 2);
+/// assert_eq!(stats.attachment_bytes, 16);
+/// println!("{:.0} bytes/s", stats.html_bytes_per_second().unwrap_or(0.0));
+/// ```
+#[derive(Debug, Default)]
+pub struct AttachmentExtractor {
+    options: ExtractOptions,
+    buffers: ExtractBuffers,
+}
+
+impl AttachmentExtractor {
+    /// Creates an extractor using `options` for every note.
+    pub fn new(options: ExtractOptions) -> Self {
+        Self {
+            options,
+            buffers: ExtractBuffers::default(),
+        }
+    }
+
+    /// Extracts the images of one HTML file into its attachments folder and
+    /// points the file at them, like
+    /// [`extract_attachments_from_html_with_options`](crate::extract_attachments_from_html_with_options).
+    pub fn extract_html<P: AsRef<Path>>(&mut self, html_path: P) -> Result<ExtractionResult> {
+        extract_attachments_in_place(html_path.as_ref(), &self.options, &mut self.buffers)
+    }
+
+    /// Extracts the images of every HTML file under `dir`, like
+    /// [`extract_attachments_from_directory_with_options`](crate::extract_attachments_from_directory_with_options).
+    pub fn extract_directory<P: AsRef<Path>>(&mut self, dir: P) -> Result<Vec<ExtractionResult>> {
+        let mut results = Vec::new();
+        extract_attachments_recursive(
+            dir.as_ref(),
+            &self.options,
+            &mut self.buffers,
+            &mut results,
+        )?;
+        Ok(results)
+    }
+
+    /// Returns the totals for every note processed since the extractor was
+    /// created or [`reset_stats`](Self::reset_stats) was last called.
+    pub fn stats(&self) -> &ExtractionStats {
+        &self.buffers.stats
+    }
+
+    /// Sets the [statistics](Self::stats) back to zero, e.g. between
+    /// benchmark runs, keeping the buffers.
+    pub fn reset_stats(&mut self) {
+        self.buffers.stats = ExtractionStats::default();
+    }
+}
@@ -0,0 +1,177 @@
+//! Naming exported notes with a filename template.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::format::{Item, StrftimeItems};
+
+use crate::markdown::NoteDates;
+use crate::timestamps::parse_date;
+use crate::{ExportError, Result};
+
+/// The filename template matching how the script names notes.
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{title} -- {id}";
+
+/// The date format for `{date}` and `{created}`, e.g. `2025-03-01`.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Filled in for `{date}` and `{created}` when a note's date can't be read.
+const UNDATED: &str = "undated";
+
+/// A piece of a parsed filename template.
+enum Segment<'a> {
+    Literal(&'a str),
+    Title,
+    Id,
+    Date,
+    Created,
+}
+
+/// Parses `template` into its segments, failing on unknown tokens,
+/// unbalanced braces, and path separators.
+fn parse(template: &str) -> Result<Vec<Segment<'_>>> {
+    let invalid =
+        |reason: String| ExportError::InvalidFilenameTemplate(template.to_string(), reason);
+
+    if template.contains(['/', '\\']) {
+        return Err(invalid(
+            "filenames can't contain path separators".to_string(),
+        ));
+    }
+
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(invalid("unmatched \"}\"".to_string()));
+        }
+        if start > 0 {
+            segments.push(Segment::Literal(&rest[..start]));
+        }
+
+        let Some(len) = rest[start..].find('}') else {
+            return Err(invalid("unclosed \"{\"".to_string()));
+        };
+        segments.push(match &rest[start + 1..start + len] {
+            "title" => Segment::Title,
+            "id" => Segment::Id,
+            "date" => Segment::Date,
+            "created" => Segment::Created,
+            token => return Err(invalid(format!("unknown token {{{token}}}"))),
+        });
+        rest = &rest[start + len + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+
+    Ok(segments)
+}
+
+/// Checks `template`, so a bad one fails before the script runs.
+pub(crate) fn validate(template: &str) -> Result<()> {
+    parse(template).map(|_| ())
+}
+
+/// Checks `date_format`, so a bad one fails before the script runs: it must
+/// be a valid format string whose dates can't contain path separators.
+pub(crate) fn validate_date_format(date_format: &str) -> Result<()> {
+    let invalid = || Err(ExportError::InvalidDatePattern(date_format.to_string()));
+    if StrftimeItems::new(date_format).any(|item| matches!(item, Item::Error)) {
+        return invalid();
+    }
+
+    // Date-dependent separators (such as from `%D`) show up in any date
+    let sample = DateTime::UNIX_EPOCH.format(date_format).to_string();
+    if sample.is_empty() || sample.contains(['/', '\\']) {
+        return invalid();
+    }
+    Ok(())
+}
+
+/// Returns whether `template` uses note dates, which take another script run.
+pub(crate) fn needs_dates(template: &str) -> bool {
+    parse(template).is_ok_and(|segments| {
+        segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Date | Segment::Created))
+    })
+}
+
+/// Renames every note under `dir` from the script's `<title> -- <id>.html`
+/// to `template`, with dates formatted with `date_format`, keeping an index
+/// prefix (`007 - `) if `index_prefix` is set.
+///
+/// A note whose new name is already taken keeps its original name.
+pub(crate) fn rename_notes(
+    dir: &Path,
+    template: &str,
+    date_format: &str,
+    dates: &HashMap<String, NoteDates>,
+    index_prefix: bool,
+) -> Result<()> {
+    let segments = parse(template)?;
+    rename_notes_in(dir, &segments, date_format, dates, index_prefix)
+}
+
+fn rename_notes_in(
+    dir: &Path,
+    segments: &[Segment<'_>],
+    date_format: &str,
+    dates: &HashMap<String, NoteDates>,
+    index_prefix: bool,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            rename_notes_in(&path, segments, date_format, dates, index_prefix)?;
+            continue;
+        }
+        if path.extension().is_none_or(|ext| ext != "html") {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        let (prefix, stem) = match stem.split_once(" - ") {
+            Some((index, rest)) if index_prefix => (Some(index), rest),
+            _ => (None, stem),
+        };
+        let (title, id) = stem.rsplit_once(" -- ").unwrap_or((stem, ""));
+        let note_dates = dates.get(id);
+        let format_date = |date: Option<&str>| {
+            date.and_then(parse_date)
+                .map_or(UNDATED.to_string(), |date| {
+                    date.format(date_format).to_string()
+                })
+        };
+
+        let mut name = prefix
+            .map(|index| format!("{index} - "))
+            .unwrap_or_default();
+        for segment in segments {
+            match segment {
+                Segment::Literal(text) => name.push_str(text),
+                Segment::Title => name.push_str(title),
+                Segment::Id => name.push_str(id),
+                Segment::Date => name.push_str(&format_date(note_dates.map(NoteDates::updated))),
+                Segment::Created => {
+                    name.push_str(&format_date(note_dates.map(NoteDates::created)));
+                }
+            }
+        }
+        name.push_str(".html");
+
+        let target = dir.join(&name);
+        if target != path && !target.exists() {
+            fs::rename(&path, target)?;
+        }
+    }
+
+    Ok(())
+}
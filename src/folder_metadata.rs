@@ -0,0 +1,75 @@
+//! Describing exported folders in `folder.json` files.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::Result;
+use crate::report::{json_array, json_object, json_string};
+
+/// The name of the file describing the Notes folder a directory was exported
+/// from; see [`ExportOptions::folder_metadata`](crate::ExportOptions::folder_metadata).
+pub const FOLDER_METADATA_FILE: &str = "folder.json";
+
+/// Parses the output of the `folder-metadata` script command into folder ids,
+/// keyed by the folder's path starting with the account name.
+pub(crate) fn parse_folder_ids(output: &str) -> HashMap<Vec<String>, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (id, path) = line.split_once('\t')?;
+            Some((
+                path.split('\t').map(str::to_string).collect(),
+                id.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Writes a [`FOLDER_METADATA_FILE`] into every directory under `dir`.
+///
+/// `parent` is the path of the Notes folder `dir` stands for, starting with
+/// the account name, and `ids` the folder ids from [`parse_folder_ids`].
+pub(crate) fn write_directory(
+    dir: &Path,
+    parent: &[String],
+    ids: &HashMap<Vec<String>, String>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let path = entry.path();
+        let mut folder = parent.to_vec();
+        folder.push(entry.file_name().to_string_lossy().into_owned());
+
+        let notes = fs::read_dir(&path)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "html"))
+            .count();
+        let fields = [
+            ("name", json_string(&folder[folder.len() - 1])),
+            ("account", json_string(&folder[0])),
+            (
+                "path",
+                json_array(folder[1..].iter().map(|name| json_string(name))),
+            ),
+            (
+                "id",
+                ids.get(&folder)
+                    .map_or("null".to_string(), |id| json_string(id)),
+            ),
+            ("notes", notes.to_string()),
+        ];
+        fs::write(
+            path.join(FOLDER_METADATA_FILE),
+            format!("{}\n", json_object(&fields, 0)),
+        )?;
+
+        write_directory(&path, &folder, ids)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Parsing `"AccountName:FolderName"` folder specs.
+
+use std::fmt;
+
+use crate::AccountSelector;
+
+/// The account used in a folder spec to select the on-device account.
+pub(crate) const LOCAL_ACCOUNT: &str = "@local";
+
+/// A folder to export, optionally restricted to one account.
+///
+/// Parse one from user input with [`parse_folder_spec`], and format one with
+/// [`Display`](fmt::Display) to get a spec that parses back to the same value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FolderSpec {
+    /// The account to search, or `None` to search every account.
+    pub account: Option<AccountSelector>,
+    /// The folder name.
+    pub folder: String,
+}
+
+impl FolderSpec {
+    /// Returns the `<folder>` argument passed to the script.
+    ///
+    /// The script splits it on the first colon, so the folder may contain
+    /// colons, and an empty account searches every account.
+    pub(crate) fn script_arg(&self) -> String {
+        match &self.account {
+            Some(AccountSelector::Named(account)) => format!("{account}:{}", self.folder),
+            Some(AccountSelector::LocalAccount) => format!("{LOCAL_ACCOUNT}:{}", self.folder),
+            None if self.folder.contains(':') => format!(":{}", self.folder),
+            None => self.folder.clone(),
+        }
+    }
+}
+
+impl fmt::Display for FolderSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.account {
+            Some(AccountSelector::Named(account)) => write!(f, "{}:", escape(account))?,
+            Some(AccountSelector::LocalAccount) => write!(f, "{LOCAL_ACCOUNT}:")?,
+            None => {}
+        }
+        f.write_str(&escape(&self.folder))
+    }
+}
+
+/// Parses a folder spec such as `"My Notes"`, `"iCloud:My Notes"`, or
+/// `"@local:My Notes"`.
+///
+/// The rules are:
+///
+/// - The spec is split on its first colon into an account and a folder.
+///   Later colons belong to the folder, so `"iCloud:Q1: Plans"` is the folder
+///   `"Q1: Plans"` in iCloud.
+/// - Without a colon, the whole spec is a folder name searched for in every
+///   account.
+/// - `\:` is a literal colon and `\\` a literal backslash, so a folder whose
+///   name contains a colon can be searched for in every account as
+///   `"Q1\: Plans"`. Any other backslash is kept as is.
+/// - The account `@local` is the on-device account, see
+///   [`AccountSelector::LocalAccount`]. An empty account searches every account.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::{parse_folder_spec, AccountSelector};
+///
+/// let spec = parse_folder_spec("iCloud:Q1: Plans");
+/// assert_eq!(spec.account, Some(AccountSelector::Named("iCloud".to_string())));
+/// assert_eq!(spec.folder, "Q1: Plans");
+///
+/// let spec = parse_folder_spec(r"Q1\: Plans");
+/// assert_eq!(spec.account, None);
+/// assert_eq!(spec.folder, "Q1: Plans");
+/// assert_eq!(spec.to_string(), r"Q1\: Plans");
+///
+/// assert_eq!(parse_folder_spec("@local:Work").account, Some(AccountSelector::LocalAccount));
+/// ```
+pub fn parse_folder_spec(spec: &str) -> FolderSpec {
+    let mut account = None;
+    let mut current = String::new();
+    let mut chars = spec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.clone().next() {
+                Some(escaped @ (':' | '\\')) => {
+                    current.push(escaped);
+                    chars.next();
+                }
+                _ => current.push('\\'),
+            },
+            ':' if account.is_none() => account = Some(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+
+    let account = account
+        .filter(|account| !account.is_empty())
+        .map(|account| {
+            if account == LOCAL_ACCOUNT {
+                AccountSelector::LocalAccount
+            } else {
+                AccountSelector::Named(account)
+            }
+        });
+
+    FolderSpec {
+        account,
+        folder: current,
+    }
+}
+
+/// Escapes backslashes and colons so the text parses back literally.
+fn escape(text: &str) -> String {
+    text.replace('\\', r"\\").replace(':', r"\:")
+}
@@ -0,0 +1,177 @@
+//! Committing exports to a git repository (requires the `git` feature).
+
+use std::path::Path;
+
+use chrono::{SecondsFormat, Utc};
+use git2::{Commit, Delta, DiffOptions, ErrorCode, Index, IndexAddOption, Repository};
+
+use crate::{ExportError, Result};
+
+/// A commit made by [`commit_export`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitCommit {
+    /// The commit's hash.
+    pub id: String,
+    /// The number of notes added since the previous commit.
+    pub added: usize,
+    /// The number of notes whose HTML changed.
+    pub modified: usize,
+    /// The number of notes removed.
+    pub removed: usize,
+}
+
+/// Commits the changes under `dir`, which must be inside a git working
+/// tree, for versioned backups of exported notes.
+///
+/// Every change under `dir` is staged and committed, and nothing outside it:
+/// changes already staged elsewhere in the repository are left staged. The
+/// commit message summarizes the notes added, modified, and removed, with
+/// `description` (such as the exported folder) and the time of the commit:
+///
+/// ```text
+/// Export Recipes: 2 notes added, 0 modified, 0 removed
+///
+/// Exported at 2025-01-02T09:00:00Z by apple-notes-exporter 4.0.0
+/// ```
+///
+/// Returns `None` without committing if nothing changed. Uses libgit2, so
+/// the `git` command isn't needed, but the repository must have an author
+/// configured (`user.name` and `user.email`); libgit2's error is returned in
+/// [`ExportError::GitFailed`] otherwise. [`ExportOptions::git_commit`](crate::ExportOptions::git_commit)
+/// calls this after an export; call it directly to commit after extracting
+/// attachments as well.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use apple_notes_exporter_rs::{Exporter, commit_export, extract_attachments_from_directory};
+///
+/// let backup = Path::new("./notes-backup");
+/// Exporter::new().export_folder("My Notes", backup).expect("Failed to export");
+/// extract_attachments_from_directory(backup).expect("Failed to extract attachments");
+///
+/// match commit_export(backup, "My Notes").expect("Failed to commit") {
+///     Some(commit) => println!("Committed {} ({} notes changed)", commit.id, commit.modified),
+///     None => println!("No changes"),
+/// }
+/// ```
+pub fn commit_export(dir: &Path, description: &str) -> Result<Option<GitCommit>> {
+    let repo = Repository::discover(dir).map_err(git_failed)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| ExportError::GitFailed("the repository has no working tree".to_string()))?
+        .canonicalize()?;
+    let prefix = dir
+        .canonicalize()?
+        .strip_prefix(&workdir)
+        .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default();
+    let pathspec: Vec<&str> = if prefix.is_empty() {
+        Vec::new()
+    } else {
+        vec![&prefix]
+    };
+
+    // Stage every change under `dir`, including deletions
+    let mut index = repo.index().map_err(git_failed)?;
+    index
+        .add_all(&pathspec, IndexAddOption::DEFAULT, None)
+        .map_err(git_failed)?;
+    index.update_all(&pathspec, None).map_err(git_failed)?;
+    index.write().map_err(git_failed)?;
+
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit().map_err(git_failed)?),
+        Err(error) if error.code() == ErrorCode::UnbornBranch => None,
+        Err(error) => return Err(git_failed(error)),
+    };
+    let parent_tree = parent
+        .as_ref()
+        .map(Commit::tree)
+        .transpose()
+        .map_err(git_failed)?;
+
+    let mut diff_options = DiffOptions::new();
+    for path in &pathspec {
+        diff_options.pathspec(path);
+    }
+    let diff = repo
+        .diff_tree_to_index(parent_tree.as_ref(), Some(&index), Some(&mut diff_options))
+        .map_err(git_failed)?;
+    if diff.deltas().len() == 0 {
+        return Ok(None);
+    }
+
+    let (mut added, mut modified, mut removed) = (0, 0, 0);
+    for delta in diff.deltas() {
+        let path = delta.new_file().path().or(delta.old_file().path());
+        if path.is_none_or(|path| path.extension().is_none_or(|ext| ext != "html")) {
+            continue;
+        }
+        match delta.status() {
+            Delta::Added => added += 1,
+            Delta::Deleted => removed += 1,
+            _ => modified += 1,
+        }
+    }
+
+    // Commit only the changes under `dir`, leaving anything else staged as is
+    let mut commit_index = Index::new().map_err(git_failed)?;
+    if let Some(tree) = &parent_tree {
+        commit_index.read_tree(tree).map_err(git_failed)?;
+    }
+    let under_dir = |path: &[u8]| {
+        prefix.is_empty()
+            || path
+                .strip_prefix(prefix.as_bytes())
+                .is_some_and(|rest| rest.starts_with(b"/"))
+    };
+    if prefix.is_empty() {
+        commit_index.clear().map_err(git_failed)?;
+    } else {
+        commit_index
+            .remove_dir(Path::new(&prefix), 0)
+            .map_err(git_failed)?;
+    }
+    for entry in index.iter().filter(|entry| under_dir(&entry.path)) {
+        commit_index.add(&entry).map_err(git_failed)?;
+    }
+    let tree = repo
+        .find_tree(commit_index.write_tree_to(&repo).map_err(git_failed)?)
+        .map_err(git_failed)?;
+
+    let subject = format!(
+        "Export {description}: {added} notes added, {modified} modified, {removed} removed"
+    );
+    let body = format!(
+        "Exported at {} by apple-notes-exporter {}",
+        Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        env!("CARGO_PKG_VERSION")
+    );
+    let signature = repo.signature().map_err(git_failed)?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    let id = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &format!("{subject}\n\n{body}\n"),
+            &tree,
+            &parents,
+        )
+        .map_err(git_failed)?;
+
+    Ok(Some(GitCommit {
+        id: id.to_string(),
+        added,
+        modified,
+        removed,
+    }))
+}
+
+/// Returns libgit2's `error` as [`ExportError::GitFailed`].
+fn git_failed(error: git2::Error) -> ExportError {
+    ExportError::GitFailed(error.message().to_string())
+}
@@ -0,0 +1,33 @@
+//! Conversion of HEIC/HEIF images to JPEG (requires the `heic` feature).
+
+use std::fs;
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Returns whether `mime_type` is a HEIC or HEIF image.
+pub(crate) fn is_heic(mime_type: &str) -> bool {
+    matches!(mime_type, "image/heic" | "image/heif")
+}
+
+/// Converts HEIC image data to JPEG using the macOS `sips` tool.
+pub(crate) fn convert_to_jpeg(data: &[u8]) -> io::Result<Vec<u8>> {
+    let dir = tempfile::tempdir()?;
+    let input = dir.path().join("image.heic");
+    let output = dir.path().join("image.jpg");
+    fs::write(&input, data)?;
+
+    let status = Command::new("sips")
+        .args(["-s", "format", "jpeg"])
+        .arg(&input)
+        .arg("--out")
+        .arg(&output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("sips exited with {status}")));
+    }
+
+    fs::read(&output)
+}
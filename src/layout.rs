@@ -0,0 +1,115 @@
+//! Arranging exported notes by date instead of by folder.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+
+use crate::markdown::NoteDates;
+use crate::timestamps::parse_date;
+use crate::{ExportError, Result};
+
+/// The directory for notes whose modification date can't be read.
+const UNDATED_DIR: &str = "Undated";
+
+/// How exported notes are arranged in the output directory.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// use apple_notes_exporter_rs::{ExportLayout, ExportOptions, Exporter};
+///
+/// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+/// let dir = tempfile::tempdir().unwrap();
+///
+/// let options = ExportOptions {
+///     layout: ExportLayout::ByDate {
+///         pattern: "%Y".to_string(),
+///     },
+///     ..Default::default()
+/// };
+/// exporter
+///     .export_folder_with_options("Recipes", dir.path(), &options)
+///     .unwrap();
+/// assert!(dir.path().join("2025/Brownies -- x-coredata-p102.html").exists());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ExportLayout {
+    /// Mirror the folder's subfolders, as in Notes.
+    #[default]
+    Folders,
+    /// Group notes by their modification date in Notes, ignoring subfolders.
+    ///
+    /// Reading the dates runs the script a second time, with the
+    /// `note-metadata` command the embedded script lacks (see
+    /// [Script Commands](crate#script-commands)). Notes keep their
+    /// filenames, whose ` -- <id>` suffix keeps notes with the same title
+    /// apart. Notes without a readable date go in an `Undated` directory.
+    ByDate {
+        /// A [`chrono` format string](chrono::format::strftime) for the
+        /// directory of each note, relative to the output directory, e.g.
+        /// `%Y/%m` for `2025/03/`.
+        pattern: String,
+    },
+}
+
+impl ExportLayout {
+    /// Checks the date pattern, so a bad one fails before the script runs.
+    pub(crate) fn validate(&self) -> Result<()> {
+        match self {
+            Self::Folders => Ok(()),
+            Self::ByDate { pattern } => {
+                if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+                    return Err(ExportError::InvalidDatePattern(pattern.clone()));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Moves every note under `dir` into `target`, in a directory named by
+/// formatting its modification date with `pattern`.
+pub(crate) fn arrange_by_date(
+    dir: &Path,
+    target: &Path,
+    pattern: &str,
+    dates: &HashMap<String, NoteDates>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            arrange_by_date(&path, target, pattern, dates)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let updated = stem
+                .rsplit_once(" -- ")
+                .and_then(|(_, id)| dates.get(id))
+                .and_then(|note_dates| parse_date(note_dates.updated()));
+            let bucket = match updated {
+                Some(updated) => PathBuf::from(updated.format(pattern).to_string()),
+                None => PathBuf::from(UNDATED_DIR),
+            };
+
+            // Keep notes inside the output directory whatever the pattern
+            if !bucket
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(ExportError::InvalidDatePattern(pattern.to_string()));
+            }
+
+            let bucket = target.join(bucket);
+            fs::create_dir_all(&bucket)?;
+            fs::rename(&path, bucket.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
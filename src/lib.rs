@@ -40,20 +40,242 @@
 //! exporter.list_folders().expect("Failed to list folders");
 //! exporter.export_folder("My Notes", "./exports").expect("Failed to export");
 //! ```
+//!
+//! ## Script Commands
+//!
+//! The script is invoked as `osascript <script> <command> [args...]`. A custom
+//! script should support the commands for the features it is used with:
+//!
+//! The embedded script only implements `list` and `export <folder> <output_dir>`,
+//! for folder names and `<account>:<folder>` specs, and prints none of the
+//! lines under [Script Output](#script-output). The rest of this protocol is
+//! for custom scripts (see [`Exporter::with_script_path`]). With the embedded
+//! script, a feature that needs another command or option fails with
+//! [`ExportError::UnsupportedByScript`] instead of being ignored by the
+//! script, and [`ExportSummary::matched_path`], [`ExportSummary::failed_notes`],
+//! and [progress](Exporter::with_progress) stay empty. Each feature names the
+//! commands it needs.
+//!
+//! Wherever a command takes a `<folder>`, it may also be given as
+//! `<account>:<folder>` to search a single account, or as `@local:<folder>` to
+//! search the on-device ("On My Mac") account. The script should identify
+//! that account by its properties rather than its name, which is localized.
+//! The script should split the argument on its first colon only, since folder
+//! names may contain colons, and search every account if the account is empty
+//! (e.g. `:Q1: Plans`). Folder specs from users are parsed with
+//! [`parse_folder_spec`] before they are passed on.
+//!
+//! A `<folder>` that starts with a tab is an exact path rather than a name:
+//! the tab-separated folder names from the account root down to the folder
+//! (e.g. `iCloud:\tWork\tProjects`, see [`Exporter::export_folder_by_path`]).
+//! The script should descend one level per name instead of searching.
+//!
+//! Commands that take `--include-recently-deleted` should skip each account's
+//! "Recently Deleted" folder while searching for `<folder>` and exporting,
+//! unless it is given, so trashed notes are not exported by accident. Like the
+//! on-device account, identify that folder by its special status rather than
+//! its name, which is localized. The embedded script doesn't, so with it the
+//! folder is only refused when exported by its English name.
+//!
+//! - `list` - print the available folders.
+//! - `export <folder> <output_dir> [--no-recursive] [--include-recently-deleted] [--pinned-only]` -
+//!   export a folder recursively, or only the notes directly in it with
+//!   `--no-recursive`. With `--pinned-only`, only pinned notes are exported.
+//!   Each subfolder gets its own directory inside its parent's, even one
+//!   with the same name as its parent (`Projects/Projects/`), so no two
+//!   folders share a directory. Print an `exported:` line for each note
+//!   written.
+//! - `note-html <folder> <note>` - print the HTML of a single note to stdout.
+//! - `note-path <folder> <note>` - print the path `export` writes the note
+//!   to, relative to its output directory, with `/` separators (e.g.
+//!   `Work/Projects/Plan -- x-coredata-p12.html`).
+//! - `exists <folder>` - print `true` if the folder can be found, `false`
+//!   otherwise, without exporting anything.
+//! - `subfolders <folder>` - print the name of each folder directly inside a
+//!   folder, one per line, or a `not-found: <folder>` line if it can't be
+//!   found. Given an exact path with no names (`<account>:<tab>`), print the
+//!   account's top-level folders, leaving out "Recently Deleted".
+//! - `count <folder> [--no-recursive] [--include-recently-deleted] [--pinned-only]` -
+//!   print the number of notes in a folder, including its subfolders unless
+//!   `--no-recursive` is given, and only pinned ones with `--pinned-only`.
+//! - `list-notes <folder>` - print `<id>\t<modified>\t<title>` for each
+//!   note in a folder, recursively and in export order, with ISO 8601 dates.
+//! - `export-stream <folder>` - like `export`, but print each note instead
+//!   of writing it: a `note: <path>\t<lines>` line, with the path `export`
+//!   would write the note to (as for `note-path`), followed by the note's
+//!   HTML in exactly `<lines>` lines. The `matched:` line comes first.
+//! - `folder-metadata <folder>` - print `<id>\t<account>\t<folder>\t...`
+//!   for a folder and each of its subfolders, recursively: the folder's id
+//!   followed by its tab-separated path, as in the `matched:` line.
+//! - `note-metadata <folder>` - print
+//!   `<id>\t<created>\t<updated>\t<shared>\t<participant>...` for each note
+//!   in a folder, recursively, with ISO 8601 dates. The id is the one used in
+//!   exported filenames (`<title> -- <id>.html`). `<shared>` is `true` or
+//!   `false`, followed by the names of the people a shared note is shared
+//!   with, if known. Scripts that stop after `<updated>` report every note as
+//!   not shared.
+//! - `export-notes <output_dir> <id>...` - export the notes with the given
+//!   ids, from any folder, to `<output_dir>/<account>/<folder>/.../<title> -- <id>.html`.
+//!   Print a `failed:` line for each id that matches no note, and
+//!   `progress:` lines as for `export`.
+//! - `attachment <url>` - print the data of the attachment a note's HTML
+//!   references by `x-coredata:` URL (in an `<img src>` or `<object data>`)
+//!   as a single `data:<mime type>;base64,<data>` line. Exports replace such
+//!   references with the data, so the images are extracted like inline ones.
+//!
+//! ## Script Output
+//!
+//! For `list`, `export`, `export-notes`, and `export-stream`, anything the script prints to stdout is forwarded
+//! to stdout (or stderr; see [`Exporter::with_script_output_to_stderr`]), except for the following structured lines, which are parsed into
+//! the returned results:
+//!
+//! - `matched: <account>\t<folder>\t...` - the tab-separated path of the
+//!   folder an export resolved to (see [`ExportSummary::matched_path`]).
+//! - `failed: <note>\t<reason>` - a note that could not be exported. The
+//!   script should catch errors for each note, print this line, and carry on
+//!   with the rest of the folder (see [`ExportSummary::failed_notes`]).
+//! - `not-found: <folder>` - the folder to export could not be found. The
+//!   export fails with [`ExportError::FolderNotFound`].
+//! - `progress: <done>/<total>` - how many of the folder's notes have been
+//!   exported so far (see [`Exporter::with_progress`]).
+//! - `exported: <index>\t<path>` - a note was written to `<path>`, relative
+//!   to the output directory as for `note-path`. `<index>` is its position
+//!   among the notes directly in its folder in Notes' display order,
+//!   starting at 1 (see [`ExportOptions::index_prefix`]).
+//!
+//! Output should be UTF-8. Bytes that aren't valid UTF-8 are replaced with
+//! U+FFFD instead of failing, except where the exact text matters, which
+//! fails with [`ExportError::OutputNotUtf8`]:
+//!
+//! ```
+//! # #[cfg(unix)]
+//! # {
+//! use std::fs;
+//! use std::os::unix::fs::PermissionsExt;
+//!
+//! use apple_notes_exporter_rs::{ExportOptions, Exporter};
+//!
+//! // A stand-in for osascript reporting a folder named "🍳 Breakfast" plus
+//! // the start of an encoded UTF-16 surrogate, which is invalid UTF-8
+//! let dir = tempfile::tempdir().unwrap();
+//! let script = dir.path().join("osascript");
+//! fs::write(
+//!     &script,
+//!     "#!/bin/sh\nprintf 'matched: iCloud\\t\\360\\237\\215\\263 Breakfast \\355\\n'\n",
+//! )
+//! .unwrap();
+//! fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+//!
+//! let exporter = Exporter::new().with_osascript_path(&script);
+//! let summary = exporter
+//!     .export_folder_with_options("Breakfast", dir.path().join("out"), &ExportOptions::default())
+//!     .unwrap();
+//! assert_eq!(summary.matched_path, ["iCloud", "🍳 Breakfast \u{FFFD}"]);
+//! # }
+//! ```
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::io::Write;
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Once};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
 use base64::prelude::*;
-use scraper::{Html, Selector};
+use scraper::{Html, Node, Selector, StrTendril};
+use serde::Serialize;
 use thiserror::Error;
 
+mod checklists;
+mod cleanup;
+mod consolidate;
+mod csv_index;
+mod doctor;
+#[cfg(feature = "download")]
+mod download;
+mod encoding;
+mod epub;
+mod export_diff;
+mod export_tree;
+mod extractor;
+mod filename_template;
+mod folder_metadata;
+mod folder_spec;
+#[cfg(feature = "git")]
+mod git;
+#[cfg(feature = "heic")]
+mod heic;
+mod layout;
+mod markdown;
+mod naming;
+mod notes_by_id;
+mod object_attachments;
+mod placeholders;
+mod progress;
+mod provenance;
+mod queries;
+mod readiness;
+#[cfg(feature = "image")]
+mod reencode;
+mod report;
+mod resume;
+mod rtfd;
+mod session_log;
+mod sharing;
+mod single_html;
+mod stream;
+mod stylesheet;
+mod text_twin;
+mod timestamps;
+mod title_filter;
+
+pub use checklists::checklists_to_inputs;
+pub use cleanup::clean_html;
+pub use consolidate::consolidate_attachments;
+pub use csv_index::generate_csv_index;
+pub use doctor::DiagnosticCheck;
+pub use export_diff::{ExportDiff, diff_exports};
+pub use extractor::{AttachmentExtractor, ExtractionStats};
+pub use filename_template::{DEFAULT_DATE_FORMAT, DEFAULT_FILENAME_TEMPLATE};
+pub use folder_metadata::FOLDER_METADATA_FILE;
+pub use folder_spec::{FolderSpec, parse_folder_spec};
+#[cfg(feature = "git")]
+pub use git::{GitCommit, commit_export};
+pub use layout::ExportLayout;
+pub use markdown::{FrontmatterKey, MarkdownOptions};
+pub use naming::{AttachmentContext, AttachmentNamer, AttachmentNaming};
+pub use progress::{ExportProgress, NoProgress, ProgressReporter, StderrProgress};
+pub use provenance::PROVENANCE_FILE;
+pub use queries::{FolderInfo, NoteInfo, NoteMetadata};
+#[cfg(feature = "image")]
+pub use reencode::{ReencodeFormat, ReencodeOptions};
+pub use report::{export_summary_json, write_extraction_report};
+pub use resume::CHECKPOINT_FILE;
+pub use stream::{ExportedNote, NoteStream};
+pub use stylesheet::{DEFAULT_STYLESHEET, Stylesheet};
+pub use text_twin::html_to_text;
+pub use title_filter::TitleFilter;
+
 /// The embedded AppleScript used for exporting notes.
 const EMBEDDED_SCRIPT: &str =
     include_str!("../vendor/apple-notes-exporter/scripts/export_notes.applescript");
 
+/// How often a running script checks whether it has been cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The English name of the folder Notes keeps deleted notes in, which the
+/// embedded script can't otherwise tell apart.
+const RECENTLY_DELETED: &str = "Recently Deleted";
+
+/// How osascript's error output ends when macOS denies it control of Notes.
+const NOT_AUTHORIZED_ERROR: &str = "(-1743)";
+
 /// Checks if the current platform is macOS and returns an error if not.
 #[cfg(target_os = "macos")]
 fn check_platform() -> Result<()> {
@@ -96,369 +318,4482 @@ pub enum ExportError {
     #[error("AppleScript exited with status {0}")]
     ScriptFailed(i32),
 
+    /// macOS denied the script control of Notes (error -1743). Allow it in
+    /// System Settings > Privacy & Security > Automation; see
+    /// [`Exporter::diagnose`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::fs;
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// use apple_notes_exporter_rs::{ExportError, Exporter};
+    ///
+    /// // A stand-in for osascript when Automation permission is denied
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let script = dir.path().join("osascript");
+    /// fs::write(
+    ///     &script,
+    ///     "#!/bin/sh\necho 'execution error: Not authorized to send Apple events to Notes. (-1743)' >&2\nexit 1\n",
+    /// )
+    /// .unwrap();
+    /// fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    ///
+    /// let exporter = Exporter::new().with_osascript_path(&script);
+    /// let error = exporter.export_folder("Recipes", dir.path().join("out")).unwrap_err();
+    /// assert!(matches!(error, ExportError::NotAuthorized));
+    /// # }
+    /// ```
+    #[error(
+        "Not authorized to control Notes; allow it in System Settings > Privacy & Security > \
+         Automation"
+    )]
+    NotAuthorized,
+
+    /// A custom script doesn't support the commands this crate sends it; see
+    /// [`Exporter::validate_script`].
+    #[error("Script is not compatible: {0}")]
+    IncompatibleScript(String),
+
     /// Failed to decode base64 image data.
     #[error("Failed to decode base64 image: {0}")]
     Base64DecodeError(#[from] base64::DecodeError),
+
+    /// Failed to write an EPUB archive.
+    #[error("Failed to write EPUB archive: {0}")]
+    EpubError(#[from] zip::result::ZipError),
+
+    /// An exported file already exists and [`OnExisting::Error`] was requested.
+    #[error("Output file already exists: {0}")]
+    OutputExists(PathBuf),
+
+    /// The folder to export could not be found in any account.
+    #[error("Folder not found: {0}")]
+    FolderNotFound(String),
+
+    /// The output directory cannot be created or written to.
+    #[error("Cannot write to output directory {0}: {1}")]
+    OutputNotWritable(PathBuf, #[source] std::io::Error),
+
+    /// [`ExportOptions::filename_template`] has an unknown token or is
+    /// otherwise malformed.
+    #[error("Invalid filename template {0:?}: {1}")]
+    InvalidFilenameTemplate(String, String),
+
+    /// An [`ExportLayout::ByDate`] pattern is not a valid date format or
+    /// names a directory outside the output directory, or
+    /// [`ExportOptions::date_format`] is not a valid date format or makes
+    /// dates with path separators.
+    #[error("Invalid date pattern: {0}")]
+    InvalidDatePattern(String),
+
+    /// The script printed invalid UTF-8 where it has to be exact, such as
+    /// the path a note is written to. Elsewhere invalid UTF-8 is replaced
+    /// with U+FFFD.
+    #[error("Script output is not valid UTF-8: {0}")]
+    OutputNotUtf8(String),
+
+    /// The script printed something other than what the command should print.
+    #[error("Unexpected script output: {0}")]
+    UnexpectedOutput(String),
+
+    /// The embedded script does not implement a command or option a feature
+    /// needs; see [Script Commands](crate#script-commands). Use a script
+    /// that does with [`Exporter::with_script_path`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use apple_notes_exporter_rs::{ExportError, Exporter};
+    ///
+    /// let error = Exporter::new().get_note_html("My Notes", "Shopping List").unwrap_err();
+    /// assert!(matches!(error, ExportError::UnsupportedByScript(_)));
+    /// assert!(error.to_string().contains("`note-html`"));
+    /// ```
+    #[error("The embedded AppleScript does not support {0}; use a custom script that does")]
+    UnsupportedByScript(String),
+
+    /// Committing an export to git failed; see [`commit_export`].
+    #[cfg(feature = "git")]
+    #[error("Git commit failed: {0}")]
+    GitFailed(String),
+
+    /// Fewer notes were exported than the folder contains, and
+    /// [`NoteCountCheck::Error`] was requested.
+    #[error("Expected {expected} notes but exported {actual}")]
+    NoteCountMismatch {
+        /// The number of notes in the folder.
+        expected: usize,
+        /// The number of notes exported or reported as failed.
+        actual: usize,
+    },
+
+    /// The export was cancelled through a [`CancellationToken`].
+    ///
+    /// osascript is killed once we stop reading its output, so this is
+    /// returned however it exits afterwards. A script that dies on its own,
+    /// even from a broken pipe, fails with [`ExportError::ScriptFailed`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::fs;
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// use apple_notes_exporter_rs::{
+    ///     CancellationToken, ExportError, ExportOptions, ExportProgress, Exporter, ProgressReporter,
+    /// };
+    ///
+    /// // Cancels as soon as the first note is reported
+    /// struct CancelOnFirstNote(CancellationToken);
+    ///
+    /// impl ProgressReporter for CancelOnFirstNote {
+    ///     fn on_note(&mut self, _progress: &ExportProgress) {
+    ///         self.0.cancel();
+    ///     }
+    /// }
+    ///
+    /// // A stand-in for a script that keeps printing after we stop reading
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let script = dir.path().join("osascript");
+    /// fs::write(
+    ///     &script,
+    ///     "#!/bin/sh\n[ \"$2\" = export ] || exit 0\nwhile :; do echo 'progress: 1/2'; done\n",
+    /// )
+    /// .unwrap();
+    /// fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    ///
+    /// let token = CancellationToken::new();
+    /// let options = ExportOptions {
+    ///     cancellation: Some(token.clone()),
+    ///     ..Default::default()
+    /// };
+    /// let exporter = Exporter::new().with_osascript_path(&script);
+    /// let out = dir.path().join("out");
+    /// let mut reporter = CancelOnFirstNote(token);
+    /// let error = exporter
+    ///     .export_folder_with_progress("Inbox", &out, &options, &mut reporter)
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ExportError::Cancelled));
+    ///
+    /// // Without cancellation, a broken pipe is an ordinary failure
+    /// fs::write(&script, "#!/bin/sh\n[ \"$2\" = export ] || exit 0\nkill -PIPE $$\n").unwrap();
+    /// let error = exporter.export_folder("Inbox", &out).unwrap_err();
+    /// assert!(matches!(error, ExportError::ScriptFailed(_)));
+    /// # }
+    /// ```
+    #[error("Export was cancelled")]
+    Cancelled,
 }
 
 /// Result type alias for export operations.
 pub type Result<T> = std::result::Result<T, ExportError>;
 
-/// An Apple Notes exporter that can list folders and export notes.
+/// What to do when an exported file already exists in the output directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnExisting {
+    /// Replace the existing file with the freshly exported one (the default).
+    #[default]
+    Overwrite,
+    /// Keep the existing file and discard the freshly exported one.
+    Skip,
+    /// Fail with [`ExportError::OutputExists`] without writing anything.
+    Error,
+}
+
+/// Selects the account to export a folder from.
 ///
-/// Use [`Exporter::new()`] for the default embedded script, or
-/// [`Exporter::with_script_path()`] for a custom script.
-#[derive(Debug)]
-pub struct Exporter {
-    script_source: ScriptSource,
+/// Account names are matched as displayed in Notes, except for the on-device
+/// account, whose name ("On My Mac") depends on the system language. Use
+/// [`AccountSelector::LocalAccount`] to select it on any system.
+///
+/// Plain strings convert into [`AccountSelector::Named`], so account names
+/// can be passed directly wherever an account is expected.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::{AccountSelector, Exporter};
+///
+/// let exporter = Exporter::new();
+///
+/// // Works whether the account is called "On My Mac", "Sur mon Mac", ...
+/// exporter.export_folder_from_account(AccountSelector::LocalAccount, "Work", "./exports")
+///     .expect("Failed to export");
+///
+/// exporter.export_folder_from_account("iCloud", "Work", "./icloud_exports")
+///     .expect("Failed to export");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AccountSelector {
+    /// The account with this name (e.g. `"iCloud"` or `"Google"`).
+    Named(String),
+    /// The on-device account, whatever its localized name.
+    LocalAccount,
 }
 
-#[derive(Debug)]
-enum ScriptSource {
-    Embedded,
-    Path(PathBuf),
+impl AccountSelector {
+    /// Returns the spec for `folder` in this account.
+    fn folder(self, folder: &str) -> FolderSpec {
+        FolderSpec {
+            account: Some(self),
+            folder: folder.to_string(),
+        }
+    }
+
+    /// Returns the spec for the folder at exactly `path` from this account's
+    /// root, in the script's tab-separated form.
+    fn folder_path(self, path: &[&str]) -> FolderSpec {
+        self.folder(&format!("\t{}", path.join("\t")))
+    }
 }
 
-impl Default for Exporter {
-    fn default() -> Self {
-        Self::new()
+impl From<&str> for AccountSelector {
+    fn from(account: &str) -> Self {
+        Self::Named(account.to_string())
     }
 }
 
-impl Exporter {
-    /// Creates a new exporter using the embedded AppleScript.
+impl From<String> for AccountSelector {
+    fn from(account: String) -> Self {
+        Self::Named(account)
+    }
+}
+
+/// A flag for cancelling a running export, e.g. from a GUI thread.
+///
+/// Clones share the same flag, so keep one clone to call
+/// [`cancel`](Self::cancel) on and pass another in [`ExportOptions::cancellation`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::thread;
+///
+/// use apple_notes_exporter_rs::{CancellationToken, ExportError, ExportOptions, Exporter};
+///
+/// let token = CancellationToken::new();
+/// let options = ExportOptions {
+///     cancellation: Some(token.clone()),
+///     ..Default::default()
+/// };
+///
+/// let handle = thread::spawn(move || {
+///     Exporter::new().export_folder_with_options("My Notes", "./exports", &options)
+/// });
+///
+/// // Later, e.g. when the user clicks "Cancel"
+/// token.cancel();
+/// assert!(matches!(handle.join().unwrap(), Err(ExportError::Cancelled)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new, not yet cancelled token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of any export using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl From<Arc<AtomicBool>> for CancellationToken {
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        Self(flag)
+    }
+}
+
+/// A threshold for [`ExportOptions::large_note_bytes`]: 10 MiB.
+pub const DEFAULT_LARGE_NOTE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Options controlling how a folder is exported.
+///
+/// Use [`ExportOptions::default()`] for the same behavior as
+/// [`Exporter::export_folder`].
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// What to do when an exported file already exists in the output directory.
+    pub on_existing: OnExisting,
+
+    /// How notes are arranged in the output directory: by folder, as in
+    /// Notes (the default), or by date. See [`ExportLayout`].
+    pub layout: ExportLayout,
+
+    /// Export the folder's subfolders too. On by default.
     ///
-    /// This is the recommended way to create an exporter for most use cases.
+    /// When off, only the notes directly in the folder are exported and no
+    /// subdirectories are created, e.g. to export a deep tree folder by folder.
+    pub recursive: bool,
+
+    /// Treat the "Recently Deleted" folder like any other folder, so it can
+    /// be exported by name and is included by [`Exporter::export_all`].
+    ///
+    /// Off by default, so trashed notes aren't archived by accident: the
+    /// folder is skipped, and exporting it fails with
+    /// [`ExportError::FolderNotFound`]. Earlier versions exported it like any
+    /// other folder. A custom script recognizes it by its special status,
+    /// since its name is localized. The embedded script doesn't skip it, so
+    /// with it, exporting a folder named "Recently Deleted" fails instead.
+    /// That only goes by the English name, and misses folders deleted into
+    /// it; see [Script Commands](crate#script-commands).
     ///
     /// # Example
     ///
-    /// ```no_run
-    /// use apple_notes_exporter_rs::Exporter;
+    /// ```
+    /// use apple_notes_exporter_rs::{ExportError, Exporter};
     ///
-    /// let exporter = Exporter::new();
-    /// exporter.list_folders().expect("Failed to list folders");
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let error = Exporter::new().export_folder("Recently Deleted", dir.path()).unwrap_err();
+    /// assert!(matches!(error, ExportError::FolderNotFound(_)));
     /// ```
-    pub fn new() -> Self {
-        Self {
-            script_source: ScriptSource::Embedded,
-        }
-    }
+    pub include_recently_deleted: bool,
 
-    /// Creates a new exporter using a custom AppleScript at the specified path.
+    /// Export only the notes pinned to the top of their folder in Notes.
+    /// The script checks each note's pinned state, and combines with
+    /// [`title_filter`](Self::title_filter) to archive just the important
+    /// notes. Off by default.
     ///
-    /// Returns an error if the script file does not exist.
+    /// Needs a script that takes `--pinned-only`, which the embedded script
+    /// doesn't; see [Script Commands](crate#script-commands).
     ///
     /// # Example
     ///
-    /// ```no_run
-    /// use apple_notes_exporter_rs::Exporter;
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
     ///
-    /// let exporter = Exporter::with_script_path("./custom_script.applescript")
-    ///     .expect("Script not found");
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let options = ExportOptions {
+    ///     pinned_only: true,
+    ///     ..Default::default()
+    /// };
+    /// let summary = exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(summary.actual_notes, 1);
+    /// assert!(dir.path().join("Recipes/Pancakes -- x-coredata-p101.html").exists());
+    /// # }
     /// ```
-    pub fn with_script_path<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path = path.as_ref().to_path_buf();
-        if !path.exists() {
-            return Err(ExportError::ScriptNotFound(path));
-        }
-        Ok(Self {
-            script_source: ScriptSource::Path(path),
-        })
-    }
+    pub pinned_only: bool,
 
-    /// Lists all available top-level folders across all Apple Notes accounts.
+    /// Export only the notes whose titles match these patterns, e.g. every
+    /// note titled `Meeting *`. Exports every note by default.
+    pub title_filter: TitleFilter,
+
+    /// How exported notes are named, as a template with the tokens `{title}`,
+    /// `{id}`, `{date}` (the modification date, formatted with
+    /// [`date_format`](Self::date_format)) and `{created}` (the creation
+    /// date), e.g. `{date} - {title} [{id}]`. The
+    /// `.html` extension is added. Defaults to [`DEFAULT_FILENAME_TEMPLATE`]
+    /// (`{title} -- {id}`).
     ///
-    /// The output is printed to stdout by the AppleScript.
+    /// Unknown tokens fail with [`ExportError::InvalidFilenameTemplate`]
+    /// before anything is exported. The dates take another script run, with
+    /// the `note-metadata` command the embedded script lacks (see
+    /// [Script Commands](crate#script-commands)). Keep
+    /// `{id}` in the template unless note titles are unique: a note whose
+    /// name is already taken keeps its default name.
     ///
     /// # Example
     ///
-    /// ```no_run
-    /// use apple_notes_exporter_rs::Exporter;
-    ///
-    /// let exporter = Exporter::new();
-    /// exporter.list_folders().expect("Failed to list folders");
     /// ```
-    pub fn list_folders(&self) -> Result<()> {
-        self.run_script(&["list"])
-    }
-
-    /// Exports a folder recursively to HTML files.
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
     ///
-    /// The folder search uses breadth-first search and looks at all levels
-    /// (not just top-level) to find the folder. Once found, it exports that
-    /// folder and all its subfolders recursively.
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
     ///
-    /// This method searches all accounts for the folder. If a folder with the
-    /// same name exists in multiple accounts, use [`export_folder_from_account`](Self::export_folder_from_account)
-    /// to specify which account to use.
+    /// let options = ExportOptions {
+    ///     filename_template: "{title} [{id}]".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    /// assert!(dir.path().join("Recipes/Pancakes [x-coredata-p101].html").exists());
     ///
-    /// # Arguments
+    /// let options = ExportOptions {
+    ///     filename_template: "{title} ({author})".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let error = exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap_err();
+    /// assert!(error.to_string().contains("unknown token {author}"));
+    /// # }
+    /// ```
+    pub filename_template: String,
+
+    /// How the `{date}` and `{created}` tokens of the
+    /// [`filename_template`](Self::filename_template) are formatted, as a
+    /// strftime-style [chrono format](chrono::format::strftime), e.g.
+    /// `%d.%m.%Y` or `%B %-d, %Y`. Defaults to [`DEFAULT_DATE_FORMAT`]
+    /// (`%Y-%m-%d`).
     ///
-    /// * `folder` - The folder name to export.
-    /// * `output_dir` - The directory where exported notes will be saved.
-    ///   Will be created if it doesn't exist.
+    /// Names of months and weekdays are in English whatever the system
+    /// locale, so exports are named the same on every machine. Formats that
+    /// are invalid or make dates with `/` or `\` fail with
+    /// [`ExportError::InvalidDatePattern`] before anything is exported.
+    /// [`ExportLayout::ByDate`] directories use their own pattern, and dates
+    /// written into notes and sidecar files stay RFC 3339.
     ///
     /// # Example
     ///
-    /// ```no_run
-    /// use apple_notes_exporter_rs::Exporter;
-    ///
-    /// let exporter = Exporter::new();
-    /// exporter.export_folder("My Notes", "./exports").expect("Failed to export");
     /// ```
-    pub fn export_folder<P: AsRef<Path>>(&self, folder: &str, output_dir: P) -> Result<()> {
-        self.export_folder_impl(folder, output_dir)
-    }
-
-    /// Exports a folder from a specific account recursively to HTML files.
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
     ///
-    /// This is useful when a folder with the same name exists in multiple accounts.
-    /// The folder search uses breadth-first search and looks at all levels
-    /// (not just top-level) to find the folder within the specified account.
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
     ///
-    /// # Arguments
+    /// let options = ExportOptions {
+    ///     filename_template: "{date} {title}".to_string(),
+    ///     date_format: "%d.%m.%Y".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    /// assert!(dir.path().join("Recipes/02.01.2025 Pancakes.html").exists());
     ///
-    /// * `account` - The account name (e.g., "iCloud", "Google", "On My Mac").
-    /// * `folder` - The folder name to export.
-    /// * `output_dir` - The directory where exported notes will be saved.
-    ///   Will be created if it doesn't exist.
+    /// let options = ExportOptions {
+    ///     filename_template: "{date} {title}".to_string(),
+    ///     date_format: "%D".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let error = exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap_err();
+    /// assert!(error.to_string().contains("Invalid date pattern"));
+    /// # }
+    /// ```
+    pub date_format: String,
+
+    /// Prefix each note's filename with its zero-padded position in its folder,
+    /// e.g. `007 - Meeting Notes -- abc123.html`.
+    ///
+    /// Notes are numbered in their display order in Notes, as the script
+    /// reports it in `exported:` lines, so sorting the files by name keeps
+    /// that order. The embedded script doesn't print them, so this fails
+    /// with [`ExportError::UnsupportedByScript`] there; see
+    /// [Script Output](crate#script-output). Off by default.
     ///
     /// # Example
     ///
-    /// ```no_run
-    /// use apple_notes_exporter_rs::Exporter;
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::fs;
+    /// use std::os::unix::fs::PermissionsExt;
     ///
-    /// let exporter = Exporter::new();
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
     ///
-    /// // Export "Work" folder from iCloud account
-    /// exporter.export_folder_from_account("iCloud", "Work", "./exports")
-    ///     .expect("Failed to export");
+    /// // A stand-in for a script exporting "Zebra" before "Apple"
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let script = dir.path().join("osascript");
+    /// fs::write(
+    ///     &script,
+    ///     "#!/bin/sh\n[ \"$2\" = export ] || exit 0\nmkdir -p \"$4/Inbox\"\n\
+    ///      echo '<p>A</p>' > \"$4/Inbox/Apple -- a1.html\"\n\
+    ///      echo '<p>Z</p>' > \"$4/Inbox/Zebra -- z1.html\"\n\
+    ///      printf 'exported: 2\\tInbox/Apple -- a1.html\\nexported: 1\\tInbox/Zebra -- z1.html\\n'\n",
+    /// )
+    /// .unwrap();
+    /// fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
     ///
-    /// // Export "Work" folder from Google account
-    /// exporter.export_folder_from_account("Google", "Work", "./google_exports")
-    ///     .expect("Failed to export");
+    /// let options = ExportOptions {
+    ///     index_prefix: true,
+    ///     ..Default::default()
+    /// };
+    /// let out = dir.path().join("out");
+    /// Exporter::new()
+    ///     .with_osascript_path(&script)
+    ///     .export_folder_with_options("Inbox", &out, &options)
+    ///     .unwrap();
+    /// assert!(out.join("Inbox/001 - Zebra -- z1.html").exists());
+    /// assert!(out.join("Inbox/002 - Apple -- a1.html").exists());
+    /// # }
     /// ```
-    pub fn export_folder_from_account<P: AsRef<Path>>(
-        &self,
-        account: &str,
-        folder: &str,
-        output_dir: P,
-    ) -> Result<()> {
-        let folder_spec = format!("{account}:{folder}");
-        self.export_folder_impl(&folder_spec, output_dir)
-    }
+    pub index_prefix: bool,
 
-    fn export_folder_impl<P: AsRef<Path>>(&self, folder_spec: &str, output_dir: P) -> Result<()> {
-        let output_dir = output_dir.as_ref();
-        fs::create_dir_all(output_dir)?;
+    /// Remove directories that end up containing no notes, such as
+    /// subfolders without any notes, instead of creating them in the output
+    /// directory. The number removed is reported in
+    /// [`ExportSummary::pruned_dirs`].
+    pub prune_empty: bool,
 
-        let output_dir = output_dir.canonicalize()?;
-        let output_dir_str = output_dir.to_str().ok_or(ExportError::InvalidUtf8Path)?;
+    /// Join chains of folders that each contain nothing but one subfolder
+    /// into a single directory, e.g. `A/B/C` becomes `A - B - C`. This keeps
+    /// deeply nested exports shallow while keeping the folder names. Off by
+    /// default.
+    pub collapse_single_child: bool,
 
-        self.run_script(&["export", folder_spec, output_dir_str])
-    }
+    /// Keep the full path of each exported note, including the output
+    /// directory, at most this many bytes long by shortening note titles in
+    /// filenames. The ` -- <id>` suffix and extension are kept, so shortened
+    /// names stay unique. With a custom
+    /// [`filename_template`](Self::filename_template), the end of the name
+    /// is cut instead. Useful for deep folder trees on filesystems with
+    /// path limits, such as when syncing to Windows. Renamed notes are
+    /// reported in [`ExportSummary::truncated_notes`]. No limit by default.
+    ///
+    /// Only the title is shortened: a note whose folders alone exceed the
+    /// limit keeps an empty title and is still written. Extracted attachment
+    /// folders are named after the note, so leave room for them.
+    pub max_path_len: Option<usize>,
 
-    /// Exports a folder and extracts all embedded images to attachment folders.
+    /// Show checklist items with a checkbox reflecting whether they are done,
+    /// instead of as plain bullets. On by default; see [`checklists_to_inputs`].
+    pub export_checklists_as_inputs: bool,
+
+    /// CSS to embed in each exported note, so large exports read well in a
+    /// browser. Off by default; see [`Stylesheet`].
+    pub stylesheet: Stylesheet,
+
+    /// Remove the inline styles, `Apple-*` classes, `<font>` elements and
+    /// empty formatting tags Notes adds to each note, keeping its headings,
+    /// lists, links and images, e.g. before publishing notes or styling them
+    /// with a [`stylesheet`](Self::stylesheet). Off by default; see
+    /// [`clean_html`].
     ///
-    /// This combines [`export_folder`](Self::export_folder) with
-    /// [`extract_attachments_from_directory`] for convenience.
+    /// # Example
     ///
-    /// # Arguments
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
     ///
-    /// * `folder` - The folder name to export.
-    /// * `output_dir` - The directory where exported notes will be saved.
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
     ///
-    /// # Returns
+    /// let options = ExportOptions {
+    ///     clean_html: true,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
     ///
-    /// Returns a vector of `ExtractionResult` for each HTML file processed.
+    /// let html = std::fs::read_to_string(dir.path().join("Recipes/Pancakes -- x-coredata-p101.html")).unwrap();
+    /// assert!(html.contains("<div>Flour, milk, eggs. #breakfast</div>"));
+    /// # }
+    /// ```
+    pub clean_html: bool,
+
+    /// Start every exported note with a UTF-8 byte order mark, and declare
+    /// `<meta charset="utf-8">` in notes that don't declare a charset, for
+    /// Windows tools that otherwise read the HTML in a legacy encoding. Off
+    /// by default, since most tools don't need it.
+    ///
+    /// Attachment extraction keeps the byte order mark when it rewrites a
+    /// note.
     ///
     /// # Example
     ///
-    /// ```no_run
-    /// use apple_notes_exporter_rs::Exporter;
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
     ///
-    /// let exporter = Exporter::new();
-    /// let results = exporter.export_folder_with_attachments("My Notes", "./exports")
-    ///     .expect("Failed to export");
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
     ///
-    /// let total: usize = results.iter().map(|r| r.attachments.len()).sum();
-    /// println!("Extracted {total} attachments");
+    /// let options = ExportOptions {
+    ///     utf8_bom: true,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    ///
+    /// let html = std::fs::read(dir.path().join("Recipes/Pancakes -- x-coredata-p101.html")).unwrap();
+    /// assert!(html.starts_with(b"\xEF\xBB\xBF<html><head><meta charset=\"utf-8\">"));
+    /// # }
     /// ```
-    pub fn export_folder_with_attachments<P: AsRef<Path>>(
-        &self,
-        folder: &str,
-        output_dir: P,
-    ) -> Result<Vec<ExtractionResult>> {
-        self.export_folder(folder, &output_dir)?;
-        extract_attachments_from_directory(&output_dir)
-    }
+    pub utf8_bom: bool,
 
-    /// Exports a folder from a specific account and extracts all embedded images.
+    /// Rewrite exported notes that have CRLF or mixed line endings with LF
+    /// ones, so exports kept in git or compared with [`diff_exports`] don't
+    /// change with the line endings Notes happens to produce. Applied after
+    /// [`Exporter::with_html_transform`]; [`text_twin`](Self::text_twin)
+    /// files always use LF. Off by default, so notes are written exactly as
+    /// Notes produced them.
     ///
-    /// This combines [`export_folder_from_account`](Self::export_folder_from_account) with
-    /// [`extract_attachments_from_directory`] for convenience.
+    /// # Example
     ///
-    /// # Arguments
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
     ///
-    /// * `account` - The account name (e.g., "iCloud", "Google", "On My Mac").
-    /// * `folder` - The folder name to export.
-    /// * `output_dir` - The directory where exported notes will be saved.
+    /// // Stands in for Notes producing Windows line endings
+    /// let exporter = Exporter::new()
+    ///     .with_osascript_path("fixtures/fake-osascript")
+    ///     .with_html_transform(|html| html.replace("</div>", "</div>\r\n"));
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let options = ExportOptions {
+    ///     normalize_line_endings: true,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    ///
+    /// let html = std::fs::read_to_string(dir.path().join("Recipes/Pancakes -- x-coredata-p101.html")).unwrap();
+    /// assert!(html.contains("</div>\n"));
+    /// assert!(!html.contains('\r'));
+    /// # }
+    /// ```
+    pub normalize_line_endings: bool,
+
+    /// Report notes whose HTML is larger than this many bytes in
+    /// [`ExportSummary::large_notes`], e.g. to suggest extracting their
+    /// images, which are otherwise kept inline as data URLs.
+    /// [`DEFAULT_LARGE_NOTE_BYTES`] is a reasonable limit. `None` (the
+    /// default) turns the check off.
+    pub large_note_bytes: Option<u64>,
+
+    /// Also write each note's text as `<name>.txt` next to its `<name>.html`,
+    /// for search indexing. The text is taken from the final HTML, after
+    /// every other option is applied; see [`html_to_text`]. Off by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let options = ExportOptions {
+    ///     text_twin: true,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    ///
+    /// let text = dir.path().join("Recipes/Pancakes -- x-coredata-p101.txt");
+    /// assert_eq!(
+    ///     std::fs::read_to_string(text).unwrap(),
+    ///     "Pancakes\n\nFlour, milk, eggs. #breakfast\n- [x] Buy eggs\n- [ ] Buy milk\n"
+    /// );
+    /// # }
+    /// ```
+    pub text_twin: bool,
+
+    /// Mark notes shared with other people by adding `data-shared="true"`
+    /// and a comma-separated `data-participants` list to their `<body>`, so
+    /// collaborative notes can be told apart after export. Off by default,
+    /// since reading the sharing state runs the script a second time; see
+    /// [`Exporter::note_metadata`].
+    ///
+    /// Needs a script with the `note-metadata` command, which the embedded
+    /// script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let options = ExportOptions {
+    ///     mark_shared_notes: true,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    ///
+    /// let read = |name| std::fs::read_to_string(dir.path().join("Recipes").join(name)).unwrap();
+    /// assert!(read("Pancakes -- x-coredata-p101.html")
+    ///     .contains(r#"<body data-shared="true" data-participants="Alex Kim, Sam Lee">"#));
+    /// assert!(!read("Desserts/Brownies -- x-coredata-p102.html").contains("data-shared"));
+    /// # }
+    /// ```
+    pub mark_shared_notes: bool,
+
+    /// Read notes that came out empty again, a few times a couple of seconds
+    /// apart, in case iCloud was still syncing them. Off by default.
+    ///
+    /// Notes doesn't report whether it is syncing, and exports a note whose
+    /// body hasn't arrived yet as a document with no text or attachments.
+    /// Such notes are kept and listed in [`ExportSummary::empty_notes`] by
+    /// default, since a note can also be blank on purpose. With this set, the
+    /// ones that are still empty after waiting are not written, and are
+    /// listed in [`ExportSummary::failed_notes`] by id instead, so they can
+    /// be exported later with [`Exporter::retry_failed`].
+    ///
+    /// Needs a script with the `note-html` command, which the embedded script
+    /// lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::path::Path;
+    ///
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// // The fixture's "Untitled" note has an empty body
+    /// let summary = exporter
+    ///     .export_folder_with_options("Work", dir.path(), &Default::default())
+    ///     .unwrap();
+    /// assert_eq!(summary.actual_notes, 2);
+    /// assert!(summary.failed_notes.is_empty());
+    /// assert_eq!(summary.empty_notes, [Path::new("Work/Untitled -- x-coredata-p107.html")]);
+    /// assert!(dir.path().join("Work/Untitled -- x-coredata-p107.html").exists());
+    /// # }
+    /// ```
+    pub wait_for_sync: bool,
+
+    /// Whether to compare the number of exported notes with the number of
+    /// notes in the folder, to catch exports the script cut short without
+    /// failing. Off by default, since counting runs the script a second time.
+    ///
+    /// Needs a script with the `count` command, which the embedded script
+    /// lacks; see [Script Commands](crate#script-commands).
+    pub note_count_check: NoteCountCheck,
+
+    /// Give each exported note the modification date it has in Notes as its
+    /// file modification time, instead of the time it was exported, so the
+    /// files sort chronologically by date. Off by default, since reading the
+    /// dates runs the script a second time.
+    ///
+    /// Set [`ExtractOptions::preserve_timestamps`] as well to carry the dates
+    /// over to extracted attachments.
+    ///
+    /// Needs a script with the `note-metadata` command, which the embedded
+    /// script lacks; see [Script Commands](crate#script-commands).
+    pub preserve_timestamps: bool,
+
+    /// Write a [`FOLDER_METADATA_FILE`] (`folder.json`) into each exported
+    /// directory describing the Notes folder it came from, so the folder
+    /// structure can be recreated elsewhere without guessing from directory
+    /// names:
+    ///
+    /// ```json
+    /// {
+    ///   "name": "Desserts",
+    ///   "account": "iCloud",
+    ///   "path": ["Recipes", "Desserts"],
+    ///   "id": "x-coredata://.../ICFolder/p12",
+    ///   "notes": 1
+    /// }
+    /// ```
+    ///
+    /// `path` starts at the account's top level, `id` is `null` if the script
+    /// does not report one, and `notes` counts the notes directly in the
+    /// folder. With [`collapse_single_child`](Self::collapse_single_child), a
+    /// merged directory is described as its deepest folder. Not written with
+    /// [`ExportLayout::ByDate`], whose directories are not folders. Off by
+    /// default, since reading the folder ids runs the script a second time.
+    ///
+    /// Needs a script with the `folder-metadata` command, which the embedded
+    /// script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter, FOLDER_METADATA_FILE};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let options = ExportOptions {
+    ///     folder_metadata: true,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    ///
+    /// let path = dir.path().join("Recipes/Desserts").join(FOLDER_METADATA_FILE);
+    /// let json = std::fs::read_to_string(path).unwrap();
+    /// assert!(json.contains(r#""account": "iCloud""#));
+    /// assert!(json.contains(r#""path": ["Recipes", "Desserts"]"#));
+    /// assert!(json.contains(r#""id": "x-coredata-f"#));
+    /// assert!(json.contains(r#""notes": 1"#));
+    /// # }
+    /// ```
+    pub folder_metadata: bool,
+
+    /// Append a description of the export to [`PROVENANCE_FILE`]
+    /// (`EXPORT_INFO.txt`) in the output directory: when and by which version
+    /// of this crate it ran, the requested and matched folder, how many notes
+    /// were exported, and a hash identifying the AppleScript used. Each export
+    /// adds a section, so an archive can always be traced back to its source.
+    /// Off by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter, PROVENANCE_FILE};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let options = ExportOptions {
+    ///     write_provenance: true,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    ///
+    /// let info = std::fs::read_to_string(dir.path().join(PROVENANCE_FILE)).unwrap();
+    /// assert!(info.starts_with("Exported by: apple-notes-exporter "));
+    /// assert!(info.contains("Requested folder: Recipes\n"));
+    /// assert!(info.contains("Source: iCloud > Recipes\n"));
+    /// assert!(info.contains("Notes: 2\n"));
+    /// assert!(info.contains("Script: embedded (fnv1a "));
+    /// # }
+    /// ```
+    pub write_provenance: bool,
+
+    /// Commit the export to the git repository containing the output
+    /// directory, with a message summarizing the notes added, modified, and
+    /// removed; see [`commit_export`]. Nothing is committed if no note
+    /// changed, so a scheduled backup only records real edits. Off by
+    /// default.
+    ///
+    /// The commit is made when the export finishes, so attachments extracted
+    /// afterwards are not in it; call [`commit_export`] yourself after
+    /// extracting instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::process::Command;
+    ///
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter, OnExisting};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let git = |args: &[&str]| {
+    ///     let output = Command::new("git").arg("-C").arg(dir.path()).args(args).output().unwrap();
+    ///     String::from_utf8(output.stdout).unwrap()
+    /// };
+    /// git(&["init", "--quiet"]);
+    /// git(&["config", "user.name", "Notes Backup"]);
+    /// git(&["config", "user.email", "backup@example.com"]);
+    ///
+    /// let options = ExportOptions {
+    ///     git_commit: true,
+    ///     on_existing: OnExisting::Overwrite,
+    ///     ..Default::default()
+    /// };
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    /// assert!(git(&["log", "--format=%s"]).starts_with("Export Recipes: 2 notes added"));
+    ///
+    /// // Exporting the same notes again commits nothing
+    /// exporter
+    ///     .export_folder_with_options("Recipes", dir.path(), &options)
+    ///     .unwrap();
+    /// assert_eq!(git(&["rev-list", "--count", "HEAD"]).trim(), "1");
+    /// # }
+    /// ```
+    #[cfg(feature = "git")]
+    pub git_commit: bool,
+
+    /// Cancels the export when triggered.
+    ///
+    /// The osascript process is killed and [`ExportError::Cancelled`] is
+    /// returned. Notes exported before cancellation are kept in the output
+    /// directory.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl ExportOptions {
+    /// Returns the flags for the script's `export` and `count` commands that
+    /// select which notes are exported.
+    fn selection_flags(&self) -> Vec<&'static str> {
+        let mut flags = Vec::new();
+        if !self.recursive {
+            flags.push("--no-recursive");
+        }
+        if self.include_recently_deleted {
+            flags.push("--include-recently-deleted");
+        }
+        if self.pinned_only {
+            flags.push("--pinned-only");
+        }
+        flags
+    }
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            on_existing: OnExisting::default(),
+            layout: ExportLayout::default(),
+            recursive: true,
+            include_recently_deleted: false,
+            pinned_only: false,
+            title_filter: TitleFilter::default(),
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            index_prefix: false,
+            prune_empty: false,
+            collapse_single_child: false,
+            max_path_len: None,
+            export_checklists_as_inputs: true,
+            stylesheet: Stylesheet::default(),
+            clean_html: false,
+            utf8_bom: false,
+            normalize_line_endings: false,
+            large_note_bytes: None,
+            text_twin: false,
+            mark_shared_notes: false,
+            wait_for_sync: false,
+            note_count_check: NoteCountCheck::default(),
+            preserve_timestamps: false,
+            folder_metadata: false,
+            write_provenance: false,
+            #[cfg(feature = "git")]
+            git_commit: false,
+            cancellation: None,
+        }
+    }
+}
+
+/// What to do when an export has fewer notes than its folder; see
+/// [`ExportOptions::note_count_check`].
+///
+/// Notes reported in [`ExportSummary::failed_notes`] count as accounted for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoteCountCheck {
+    /// Don't count the folder's notes.
+    #[default]
+    Off,
+    /// Count them into [`ExportSummary::expected_notes`], for the caller to
+    /// compare with the notes accounted for.
+    Warn,
+    /// Return [`ExportError::NoteCountMismatch`]. The exported notes are
+    /// still kept in the output directory.
+    Error,
+}
+
+/// Summary of a completed folder export.
+#[derive(Debug, Clone, Default)]
+pub struct ExportSummary {
+    /// The full path of the exported folder: the account name, its parent
+    /// folders, and the folder itself (e.g. `["iCloud", "Work", "Projects"]`).
+    ///
+    /// Empty if the script did not report which folder it matched.
+    pub matched_path: Vec<String>,
+
+    /// Directories created by the export, relative to the output directory,
+    /// with parents listed before their children.
+    ///
+    /// Directories that already existed in the output directory are not included.
+    pub created_dirs: Vec<PathBuf>,
+
+    /// The number of empty directories removed because
+    /// [`ExportOptions::prune_empty`] was set.
+    pub pruned_dirs: usize,
+
+    /// Notes the script skipped because they could not be exported.
+    ///
+    /// The rest of the folder is still exported.
+    pub failed_notes: Vec<FailedNote>,
+
+    /// The number of notes in the folder, if [`ExportOptions::note_count_check`]
+    /// is on.
+    pub expected_notes: Option<usize>,
+
+    /// The number of note files the script wrote, before
+    /// [`ExportOptions::title_filter`] is applied.
+    pub actual_notes: usize,
+
+    /// The number of notes [`Exporter::export_folder_resumable`] left alone
+    /// because an earlier run had already exported them unchanged.
+    pub skipped_notes: usize,
+
+    /// The status the script exited with: 0, or one of the codes allowed
+    /// with [`Exporter::with_success_codes`]. `None` if the export was
+    /// cancelled, or ran the script more than once.
+    pub exit_code: Option<i32>,
+
+    /// Notes whose filenames were shortened to fit
+    /// [`ExportOptions::max_path_len`].
+    pub truncated_notes: Vec<TruncatedNote>,
+
+    /// Notes larger than [`ExportOptions::large_note_bytes`], relative to
+    /// the output directory, with their sizes in bytes. Extracting their
+    /// images with [`extract_attachments_from_directory`] shrinks them.
+    pub large_notes: Vec<(PathBuf, u64)>,
+
+    /// Notes with no text or attachments, relative to the output directory.
+    /// They may be blank, or not synced yet; see
+    /// [`ExportOptions::wait_for_sync`].
+    pub empty_notes: Vec<PathBuf>,
+}
+
+/// A note whose filename was shortened to fit [`ExportOptions::max_path_len`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruncatedNote {
+    /// The path the note would have had, relative to the output directory.
+    pub original: PathBuf,
+    /// The path the note was written to, relative to the output directory.
+    pub path: PathBuf,
+}
+
+/// A note that failed to export, as reported by the script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedNote {
+    /// The note's title (or id, if the script could not read the title).
+    pub note: String,
+    /// Why the note failed, e.g. the AppleScript error message.
+    pub reason: String,
+}
+
+/// A structured line printed by the AppleScript on stdout.
+///
+/// See the "Script Output" section of the crate documentation.
+#[derive(Debug, PartialEq)]
+enum ScriptLine {
+    /// `matched: <account>\t<folder>\t...`: the folder the export resolved to.
+    Matched(Vec<String>),
+    /// `failed: <note>\t<reason>`: a note that could not be exported.
+    Failed(FailedNote),
+    /// `progress: <done>/<total>`: how many notes have been exported so far.
+    Progress { done: usize, total: usize },
+    /// `not-found: <folder>`: the folder to export could not be found.
+    NotFound(String),
+    /// `exported: <index>\t<path>`: a note was written to `path`, and is
+    /// at `index` in its folder's display order.
+    Exported { index: usize, path: String },
+}
+
+impl ScriptLine {
+    fn parse(line: &str) -> Option<Self> {
+        if let Some(path) = line.strip_prefix("matched: ") {
+            return Some(Self::Matched(
+                path.split('\t').map(str::to_string).collect(),
+            ));
+        }
+
+        if let Some(failure) = line.strip_prefix("failed: ") {
+            let (note, reason) = failure.split_once('\t').unwrap_or((failure, ""));
+            return Some(Self::Failed(FailedNote {
+                note: note.to_string(),
+                reason: reason.to_string(),
+            }));
+        }
+
+        if let Some(folder) = line.strip_prefix("not-found: ") {
+            return Some(Self::NotFound(folder.to_string()));
+        }
+
+        if let Some(progress) = line.strip_prefix("progress: ")
+            && let Some((done, total)) = progress.split_once('/')
+            && let (Ok(done), Ok(total)) = (done.trim().parse(), total.trim().parse())
+        {
+            return Some(Self::Progress { done, total });
+        }
+
+        if let Some(exported) = line.strip_prefix("exported: ")
+            && let Some((index, path)) = exported.split_once('\t')
+            && let Ok(index) = index.trim().parse()
+        {
+            return Some(Self::Exported {
+                index,
+                path: path.to_string(),
+            });
+        }
+
+        None
+    }
+}
+
+/// An Apple Notes exporter that can list folders and export notes.
+///
+/// Use [`Exporter::new()`] for the default embedded script, or
+/// [`Exporter::with_script_path()`] for a custom script.
+///
+/// An exporter is cheap to clone and is `Send + Sync`, so one configured
+/// exporter can be shared with worker threads, e.g. to export several
+/// folders at once. Each export runs its own osascript process.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::thread;
+///
+/// use apple_notes_exporter_rs::Exporter;
+///
+/// let exporter = Exporter::new().with_progress(false);
+/// let handles: Vec<_> = ["Work", "Personal"]
+///     .into_iter()
+///     .map(|folder| {
+///         let exporter = exporter.clone();
+///         thread::spawn(move || exporter.export_folder(folder, format!("./exports/{folder}")))
+///     })
+///     .collect();
+///
+/// for handle in handles {
+///     handle.join().unwrap().expect("Failed to export");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    script_source: ScriptSource,
+    extra_osascript_args: Vec<String>,
+    temp_dir: Option<PathBuf>,
+    script_cache_dir: Option<PathBuf>,
+    max_concurrency: usize,
+    progress: bool,
+    script_output_to_stderr: bool,
+    log_file: Option<PathBuf>,
+    html_transform: Option<HtmlTransform>,
+    folder_name_transform: Option<FolderNameTransform>,
+    osascript: Option<PathBuf>,
+    wait_for_ready: bool,
+    success_codes: Vec<i32>,
+    /// Shared by clones, so Notes is waited for only once
+    ready: Arc<Once>,
+}
+
+/// A function that rewrites a note's HTML; see [`Exporter::with_html_transform`].
+#[derive(Clone)]
+struct HtmlTransform(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl std::fmt::Debug for HtmlTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HtmlTransform(..)")
+    }
+}
+
+/// A function that renames exported folders' directories; see
+/// [`Exporter::with_folder_name_transform`].
+#[derive(Clone)]
+struct FolderNameTransform(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl std::fmt::Debug for FolderNameTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FolderNameTransform(..)")
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ScriptSource {
+    Embedded,
+    Path(PathBuf),
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Exporter {
+    /// Creates a new exporter using the embedded AppleScript.
+    ///
+    /// This is the recommended way to create an exporter for most use cases.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// exporter.list_folders().expect("Failed to list folders");
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            script_source: ScriptSource::Embedded,
+            extra_osascript_args: Vec::new(),
+            temp_dir: None,
+            script_cache_dir: None,
+            max_concurrency: 1,
+            progress: false,
+            script_output_to_stderr: false,
+            log_file: None,
+            html_transform: None,
+            folder_name_transform: None,
+            osascript: None,
+            wait_for_ready: true,
+            success_codes: Vec::new(),
+            ready: Arc::new(Once::new()),
+        }
+    }
+
+    /// Creates a new exporter using a custom AppleScript at the specified path.
+    ///
+    /// Returns an error if the script file does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::with_script_path("./custom_script.applescript")
+    ///     .expect("Script not found");
+    /// ```
+    pub fn with_script_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            return Err(ExportError::ScriptNotFound(path));
+        }
+        Ok(Self {
+            script_source: ScriptSource::Path(path),
+            ..Self::new()
+        })
+    }
+
+    /// Checks that the script answers the commands this crate sends it, so an
+    /// incompatible custom script (see [`with_script_path`](Self::with_script_path))
+    /// is caught before an export fails partway through.
+    ///
+    /// Runs `list`, and `exists` with a folder name no one uses, which must
+    /// print `false`; see [Script Commands](crate#script-commands). Nothing is
+    /// exported. Both need Notes and Automation permission, which
+    /// [`diagnose`](Self::diagnose) checks.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExportError::IncompatibleScript`] describing the command
+    /// that failed or printed something unexpected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::fs;
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// use apple_notes_exporter_rs::{ExportError, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// exporter.validate_script().unwrap();
+    ///
+    /// // A stand-in for a script that prints the same thing for every command
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let script = dir.path().join("osascript");
+    /// fs::write(&script, "#!/bin/sh\necho 'Hello from my script'\n").unwrap();
+    /// fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    ///
+    /// let error = Exporter::new().with_osascript_path(&script).validate_script().unwrap_err();
+    /// assert!(matches!(error, ExportError::IncompatibleScript(_)));
+    /// assert!(error.to_string().contains("`exists`"));
+    /// # }
+    /// ```
+    pub fn validate_script(&self) -> Result<()> {
+        let probe = |args: &[&str]| {
+            self.run_script_capture(args).map_err(|error| match error {
+                ExportError::ScriptFailed(_)
+                | ExportError::UnexpectedOutput(_)
+                | ExportError::OutputNotUtf8(_) => {
+                    ExportError::IncompatibleScript(format!("`{}` failed: {error}", args[0]))
+                }
+                error => error,
+            })
+        };
+
+        probe(&["list"])?;
+
+        let output = probe(&["exists", "apple-notes-exporter validation probe"])?;
+        if output.trim() != "false" {
+            return Err(ExportError::IncompatibleScript(format!(
+                "`exists` printed {:?} instead of `false`",
+                output.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Passes extra arguments to osascript, before the script path.
+    ///
+    /// This is mostly useful for debugging, e.g. `-s s` to print results in
+    /// recompilable source form. No extra arguments are passed by default.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new()
+    ///     .with_extra_osascript_args(vec!["-s".to_string(), "s".to_string()]);
+    /// exporter.list_folders().expect("Failed to list folders");
+    /// ```
+    pub fn with_extra_osascript_args(mut self, args: Vec<String>) -> Self {
+        self.extra_osascript_args = args;
+        self
+    }
+
+    /// Sets the directory for temporary files, such as the embedded script
+    /// written out for osascript.
+    ///
+    /// Useful when the system temp directory (`$TMPDIR`) is not writable.
+    /// Defaults to the system temp directory.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_temp_dir("/Volumes/Scratch/tmp");
+    /// exporter.list_folders().expect("Failed to list folders");
+    /// ```
+    pub fn with_temp_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Writes the embedded script to `dir` once and runs it from there,
+    /// instead of writing it to a new temporary file for every command.
+    ///
+    /// Saves rewriting the script for each folder of a batch export. The file
+    /// is named after a hash of the script, so exporters, including ones in
+    /// other processes or from other versions of this crate, can share a
+    /// directory such as `~/Library/Caches/apple-notes-exporter`. It is
+    /// created if missing. Has no effect with a custom script from
+    /// [`with_script_path`](Self::with_script_path). Off by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let cache = tempfile::tempdir().unwrap();
+    /// let exporter = Exporter::new()
+    ///     .with_osascript_path("fixtures/fake-osascript")
+    ///     .with_script_cache_dir(cache.path());
+    ///
+    /// exporter.list_notes("Recipes").unwrap();
+    /// exporter.list_notes("Work").unwrap();
+    ///
+    /// let scripts: Vec<_> = std::fs::read_dir(cache.path()).unwrap().collect();
+    /// assert_eq!(scripts.len(), 1);
+    /// # }
+    /// ```
+    pub fn with_script_cache_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.script_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets how many scripts may run at once when exporting several folders,
+    /// as in [`export_all`](Self::export_all).
+    ///
+    /// Too many concurrent Apple events can destabilize the Notes app, so this
+    /// defaults to 1 (one folder at a time). Values of 2-4 can speed up large
+    /// exports on fast machines. Zero is treated as 1.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_max_concurrency(2);
+    /// exporter.export_all("./exports").expect("Failed to export");
+    /// ```
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Prints an `Exported N/M notes` progress line to stderr while exporting.
+    ///
+    /// The line is updated in place as the script reports its progress. Off
+    /// by default. Use [`export_folder_with_progress`](Self::export_folder_with_progress)
+    /// to report progress some other way.
+    ///
+    /// Needs a script that prints `progress:` lines, which the embedded script
+    /// doesn't; see [Script Output](crate#script-output).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_progress(true);
+    /// exporter.export_folder("My Notes", "./exports").expect("Failed to export");
+    /// ```
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Forwards the lines the script prints that aren't
+    /// [structured output](crate#script-output) to stderr instead of stdout.
+    ///
+    /// Off by default. Turn it on when stdout has to hold only what the
+    /// caller prints, such as a JSON summary another program parses.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_script_output_to_stderr(true);
+    /// let summary = exporter.export_folder("My Notes", "./exports").expect("Failed to export");
+    /// ```
+    pub fn with_script_output_to_stderr(mut self, to_stderr: bool) -> Self {
+        self.script_output_to_stderr = to_stderr;
+        self
+    }
+
+    /// Waits for the Notes app to answer consistently before running the
+    /// first command.
+    ///
+    /// Notes that was just launched can report incomplete accounts and
+    /// folders, so by default a trivial query is repeated, with short delays,
+    /// until it gets the same answer twice. This happens once per exporter
+    /// and its clones. Pass `false` to skip it when Notes is known to be
+    /// running already.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_wait_for_ready(false);
+    /// exporter.list_folders().expect("Failed to list folders");
+    /// ```
+    pub fn with_wait_for_ready(mut self, wait_for_ready: bool) -> Self {
+        self.wait_for_ready = wait_for_ready;
+        self
+    }
+
+    /// Treats the script exiting with any of `codes` as success, besides 0.
+    ///
+    /// For custom scripts (see [`with_script_path`](Self::with_script_path))
+    /// that exit with a nonzero status to report something that isn't fatal,
+    /// e.g. that some notes were skipped. The exit status of an export is
+    /// returned in [`ExportSummary::exit_code`] either way, or by
+    /// [`NoteStream::exit_code`] for [`export_folder_iter`](Self::export_folder_iter).
+    /// Any other nonzero
+    /// status fails with [`ExportError::ScriptFailed`], as by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::fs;
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// use apple_notes_exporter_rs::{ExportError, ExportOptions, Exporter};
+    ///
+    /// // A stand-in for a script that exports the folder and then exits with 3
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let script = dir.path().join("osascript");
+    /// fs::write(
+    ///     &script,
+    ///     "#!/bin/sh\nfixtures/fake-osascript \"$@\" || exit\n[ \"$1\" = -e ] || exit 3\n",
+    /// )
+    /// .unwrap();
+    /// fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    ///
+    /// let exporter = Exporter::new().with_osascript_path(&script);
+    /// let error = exporter.export_folder("Recipes", dir.path().join("strict")).unwrap_err();
+    /// assert!(matches!(error, ExportError::ScriptFailed(3)));
+    ///
+    /// let exporter = exporter.with_success_codes(&[3]);
+    /// let summary = exporter
+    ///     .export_folder_with_options("Recipes", dir.path().join("lenient"), &ExportOptions::default())
+    ///     .unwrap();
+    /// assert_eq!(summary.exit_code, Some(3));
+    /// assert_eq!(summary.actual_notes, 2);
+    ///
+    /// let mut stream = exporter.export_folder_iter("Recipes").unwrap();
+    /// assert!(stream.by_ref().all(|note| note.is_ok()));
+    /// assert_eq!(stream.exit_code(), Some(3));
+    /// # }
+    /// ```
+    pub fn with_success_codes(mut self, codes: &[i32]) -> Self {
+        self.success_codes = codes.to_vec();
+        self
+    }
+
+    /// Rewrites each exported note's HTML with `transform` before it is
+    /// written to the output directory.
+    ///
+    /// The transform runs on every note file once the script has exported
+    /// it, after the post-processing chosen in [`ExportOptions`] and before
+    /// embedded images are extracted. This is the place to add a header,
+    /// strip markup, or link a stylesheet without walking the output
+    /// directory afterwards. No transform is applied by default.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_html_transform(|html| {
+    ///     html.replace("<head>", r#"<head><link rel="stylesheet" href="notes.css">"#)
+    /// });
+    /// exporter.export_folder("My Notes", "./exports").expect("Failed to export");
+    /// ```
+    pub fn with_html_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.html_transform = Some(HtmlTransform(Arc::new(transform)));
+        self
+    }
+
+    /// Names the directory of each exported folder with `transform`, which
+    /// is given the directory name the script chose, e.g. to use kebab-case,
+    /// lowercase, or ASCII-only names. Together with
+    /// [`ExportOptions::filename_template`] this decides every path in an
+    /// export.
+    ///
+    /// The transform applies to every folder's directory, including the
+    /// exported folder's own, after
+    /// [`collapse_single_child`](ExportOptions::collapse_single_child) has
+    /// joined any names. It does not apply to [`ExportLayout::ByDate`]
+    /// directories or attachment folders. A directory keeps its name if the
+    /// transform returns an empty name, one with a path separator, or one
+    /// already taken. Directory names are used as they are by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new()
+    ///     .with_osascript_path("fixtures/fake-osascript")
+    ///     .with_folder_name_transform(|name| name.to_lowercase().replace(' ', "-"));
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// exporter.export_folder("Recipes", dir.path()).unwrap();
+    /// assert!(dir.path().join("recipes/Pancakes -- x-coredata-p101.html").exists());
+    /// assert!(dir.path().join("recipes/desserts/Brownies -- x-coredata-p102.html").exists());
+    /// # }
+    /// ```
+    pub fn with_folder_name_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.folder_name_transform = Some(FolderNameTransform(Arc::new(transform)));
+        self
+    }
+
+    /// Runs `program` instead of `osascript`.
+    ///
+    /// The program is called with the same arguments osascript would be, and
+    /// the macOS platform check is skipped, so exports can run anywhere. The
+    /// crate ships `fixtures/fake-osascript`, a shell script that serves notes
+    /// from a fixtures directory instead of the Notes app, to test the export
+    /// pipeline without Notes. Uses `osascript` from `PATH` by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let summary = exporter
+    ///     .export_folder_with_options("iCloud:Recipes", dir.path(), &ExportOptions::default())
+    ///     .unwrap();
+    /// assert_eq!(summary.matched_path, ["iCloud", "Recipes"]);
+    /// assert_eq!(summary.actual_notes, 2);
+    /// assert!(dir.path().join("Recipes/Desserts/Brownies -- x-coredata-p102.html").exists());
+    ///
+    /// assert!(exporter.folder_exists("@local:Journal").unwrap());
+    /// assert_eq!(exporter.count_notes("Recipes").unwrap(), 2);
+    /// # }
+    /// ```
+    pub fn with_osascript_path<P: Into<PathBuf>>(mut self, program: P) -> Self {
+        self.osascript = Some(program.into());
+        self
+    }
+
+    /// Copies everything osascript prints to a log file, for troubleshooting.
+    ///
+    /// Each line of the script's stdout and stderr is appended to `path` with
+    /// a timestamp and the stream it came from, along with the command line
+    /// and exit status of each run. The output is still handled and printed
+    /// as usual. The file is created if needed and appended to otherwise.
+    ///
+    /// Problems that don't fail an export, such as an attachment the script
+    /// can't read, are logged here as `warning` lines.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_log_file("./export.log");
+    /// exporter.export_folder("My Notes", "./exports").expect("Failed to export");
+    /// ```
+    pub fn with_log_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.log_file = Some(path.into());
+        self
+    }
+
+    /// Lists all available top-level folders across all Apple Notes accounts.
+    ///
+    /// The output is printed to stdout by the AppleScript.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// exporter.list_folders().expect("Failed to list folders");
+    /// ```
+    pub fn list_folders(&self) -> Result<()> {
+        self.run_script(&["list"], None, |_| {})?;
+        Ok(())
+    }
+
+    /// Exports a folder recursively to HTML files.
+    ///
+    /// The folder search uses breadth-first search and looks at all levels
+    /// (not just top-level) to find the folder. Once found, it exports that
+    /// folder and all its subfolders recursively.
+    ///
+    /// This method searches all accounts for the folder. If a folder with the
+    /// same name exists in multiple accounts, use [`export_folder_from_account`](Self::export_folder_from_account)
+    /// to specify which account to use.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder name to export.
+    /// * `output_dir` - The directory where exported notes will be saved.
+    ///   Will be created if it doesn't exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// exporter.export_folder("My Notes", "./exports").expect("Failed to export");
+    /// ```
+    pub fn export_folder<P: AsRef<Path>>(&self, folder: &str, output_dir: P) -> Result<()> {
+        self.export_folder_impl(
+            &parse_folder_spec(folder),
+            output_dir,
+            &ExportOptions::default(),
+        )?;
+        Ok(())
+    }
+
+    /// Exports a folder recursively to HTML files using the given options.
+    ///
+    /// The folder may be given as `"AccountName:FolderName"` to restrict the
+    /// search to a single account; see [`parse_folder_spec`] for the rules.
+    ///
+    /// Notes are first exported into a hidden staging directory inside
+    /// `output_dir` and then moved into place, so files that already exist
+    /// are handled according to [`ExportOptions::on_existing`]. With
+    /// [`OnExisting::Error`], nothing is moved if any exported file would
+    /// replace an existing one.
+    ///
+    /// Returns an [`ExportSummary`] describing what was exported.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::{ExportOptions, Exporter, OnExisting};
+    ///
+    /// let exporter = Exporter::new();
+    /// let options = ExportOptions {
+    ///     on_existing: OnExisting::Skip,
+    ///     ..Default::default()
+    /// };
+    /// let summary = exporter.export_folder_with_options("My Notes", "./exports", &options)
+    ///     .expect("Failed to export");
+    ///
+    /// println!("Exported {}", summary.matched_path.join(" / "));
+    /// ```
+    pub fn export_folder_with_options<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        output_dir: P,
+        options: &ExportOptions,
+    ) -> Result<ExportSummary> {
+        self.export_folder_impl(&parse_folder_spec(folder), output_dir, options)
+    }
+
+    /// Exports a folder from a specific account recursively to HTML files.
+    ///
+    /// This is useful when a folder with the same name exists in multiple accounts.
+    /// The folder search uses breadth-first search and looks at all levels
+    /// (not just top-level) to find the folder within the specified account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account name (e.g., "iCloud", "Google"), or
+    ///   [`AccountSelector::LocalAccount`] for the on-device account.
+    /// * `folder` - The folder name to export.
+    /// * `output_dir` - The directory where exported notes will be saved.
+    ///   Will be created if it doesn't exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    ///
+    /// // Export "Work" folder from iCloud account
+    /// exporter.export_folder_from_account("iCloud", "Work", "./exports")
+    ///     .expect("Failed to export");
+    ///
+    /// // Export "Work" folder from Google account
+    /// exporter.export_folder_from_account("Google", "Work", "./google_exports")
+    ///     .expect("Failed to export");
+    /// ```
+    pub fn export_folder_from_account<A: Into<AccountSelector>, P: AsRef<Path>>(
+        &self,
+        account: A,
+        folder: &str,
+        output_dir: P,
+    ) -> Result<()> {
+        let folder = account.into().folder(folder);
+        self.export_folder_impl(&folder, output_dir, &ExportOptions::default())?;
+        Ok(())
+    }
+
+    /// Exports the folder at an exact path in an account recursively to HTML
+    /// files.
+    ///
+    /// Unlike [`export_folder_from_account`](Self::export_folder_from_account),
+    /// which exports the shallowest folder with a given name, this follows
+    /// `path` from the account root one folder name at a time, so folders
+    /// with the same name in different places can't be confused. Fails with
+    /// [`ExportError::FolderNotFound`] if any folder along the path is missing.
+    ///
+    /// Needs a script that takes exact folder paths, which the embedded script
+    /// doesn't; see [Script Commands](crate#script-commands).
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account name (e.g., "iCloud", "Google"), or
+    ///   [`AccountSelector::LocalAccount`] for the on-device account.
+    /// * `path` - The folder names from the account's top level down to the
+    ///   folder to export, e.g. `["Work", "Projects", "2025"]`.
+    /// * `output_dir` - The directory where exported notes will be saved.
+    ///   Will be created if it doesn't exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportError, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// exporter
+    ///     .export_folder_by_path("iCloud", &["Recipes", "Desserts"], dir.path())
+    ///     .unwrap();
+    /// assert!(dir.path().join("Desserts/Brownies -- x-coredata-p102.html").exists());
+    ///
+    /// // "Desserts" is not a top-level folder
+    /// let error = exporter
+    ///     .export_folder_by_path("iCloud", &["Desserts"], dir.path())
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ExportError::FolderNotFound(_)));
+    /// # }
+    /// ```
+    ///
+    /// A subfolder with the same name as its parent is exported into its own
+    /// directory, inside the parent's when the parent is exported:
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{AccountSelector, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// // The search finds the top-level "Projects" first
+    /// exporter.export_folder("@local:Projects", dir.path().join("parent")).unwrap();
+    /// assert!(dir.path().join("parent/Projects/Plan -- x-coredata-p108.html").is_file());
+    /// assert!(dir.path().join("parent/Projects/Projects/Roadmap -- x-coredata-p109.html").is_file());
+    /// assert!(!dir.path().join("parent/Projects/Roadmap -- x-coredata-p109.html").exists());
+    /// assert!(!dir.path().join("parent/Projects/Projects/Plan -- x-coredata-p108.html").exists());
+    ///
+    /// exporter
+    ///     .export_folder_by_path(AccountSelector::LocalAccount, &["Projects", "Projects"], dir.path().join("child"))
+    ///     .unwrap();
+    /// assert!(dir.path().join("child/Projects/Roadmap -- x-coredata-p109.html").is_file());
+    /// assert!(!dir.path().join("child/Projects/Plan -- x-coredata-p108.html").exists());
+    /// # }
+    /// ```
+    pub fn export_folder_by_path<A: Into<AccountSelector>, P: AsRef<Path>>(
+        &self,
+        account: A,
+        path: &[&str],
+        output_dir: P,
+    ) -> Result<()> {
+        let folder = account.into().folder_path(path);
+        self.export_folder_impl(&folder, output_dir, &ExportOptions::default())?;
+        Ok(())
+    }
+
+    fn export_folder_impl<P: AsRef<Path>>(
+        &self,
+        folder: &FolderSpec,
+        output_dir: P,
+        options: &ExportOptions,
+    ) -> Result<ExportSummary> {
+        self.export_folder_reporting(folder, output_dir.as_ref(), options, None)
+    }
+
+    /// Exports a folder, reporting progress to `reporter`, or as set with
+    /// [`with_progress`](Self::with_progress) if there is none.
+    fn export_folder_reporting(
+        &self,
+        folder: &FolderSpec,
+        output_dir: &Path,
+        options: &ExportOptions,
+        reporter: Option<&mut dyn ProgressReporter>,
+    ) -> Result<ExportSummary> {
+        let mut stderr_progress = StderrProgress::new();
+        let mut no_progress = NoProgress;
+        let reporter: &mut dyn ProgressReporter = match reporter {
+            Some(reporter) => reporter,
+            None if self.progress => &mut stderr_progress,
+            None => &mut no_progress,
+        };
+
+        fs::create_dir_all(output_dir)
+            .map_err(|error| ExportError::OutputNotWritable(output_dir.to_path_buf(), error))?;
+
+        let output_dir = output_dir.canonicalize()?;
+        check_writable(&output_dir)?;
+
+        // Read a custom stylesheet before the script runs, so a bad path fails fast
+        let css = options.stylesheet.css()?;
+        options.layout.validate()?;
+        filename_template::validate(&options.filename_template)?;
+        filename_template::validate_date_format(&options.date_format)?;
+        if options.index_prefix && self.runs_embedded_script() {
+            return Err(ExportError::UnsupportedByScript(
+                "`exported:` lines, which index_prefix needs".to_string(),
+            ));
+        }
+        if options.wait_for_sync && self.runs_embedded_script() {
+            return Err(ExportError::UnsupportedByScript(
+                "the `note-html` command, which wait_for_sync needs".to_string(),
+            ));
+        }
+        if self.runs_embedded_script()
+            && !options.include_recently_deleted
+            && folder.folder == RECENTLY_DELETED
+        {
+            return Err(ExportError::FolderNotFound(folder.script_arg()));
+        }
+
+        // Export into a staging directory on the same filesystem so the
+        // results can be moved into place according to the overwrite policy.
+        let staging = tempfile::Builder::new()
+            .prefix(".apple-notes-export-")
+            .tempdir_in(&output_dir)?;
+        let staging_str = staging
+            .path()
+            .to_str()
+            .ok_or(ExportError::InvalidUtf8Path)?;
+
+        let mut summary = ExportSummary::default();
+        let mut not_found = None;
+        let mut note_indexes = HashMap::new();
+        let folder_spec = folder.script_arg();
+        let mut args = vec!["export", folder_spec.as_str(), staging_str];
+        if self.runs_embedded_script() {
+            // It always exports recursively, so subfolders are removed below
+            args.extend(options.pinned_only.then_some("--pinned-only"));
+        } else {
+            args.extend(options.selection_flags());
+        }
+        let result = self.run_script(&args, options.cancellation.as_ref(), |line| match line {
+            ScriptLine::Matched(path) => summary.matched_path = path,
+            ScriptLine::Failed(failure) => summary.failed_notes.push(failure),
+            ScriptLine::Progress { done, total } => {
+                reporter.on_note(&ExportProgress { done, total });
+            }
+            ScriptLine::NotFound(folder) => not_found = Some(folder),
+            ScriptLine::Exported { index, path } => {
+                note_indexes.insert(PathBuf::from(path), index);
+            }
+        });
+
+        if let Some(folder) = not_found {
+            return Err(ExportError::FolderNotFound(folder));
+        }
+
+        // Keep whatever was exported before a cancellation
+        let cancelled = match result {
+            Ok(code) => {
+                summary.exit_code = Some(code);
+                false
+            }
+            Err(ExportError::Cancelled) => true,
+            Err(error) => return Err(error),
+        };
+
+        if !options.recursive {
+            remove_subfolders(staging.path())?;
+        }
+
+        if options.wait_for_sync && !cancelled {
+            let placeholders = self.remove_placeholders(
+                &folder_spec,
+                staging.path(),
+                options.cancellation.as_ref(),
+            )?;
+            summary.failed_notes.extend(placeholders);
+        }
+
+        self.inline_object_attachments_in_directory(staging.path())?;
+
+        summary.actual_notes = count_html_files(staging.path())?;
+        if options.note_count_check != NoteCountCheck::Off && !cancelled {
+            summary.expected_notes =
+                Some(self.count_notes_impl(&folder_spec, &options.selection_flags())?);
+        }
+
+        // Read the note metadata once for every step that needs it
+        let by_date = matches!(options.layout, ExportLayout::ByDate { .. });
+        let dates = if by_date
+            || (options.preserve_timestamps && !cancelled)
+            || options.mark_shared_notes
+            || filename_template::needs_dates(&options.filename_template)
+        {
+            markdown::parse_note_dates(&self.run_script_capture(&["note-metadata", &folder_spec])?)
+        } else {
+            HashMap::new()
+        };
+
+        if !options.title_filter.is_empty() {
+            title_filter::filter_directory(staging.path(), &options.title_filter)?;
+        }
+
+        if options.index_prefix {
+            prefix_note_indexes(staging.path(), Path::new(""), &note_indexes)?;
+        }
+
+        let staging = match &options.layout {
+            ExportLayout::Folders => staging,
+            ExportLayout::ByDate { pattern } => {
+                let by_date = tempfile::Builder::new()
+                    .prefix(".apple-notes-export-")
+                    .tempdir_in(&output_dir)?;
+                layout::arrange_by_date(staging.path(), by_date.path(), pattern, &dates)?;
+                by_date
+            }
+        };
+
+        if options.prune_empty {
+            summary.pruned_dirs = prune_empty_dirs(staging.path())?;
+        }
+
+        // Written before collapsing, while each directory is still one folder
+        if options.folder_metadata
+            && options.layout == ExportLayout::Folders
+            && let Some((_, parent)) = summary.matched_path.split_last()
+        {
+            let ids = folder_metadata::parse_folder_ids(
+                &self.run_script_capture(&["folder-metadata", &folder_spec])?,
+            );
+            folder_metadata::write_directory(staging.path(), parent, &ids)?;
+        }
+
+        if options.collapse_single_child {
+            collapse_single_child_dirs(staging.path())?;
+        }
+
+        // After collapsing, so the transform sees the final names
+        if let Some(FolderNameTransform(transform)) = &self.folder_name_transform
+            && options.layout == ExportLayout::Folders
+        {
+            rename_folder_dirs(staging.path(), transform.as_ref())?;
+        }
+
+        if options.export_checklists_as_inputs {
+            checklists::convert_directory(staging.path())?;
+        }
+
+        if options.clean_html {
+            cleanup::clean_directory(staging.path())?;
+        }
+
+        if options.mark_shared_notes {
+            sharing::mark_directory(staging.path(), &dates)?;
+        }
+
+        if let Some(css) = &css {
+            stylesheet::inject_directory(staging.path(), css)?;
+        }
+
+        if let Some(HtmlTransform(transform)) = &self.html_transform {
+            transform_html_files(staging.path(), transform.as_ref())?;
+        }
+
+        // After the transform, so the BOM stays at the very start
+        if options.utf8_bom {
+            encoding::mark_directory(staging.path())?;
+        }
+
+        if options.normalize_line_endings {
+            encoding::normalize_directory_line_endings(staging.path())?;
+        }
+
+        // Set last, after every step that rewrites the notes
+        if options.preserve_timestamps && !cancelled {
+            timestamps::apply_note_dates(staging.path(), &dates)?;
+        }
+
+        // Renamed after every step that finds notes by the id in their name
+        if options.filename_template != DEFAULT_FILENAME_TEMPLATE {
+            filename_template::rename_notes(
+                staging.path(),
+                &options.filename_template,
+                &options.date_format,
+                &dates,
+                options.index_prefix,
+            )?;
+        }
+
+        if let Some(max_len) = options.max_path_len {
+            truncate_note_names(
+                staging.path(),
+                &output_dir,
+                Path::new(""),
+                max_len,
+                &mut summary.truncated_notes,
+            )?;
+        }
+
+        // Written after the notes have their final names
+        if options.text_twin {
+            text_twin::write_directory(staging.path(), options.preserve_timestamps && !cancelled)?;
+        }
+
+        if let Some(limit) = options.large_note_bytes {
+            find_large_notes(
+                staging.path(),
+                Path::new(""),
+                limit,
+                &mut summary.large_notes,
+            )?;
+        }
+        placeholders::find_placeholders(staging.path(), Path::new(""), &mut summary.empty_notes)?;
+
+        if options.on_existing == OnExisting::Error
+            && let Some(existing) = find_existing_output(staging.path(), &output_dir)?
+        {
+            return Err(ExportError::OutputExists(existing));
+        }
+
+        let mut created_dirs = Vec::new();
+        move_staged_output(
+            staging.path(),
+            &output_dir,
+            options.on_existing,
+            &mut created_dirs,
+        )?;
+        summary.created_dirs = created_dirs
+            .into_iter()
+            .filter_map(|dir| dir.strip_prefix(&output_dir).ok().map(Path::to_path_buf))
+            .collect();
+
+        if cancelled {
+            return Err(ExportError::Cancelled);
+        }
+
+        if options.write_provenance {
+            self.write_provenance(&output_dir, folder, &summary)?;
+        }
+
+        #[cfg(feature = "git")]
+        if options.git_commit {
+            git::commit_export(&output_dir, &folder.to_string())?;
+        }
+
+        // Checked last so a short export is still kept on disk
+        let accounted = summary.actual_notes + summary.failed_notes.len();
+        if let Some(expected) = summary.expected_notes
+            && accounted < expected
+            && options.note_count_check == NoteCountCheck::Error
+        {
+            return Err(ExportError::NoteCountMismatch {
+                expected,
+                actual: accounted,
+            });
+        }
+
+        reporter.on_done(&summary);
+        Ok(summary)
+    }
+
+    /// Exports a folder and extracts all embedded images to attachment folders.
+    ///
+    /// This combines [`export_folder`](Self::export_folder) with
+    /// [`extract_attachments_from_directory`] for convenience.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder name to export.
+    /// * `output_dir` - The directory where exported notes will be saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of `ExtractionResult` for each HTML file processed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// let results = exporter.export_folder_with_attachments("My Notes", "./exports")
+    ///     .expect("Failed to export");
+    ///
+    /// let total: usize = results.iter().map(|r| r.attachments.len()).sum();
+    /// println!("Extracted {total} attachments");
+    /// ```
+    ///
+    /// Images a note references by `x-coredata:` object URL instead of
+    /// embedding them are read from Notes during the export, so they are
+    /// extracted too:
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// // "Monday" has an <img> and an <object> that reference attachments
+    /// let results = exporter
+    ///     .export_folder_with_attachments("@local:Journal", dir.path())
+    ///     .unwrap();
+    /// let mime_types: Vec<_> = results[0].attachments.iter().map(|a| a.mime_type.as_str()).collect();
+    /// assert_eq!(mime_types, ["image/png", "image/jpeg"]);
+    ///
+    /// let html = std::fs::read_to_string(&results[0].html_path).unwrap();
+    /// assert!(!html.contains("x-coredata://") && !html.contains("<object"));
+    /// # }
+    /// ```
+    pub fn export_folder_with_attachments<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        output_dir: P,
+    ) -> Result<Vec<ExtractionResult>> {
+        self.export_folder(folder, &output_dir)?;
+        extract_attachments_from_directory(&output_dir)
+    }
+
+    /// Exports a folder from a specific account and extracts all embedded images.
+    ///
+    /// This combines [`export_folder_from_account`](Self::export_folder_from_account) with
+    /// [`extract_attachments_from_directory`] for convenience.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The account name (e.g., "iCloud", "Google"), or
+    ///   [`AccountSelector::LocalAccount`] for the on-device account.
+    /// * `folder` - The folder name to export.
+    /// * `output_dir` - The directory where exported notes will be saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of `ExtractionResult` for each HTML file processed.
+    pub fn export_folder_from_account_with_attachments<A: Into<AccountSelector>, P: AsRef<Path>>(
+        &self,
+        account: A,
+        folder: &str,
+        output_dir: P,
+    ) -> Result<Vec<ExtractionResult>> {
+        self.export_folder_impl(
+            &account.into().folder(folder),
+            &output_dir,
+            &ExportOptions::default(),
+        )?;
+        extract_attachments_from_directory(&output_dir)
+    }
+
+    /// Exports every top-level folder of every account, recursively.
+    ///
+    /// Each folder is exported into `output_dir/<account>/<folder>/...`, so
+    /// folders with the same name in different accounts do not collide.
+    ///
+    /// Returns the summary of each folder's export, paired with the folder.
+    /// Up to [`with_max_concurrency`](Self::with_max_concurrency) folders are
+    /// exported at once.
+    ///
+    /// The "Recently Deleted" folder is skipped; see
+    /// [`ExportOptions::include_recently_deleted`]. Any other folder the
+    /// script can't find, e.g. one renamed since it was listed, fails the
+    /// export with [`ExportError::FolderNotFound`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let exported = exporter.export_all(dir.path()).expect("Failed to export");
+    ///
+    /// let names: Vec<_> = exported.iter().map(|(folder, _)| folder.spec()).collect();
+    /// assert_eq!(names, ["On My Mac:Journal", "On My Mac:Projects", "iCloud:Recipes", "iCloud:Work"]);
+    /// assert!(dir.path().join("iCloud/Recipes").is_dir());
+    /// # }
+    /// ```
+    pub fn export_all<P: AsRef<Path>>(
+        &self,
+        output_dir: P,
+    ) -> Result<Vec<(FolderInfo, ExportSummary)>> {
+        let output_dir = output_dir.as_ref();
+        let options = ExportOptions::default();
+
+        // The listing can't tell "Recently Deleted" apart, so skip it by name
+        let mut folders = self.list_folders_structured()?;
+        if !options.include_recently_deleted {
+            folders.retain(|folder| folder.name != RECENTLY_DELETED);
+        }
+
+        let summaries = self.run_bounded(&folders, |folder| {
+            let account_dir = output_dir.join(&folder.account);
+            self.export_folder_with_options(&folder.spec(), account_dir, &options)
+        })?;
+
+        Ok(folders.into_iter().zip(summaries).collect())
+    }
+
+    /// Calls `f` for each item, running up to `max_concurrency` calls at once,
+    /// and returns the results in order.
+    ///
+    /// No new calls are started after one fails, and the first error is returned.
+    fn run_bounded<T: Sync, R: Send>(
+        &self,
+        items: &[T],
+        f: impl Fn(&T) -> Result<R> + Sync,
+    ) -> Result<Vec<R>> {
+        let workers = self.max_concurrency.min(items.len());
+        if workers <= 1 {
+            return items.iter().map(f).collect();
+        }
+
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let slots: Mutex<Vec<Option<Result<R>>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    while !failed.load(Ordering::SeqCst) {
+                        let index = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+
+                        let result = f(item);
+                        if result.is_err() {
+                            failed.store(true, Ordering::SeqCst);
+                        }
+                        slots.lock().unwrap()[index] = Some(result);
+                    }
+                });
+            }
+        });
+
+        // Items that were never started are skipped; they only exist after a failure
+        slots.into_inner().unwrap().into_iter().flatten().collect()
+    }
+
+    /// Returns whether a folder can be found, without exporting it.
+    ///
+    /// The folder is looked up the same way as in
+    /// [`export_folder`](Self::export_folder), so this is a cheap way to
+    /// validate a folder name before starting an export.
+    ///
+    /// Needs a script with the `exists` command, which the embedded script
+    /// lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// if !exporter.folder_exists("My Notes").expect("Failed to look up folder") {
+    ///     eprintln!("No folder named \"My Notes\"");
+    /// }
+    /// ```
+    pub fn folder_exists(&self, folder: &str) -> Result<bool> {
+        self.folder_exists_impl(&parse_folder_spec(folder))
+    }
+
+    fn folder_exists_impl(&self, folder: &FolderSpec) -> Result<bool> {
+        let output = self.run_script_capture(&["exists", &folder.script_arg()])?;
+        Ok(output.trim() == "true")
+    }
+
+    /// Returns the number of notes in a folder and all its subfolders.
+    ///
+    /// The folder is looked up the same way as in
+    /// [`export_folder`](Self::export_folder).
+    ///
+    /// Needs a script with the `count` command, which the embedded script
+    /// lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let count = Exporter::new().count_notes("My Notes").expect("Failed to count notes");
+    /// println!("{count} notes");
+    /// ```
+    pub fn count_notes(&self, folder: &str) -> Result<usize> {
+        self.count_notes_impl(&parse_folder_spec(folder).script_arg(), &[])
+    }
+
+    /// Counts the notes in a folder, passing `flags` from
+    /// [`ExportOptions::selection_flags`] to select the same notes as an export.
+    fn count_notes_impl(&self, folder_spec: &str, flags: &[&str]) -> Result<usize> {
+        let mut args = vec!["count", folder_spec];
+        args.extend(flags);
+
+        let output = self.run_script_capture(&args)?;
+        let output = output.trim();
+        output
+            .parse()
+            .map_err(|_| ExportError::UnexpectedOutput(output.to_string()))
+    }
+
+    /// Returns whether a folder can be found in a specific account, without
+    /// exporting it.
+    ///
+    /// See [`folder_exists`](Self::folder_exists).
+    pub fn folder_exists_in_account<A: Into<AccountSelector>>(
+        &self,
+        account: A,
+        folder: &str,
+    ) -> Result<bool> {
+        self.folder_exists_impl(&account.into().folder(folder))
+    }
+
+    /// Returns the HTML content of a single note without writing any files.
+    ///
+    /// The note is looked up by title (or id) within the given folder, which is
+    /// found the same way as in [`export_folder`](Self::export_folder).
+    ///
+    /// Needs a script with the `note-html` command, which the embedded script
+    /// lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder containing the note (or `"AccountName:FolderName"`).
+    /// * `note` - The note title or id.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// let html = exporter.get_note_html("My Notes", "Shopping List")
+    ///     .expect("Failed to get note");
+    /// println!("{html}");
+    /// ```
+    pub fn get_note_html(&self, folder: &str, note: &str) -> Result<String> {
+        let folder = parse_folder_spec(folder).script_arg();
+        self.run_script_capture(&["note-html", &folder, note])
+    }
+
+    /// Exports just the given notes of a folder again, e.g. the ones a
+    /// previous export reported in [`ExportSummary::failed_notes`] after a
+    /// transient error, instead of exporting the whole folder again.
+    ///
+    /// Each note is exported on its own and written to the path a folder
+    /// export to `output_dir` would use, replacing any existing file, so the
+    /// tree looks as if the original run had succeeded. Checklists are
+    /// converted and the exporter's HTML transform applied as with
+    /// [`ExportOptions::default()`].
+    ///
+    /// Needs a script with the `note-path` and `note-html` commands, which the
+    /// embedded script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder that was exported (or `"AccountName:FolderName"`).
+    /// * `failures` - The titles (or ids) of the notes to export, as in
+    ///   [`FailedNote::note`].
+    /// * `output_dir` - The output directory of the original export.
     ///
     /// # Returns
     ///
-    /// Returns a vector of `ExtractionResult` for each HTML file processed.
-    pub fn export_folder_from_account_with_attachments<P: AsRef<Path>>(
-        &self,
-        account: &str,
-        folder: &str,
-        output_dir: P,
-    ) -> Result<Vec<ExtractionResult>> {
-        self.export_folder_from_account(account, folder, &output_dir)?;
-        extract_attachments_from_directory(&output_dir)
+    /// Returns the notes that failed again, with the reason for each. An
+    /// empty vector means every note was exported.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// let summary = exporter.export_folder_with_options("My Notes", "./exports", &Default::default())
+    ///     .expect("Failed to export");
+    ///
+    /// let failures: Vec<String> = summary.failed_notes.into_iter().map(|f| f.note).collect();
+    /// let still_failed = exporter.retry_failed("My Notes", &failures, "./exports")
+    ///     .expect("Failed to retry");
+    /// println!("{} notes still failed", still_failed.len());
+    /// ```
+    pub fn retry_failed<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        failures: &[String],
+        output_dir: P,
+    ) -> Result<Vec<FailedNote>> {
+        let output_dir = output_dir.as_ref();
+        fs::create_dir_all(output_dir)
+            .map_err(|error| ExportError::OutputNotWritable(output_dir.to_path_buf(), error))?;
+        let output_dir = output_dir.canonicalize()?;
+        check_writable(&output_dir)?;
+
+        let staging = tempfile::Builder::new()
+            .prefix(".apple-notes-export-")
+            .tempdir_in(&output_dir)?;
+
+        let folder = parse_folder_spec(folder).script_arg();
+        let mut still_failed = Vec::new();
+        for note in failures {
+            match self.export_single_note(&folder, note, staging.path()) {
+                Ok(()) => {}
+                Err(
+                    error @ (ExportError::ScriptFailed(_)
+                    | ExportError::UnexpectedOutput(_)
+                    | ExportError::OutputNotUtf8(_)),
+                ) => {
+                    still_failed.push(FailedNote {
+                        note: note.clone(),
+                        reason: error.to_string(),
+                    });
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        self.inline_object_attachments_in_directory(staging.path())?;
+        checklists::convert_directory(staging.path())?;
+        if let Some(HtmlTransform(transform)) = &self.html_transform {
+            transform_html_files(staging.path(), transform.as_ref())?;
+        }
+
+        move_staged_output(
+            staging.path(),
+            &output_dir,
+            OnExisting::Overwrite,
+            &mut Vec::new(),
+        )?;
+
+        Ok(still_failed)
+    }
+
+    /// Writes one note of `folder` (a script argument) under `dir`, at the
+    /// path a folder export would use.
+    fn export_single_note(&self, folder: &str, note: &str, dir: &Path) -> Result<()> {
+        let relative = self.run_script_capture(&["note-path", folder, note])?;
+
+        let relative = note_path(relative.trim())?;
+
+        let html = self.run_script_capture(&["note-html", folder, note])?;
+        let path = dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, html)?;
+
+        Ok(())
+    }
+
+    /// Exports a folder into a single self-contained HTML file.
+    ///
+    /// All notes in the folder are concatenated into one document with a table
+    /// of contents and an anchor per note. Subfolders become nested sections.
+    /// Images are kept inline as data URLs, so the file can be read, shared, or
+    /// printed to PDF from a browser on its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder name to export (or `"AccountName:FolderName"`).
+    /// * `output_path` - The HTML file to write. Parent directories are created
+    ///   if needed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// exporter.export_folder_as_single_html("My Notes", "./my-notes.html")
+    ///     .expect("Failed to export");
+    /// ```
+    pub fn export_folder_as_single_html<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        output_path: P,
+    ) -> Result<()> {
+        let output_path = output_path.as_ref();
+
+        let export_dir = tempfile::tempdir_in(self.temp_dir())?;
+        self.export_folder(folder, export_dir.path())?;
+
+        let html = single_html::render_single_html(export_dir.path(), folder)?;
+
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(output_path, html)?;
+
+        Ok(())
+    }
+
+    /// Exports a folder as an EPUB book.
+    ///
+    /// The folder is exported to HTML and its images are extracted, then
+    /// everything is packaged into a single EPUB file. Each note becomes a
+    /// chapter, ordered by folder and note name, and subfolders become nested
+    /// entries in the book's table of contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder name to export (or `"AccountName:FolderName"`).
+    /// * `epub_path` - The EPUB file to write. Parent directories are created
+    ///   if needed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// exporter.export_folder_as_epub("My Notes", "./my-notes.epub")
+    ///     .expect("Failed to export");
+    /// ```
+    pub fn export_folder_as_epub<P: AsRef<Path>>(&self, folder: &str, epub_path: P) -> Result<()> {
+        let export_dir = tempfile::tempdir_in(self.temp_dir())?;
+        self.export_folder(folder, export_dir.path())?;
+        extract_attachments_from_directory(export_dir.path())?;
+
+        epub::write_epub(export_dir.path(), folder, epub_path.as_ref())
+    }
+
+    /// Exports a folder as Markdown files with YAML frontmatter, ready to
+    /// import into apps like Bear, Joplin, or Obsidian.
+    ///
+    /// Each note becomes a `.md` file in the same place its `.html` file would
+    /// be. Embedded images are extracted into attachment folders next to the
+    /// notes and referenced with Markdown image syntax. The frontmatter keys
+    /// are chosen with [`MarkdownOptions::frontmatter`].
+    ///
+    /// The `created` and `updated` keys need a script with the `note-metadata`
+    /// command, which the embedded script lacks; see
+    /// [Script Commands](crate#script-commands).
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder name to export (or `"AccountName:FolderName"`).
+    /// * `output_dir` - The directory where the Markdown files will be saved.
+    /// * `options` - Which frontmatter keys to write.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::{Exporter, FrontmatterKey, MarkdownOptions};
+    ///
+    /// let options = MarkdownOptions {
+    ///     frontmatter: [FrontmatterKey::Title, FrontmatterKey::Tags].into(),
+    ///     ..Default::default()
+    /// };
+    /// Exporter::new()
+    ///     .export_folder_as_markdown("My Notes", "./markdown", &options)
+    ///     .expect("Failed to export");
+    /// ```
+    pub fn export_folder_as_markdown<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        output_dir: P,
+        options: &MarkdownOptions,
+    ) -> Result<()> {
+        let dates = if options.needs_dates() {
+            let folder = parse_folder_spec(folder).script_arg();
+            markdown::parse_note_dates(&self.run_script_capture(&["note-metadata", &folder])?)
+        } else {
+            Default::default()
+        };
+
+        let export_dir = tempfile::tempdir_in(self.temp_dir())?;
+        self.export_folder(folder, export_dir.path())?;
+        extract_attachments_from_directory(export_dir.path())?;
+
+        markdown::write_markdown_tree(export_dir.path(), output_dir.as_ref(), options, &dates)
+    }
+
+    /// Exports a folder's notes into a single Markdown file, for importing
+    /// the folder as one page into a wiki or Notion.
+    ///
+    /// Each note becomes a `##` section titled with the note's title, in the
+    /// folder's display order, and the document is titled with the folder's
+    /// name. Headings within notes are moved down to fit under their
+    /// sections. Embedded images are extracted into an attachments folder
+    /// next to the file, named after it (`wiki.md` gets `wiki-attachments/`),
+    /// and linked with Markdown image syntax. Subfolders are not included;
+    /// export them separately.
+    ///
+    /// Needs a script that prints `exported:` lines to know the display
+    /// order, which the embedded script lacks; see
+    /// [Script Output](crate#script-output).
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder name to export (or `"AccountName:FolderName"`).
+    /// * `output_path` - The Markdown file to write. Parent directories are
+    ///   created if needed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::fs;
+    ///
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let output_path = dir.path().join("desserts.md");
+    ///
+    /// exporter
+    ///     .export_folder_as_single_markdown("Desserts", &output_path)
+    ///     .unwrap();
+    ///
+    /// let markdown = fs::read_to_string(&output_path).unwrap();
+    /// assert!(markdown.starts_with("# Desserts\n\n## Brownies\n"));
+    /// assert!(markdown.contains("![](desserts-attachments/"));
+    /// assert!(dir.path().join("desserts-attachments").is_dir());
+    /// # }
+    /// ```
+    pub fn export_folder_as_single_markdown<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        output_path: P,
+    ) -> Result<()> {
+        let output_path = output_path.as_ref();
+        let stem = output_path
+            .file_stem()
+            .map_or_else(|| "notes".into(), |stem| stem.to_string_lossy());
+        let assets_name = format!("{stem}{DEFAULT_ATTACHMENTS_SUFFIX}");
+
+        // Index prefixes keep the display order, which names alone lose
+        let options = ExportOptions {
+            recursive: false,
+            index_prefix: true,
+            ..Default::default()
+        };
+        let export_dir = tempfile::tempdir_in(self.temp_dir())?;
+        self.export_folder_impl(&parse_folder_spec(folder), export_dir.path(), &options)?;
+
+        let extract_options = ExtractOptions {
+            url_base: Some(assets_name.clone()),
+            ..Default::default()
+        };
+        extract_attachments_from_directory_with_options(export_dir.path(), &extract_options)?;
+
+        let markdown = markdown::render_single_markdown(export_dir.path(), folder)?;
+
+        let parent = output_path.parent().unwrap_or(Path::new(""));
+        fs::create_dir_all(parent)?;
+        markdown::copy_attachment_dirs(export_dir.path(), &parent.join(&assets_name))?;
+        fs::write(output_path, markdown)?;
+
+        Ok(())
+    }
+
+    /// Exports a folder as RTF and RTFD files, for reopening notes in
+    /// TextEdit or Pages.
+    ///
+    /// Each note becomes an `.rtfd` bundle with its images embedded, or an
+    /// `.rtf` file if it has none, in the same place its `.html` file would
+    /// be. Notes only exposes note bodies as HTML to scripts, so the HTML is
+    /// converted with the macOS `textutil` tool, which uses the same importer
+    /// as TextEdit. A note that cannot be converted is kept as HTML, with its
+    /// images extracted, and a warning is logged; see
+    /// [`with_log_file`](Self::with_log_file).
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder name to export (or `"AccountName:FolderName"`).
+    /// * `output_dir` - The directory where the RTF and RTFD files will be saved.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// Exporter::new()
+    ///     .export_folder_as_rtfd("My Notes", "./rtfd")
+    ///     .expect("Failed to export");
+    /// ```
+    pub fn export_folder_as_rtfd<P: AsRef<Path>>(&self, folder: &str, output_dir: P) -> Result<()> {
+        let export_dir = tempfile::tempdir_in(self.temp_dir())?;
+        self.export_folder(folder, export_dir.path())?;
+        extract_attachments_from_directory(export_dir.path())?;
+
+        rtfd::write_rtfd_tree(export_dir.path(), output_dir.as_ref(), |message| {
+            self.log_warning(message)
+        })
+    }
+
+    /// Appends a warning to the log file, if there is one; see
+    /// [`with_log_file`](Self::with_log_file).
+    fn log_warning(&self, message: &str) {
+        if let Some(path) = &self.log_file
+            && let Ok(log) = session_log::SessionLog::open(path)
+        {
+            log.line("warning", message);
+        }
+    }
+
+    /// The directory for temporary files; see [`with_temp_dir`](Self::with_temp_dir).
+    fn temp_dir(&self) -> PathBuf {
+        self.temp_dir.clone().unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// Checks the platform, unless osascript was replaced with
+    /// [`with_osascript_path`](Self::with_osascript_path).
+    fn check_platform(&self) -> Result<()> {
+        match self.osascript {
+            Some(_) => Ok(()),
+            None => check_platform(),
+        }
+    }
+
+    /// Returns whether the embedded script is run by osascript itself.
+    fn runs_embedded_script(&self) -> bool {
+        matches!(self.script_source, ScriptSource::Embedded) && self.osascript.is_none()
+    }
+
+    /// Checks that the embedded script implements `args`, so a feature it
+    /// lacks fails instead of being ignored by the script.
+    ///
+    /// It only has `list` and `export <folder> <output_dir>`, for folder
+    /// names and `<account>:<folder>` specs. A program replacing osascript
+    /// (see [`with_osascript_path`](Self::with_osascript_path)) stands in
+    /// for the script too, so it is sent everything.
+    fn check_embedded_support(&self, args: &[&str]) -> Result<()> {
+        if !self.runs_embedded_script() {
+            return Ok(());
+        }
+
+        let local_account = format!("{}:", folder_spec::LOCAL_ACCOUNT);
+        let unsupported = match args {
+            ["list"] => return Ok(()),
+            ["export", folder, _] if folder.contains('\t') => "exact folder paths".to_string(),
+            ["export", folder, _] if folder.starts_with(':') => {
+                "folder names with colons".to_string()
+            }
+            ["export", folder, _] if folder.starts_with(&local_account) => {
+                format!("the {} account", folder_spec::LOCAL_ACCOUNT)
+            }
+            ["export", _, _] => return Ok(()),
+            ["export", _, _, option, ..] => format!("the `{option}` option"),
+            [command, ..] => format!("the `{command}` command"),
+            [] => return Ok(()),
+        };
+        Err(ExportError::UnsupportedByScript(unsupported))
+    }
+
+    /// The program run in place of osascript.
+    fn osascript_program(&self) -> &Path {
+        self.osascript.as_deref().unwrap_or(Path::new("osascript"))
+    }
+
+    /// Runs a snippet of AppleScript source and returns what it printed.
+    ///
+    /// On failure, the script's error output is forwarded to stderr.
+    fn eval_applescript(&self, source: &str) -> Result<String> {
+        self.check_platform()?;
+        self.ensure_ready();
+
+        let output = self.osascript_eval(source)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            eprint!("{stderr}");
+            if stderr.contains(NOT_AUTHORIZED_ERROR) {
+                return Err(ExportError::NotAuthorized);
+            }
+            return Err(ExportError::ScriptFailed(
+                output.status.code().unwrap_or(-1),
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Runs a snippet of AppleScript source with `osascript -e`, capturing its output.
+    fn osascript_eval(&self, source: &str) -> Result<Output> {
+        Command::new(self.osascript_program())
+            .args(&self.extra_osascript_args)
+            .arg("-e")
+            .arg(source)
+            .output()
+            .map_err(ExportError::LaunchError)
+    }
+
+    /// Runs the script, passing structured output lines to `on_line` and
+    /// forwarding everything else with [`forward_line`].
+    /// Returns its exit status.
+    fn run_script(
+        &self,
+        args: &[&str],
+        cancel: Option<&CancellationToken>,
+        mut on_line: impl FnMut(ScriptLine),
+    ) -> Result<i32> {
+        self.run_script_raw(args, cancel, |line| match ScriptLine::parse(&line) {
+            Some(script_line) => on_line(script_line),
+            None => forward_line(&line, self.script_output_to_stderr),
+        })
+    }
+
+    /// Runs the script and returns everything it printed to stdout.
+    fn run_script_capture(&self, args: &[&str]) -> Result<String> {
+        let mut output = String::new();
+        self.run_script_raw(args, None, |line| {
+            output.push_str(&line);
+            output.push('\n');
+        })?;
+        Ok(output)
+    }
+
+    fn run_script_raw(
+        &self,
+        args: &[&str],
+        cancel: Option<&CancellationToken>,
+        on_line: impl FnMut(String),
+    ) -> Result<i32> {
+        self.check_embedded_support(args)?;
+        self.check_platform()?;
+        self.ensure_ready();
+
+        match &self.script_source {
+            ScriptSource::Embedded => self.run_embedded_script(args, cancel, on_line),
+            ScriptSource::Path(path) => self.run_script_file(path, args, cancel, on_line),
+        }
+    }
+
+    fn run_embedded_script(
+        &self,
+        args: &[&str],
+        cancel: Option<&CancellationToken>,
+        on_line: impl FnMut(String),
+    ) -> Result<i32> {
+        // Kept on disk until osascript exits
+        let (script, _temp_file) = self.embedded_script()?;
+
+        self.run_osascript(&script, args, cancel, on_line)
+    }
+
+    /// Returns the path of the embedded script for osascript to run, from
+    /// the [script cache](Self::with_script_cache_dir) if there is one, or
+    /// else written to a temporary file that is deleted when dropped.
+    fn embedded_script(&self) -> Result<(PathBuf, Option<tempfile::NamedTempFile>)> {
+        let Some(dir) = &self.script_cache_dir else {
+            let temp_file = self.write_embedded_script()?;
+            return Ok((temp_file.path().to_path_buf(), Some(temp_file)));
+        };
+
+        let path = dir.join(format!(
+            "export_notes-{:016x}.applescript",
+            naming::fnv1a(EMBEDDED_SCRIPT.as_bytes())
+        ));
+        if !path.is_file() {
+            fs::create_dir_all(dir)?;
+            // Written atomically, since other exporters may share the directory
+            write_atomically(&path, EMBEDDED_SCRIPT.as_bytes())?;
+        }
+        Ok((path, None))
+    }
+
+    /// Writes the embedded script to a temporary file for osascript to run.
+    fn write_embedded_script(&self) -> Result<tempfile::NamedTempFile> {
+        let mut temp_file = tempfile::Builder::new()
+            .suffix(".applescript")
+            .tempfile_in(self.temp_dir())?;
+        temp_file.write_all(EMBEDDED_SCRIPT.as_bytes())?;
+        temp_file.flush()?;
+        Ok(temp_file)
+    }
+
+    fn run_script_file(
+        &self,
+        script_path: &Path,
+        args: &[&str],
+        cancel: Option<&CancellationToken>,
+        on_line: impl FnMut(String),
+    ) -> Result<i32> {
+        let script = script_path.canonicalize()?;
+
+        self.run_osascript(&script, args, cancel, on_line)
+    }
+
+    /// Starts osascript running `script` with its stdout piped, along with
+    /// the session log its output should be copied to, if any.
+    ///
+    /// Stderr is forwarded, and copied to the log, by the returned thread,
+    /// which finishes with whether the script was denied control of Notes;
+    /// pass it to [`script_exit_code`] once the script exits.
+    fn spawn_osascript(
+        &self,
+        script: &Path,
+        args: &[&str],
+    ) -> Result<(
+        Child,
+        Option<Arc<session_log::SessionLog>>,
+        JoinHandle<bool>,
+    )> {
+        let log = match &self.log_file {
+            Some(path) => Some(Arc::new(session_log::SessionLog::open(path)?)),
+            None => None,
+        };
+        if let Some(log) = &log {
+            log.line(
+                "run",
+                &format!("osascript {} {}", script.display(), args.join(" ")),
+            );
+        }
+
+        let mut child = Command::new(self.osascript_program())
+            .args(&self.extra_osascript_args)
+            .arg(script)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(ExportError::LaunchError)?;
+
+        // Forward stderr ourselves to copy it to the log and to notice the
+        // error osascript prints when Automation permission is denied
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let stderr_log = log.clone();
+        let stderr = thread::spawn(move || {
+            let mut not_authorized = false;
+            for line in lossy_lines(BufReader::new(stderr)).map_while(io::Result::ok) {
+                eprintln!("{line}");
+                if let Some(log) = &stderr_log {
+                    log.line("stderr", &line);
+                }
+                not_authorized |= line.contains(NOT_AUTHORIZED_ERROR);
+            }
+            not_authorized
+        });
+
+        Ok((child, log, stderr))
+    }
+
+    /// Runs `script` with osascript, passing each line it prints to stdout to
+    /// `on_line`, and returns its exit status if it counts as success; see
+    /// [`with_success_codes`](Self::with_success_codes).
+    ///
+    /// If `cancel` is triggered, osascript is killed and [`ExportError::Cancelled`]
+    /// is returned.
+    fn run_osascript(
+        &self,
+        script: &Path,
+        args: &[&str],
+        cancel: Option<&CancellationToken>,
+        mut on_line: impl FnMut(String),
+    ) -> Result<i32> {
+        let (mut child, log, stderr) = self.spawn_osascript(script, args)?;
+
+        // Read stdout on a separate thread so cancellation is noticed even
+        // while the script is not printing anything
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in lossy_lines(BufReader::new(stdout)) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            let result = match receiver.recv_timeout(CANCEL_POLL_INTERVAL) {
+                Ok(Ok(line)) => {
+                    if let Some(log) = &log {
+                        log.line("stdout", &line);
+                    }
+                    on_line(line);
+                    Ok(())
+                }
+                Ok(Err(error)) => Err(error.into()),
+                Err(RecvTimeoutError::Timeout) => Ok(()),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            let result = result.and_then(|()| match cancel {
+                Some(cancel) if cancel.is_cancelled() => Err(ExportError::Cancelled),
+                _ => Ok(()),
+            });
+
+            if let Err(error) = result {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(error);
+            }
+        }
+
+        // The script can exit on its own after cancellation was requested but
+        // before it was noticed, e.g. from a broken pipe once we stop reading
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(ExportError::Cancelled);
+        }
+
+        let status = child.wait().map_err(ExportError::LaunchError)?;
+        if let Some(log) = &log {
+            log.line("exit", &status.to_string());
+        }
+        script_exit_code(status, stderr, &self.success_codes)
+    }
+}
+
+/// Prints a line of the script's output that isn't structured, to stderr if
+/// [`Exporter::with_script_output_to_stderr`] is on and to stdout otherwise.
+fn forward_line(line: &str, to_stderr: bool) {
+    if to_stderr {
+        eprintln!("{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
+/// Returns the status a script exited with if it counts as success: 0, or
+/// one of `success_codes`. `stderr` is the thread forwarding its error output
+/// from [`Exporter::spawn_osascript`], which tells a script denied control of
+/// Notes apart from other failures.
+fn script_exit_code(
+    status: ExitStatus,
+    stderr: JoinHandle<bool>,
+    success_codes: &[i32],
+) -> Result<i32> {
+    match status.code() {
+        Some(code) if code == 0 || success_codes.contains(&code) => Ok(code),
+        _ if stderr.join().unwrap_or(false) => Err(ExportError::NotAuthorized),
+        code => Err(ExportError::ScriptFailed(code.unwrap_or(-1))),
+    }
+}
+
+/// Checks a note path printed by the script, relative to the output directory.
+fn note_path(relative: &str) -> Result<&Path> {
+    // A lossily decoded path would put the note in the wrong place
+    if relative.contains(char::REPLACEMENT_CHARACTER) {
+        return Err(ExportError::OutputNotUtf8(relative.to_string()));
+    }
+    let relative = Path::new(relative);
+
+    // Only accept a plain relative path, so a note can't be written
+    // outside the output directory
+    if relative.extension().is_none_or(|ext| ext != "html")
+        || !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(ExportError::UnexpectedOutput(
+            relative.display().to_string(),
+        ));
+    }
+
+    Ok(relative)
+}
+
+/// Splits `reader` into lines like [`BufRead::lines`], but replaces invalid
+/// UTF-8 with U+FFFD instead of failing, since note titles and contents the
+/// script prints are not guaranteed to be valid UTF-8.
+fn lossy_lines(reader: impl BufRead) -> impl Iterator<Item = io::Result<String>> {
+    reader.split(b'\n').map(|line| {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    })
+}
+
+/// Checks that files can be created in `dir` by creating and removing one,
+/// so a read-only output directory is reported before the script runs.
+fn check_writable(dir: &Path) -> Result<()> {
+    tempfile::Builder::new()
+        .prefix(".apple-notes-write-test-")
+        .tempfile_in(dir)
+        .map(drop)
+        .map_err(|error| ExportError::OutputNotWritable(dir.to_path_buf(), error))
+}
+
+/// Returns the number of HTML files under `dir`.
+fn count_html_files(dir: &Path) -> Result<usize> {
+    let mut count = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            count += count_html_files(&path)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Rewrites every HTML file under `dir` with `transform`.
+fn transform_html_files(dir: &Path, transform: &dyn Fn(&str) -> String) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            transform_html_files(&path, transform)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read_to_string(&path)?;
+            fs::write(&path, transform(&html))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the subfolders of each exported folder in `staging`, in case the
+/// script exported recursively anyway.
+fn remove_subfolders(staging: &Path) -> Result<()> {
+    for folder in fs::read_dir(staging)? {
+        let folder = folder?;
+        if !folder.file_type()?.is_dir() {
+            continue;
+        }
+
+        for entry in fs::read_dir(folder.path())? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prefixes the exported notes in `dir` and its subdirectories with their
+/// position, in the display order the script reported in `indexes`, keyed
+/// by path within the export. `relative` is the path of `dir` within it.
+fn prefix_note_indexes(
+    dir: &Path,
+    relative: &Path,
+    indexes: &HashMap<PathBuf, usize>,
+) -> Result<()> {
+    let mut notes = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            prefix_note_indexes(&path, &relative, indexes)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let index = indexes.get(&relative).ok_or_else(|| {
+                ExportError::IncompatibleScript(format!(
+                    "no `exported:` line for {}",
+                    relative.display()
+                ))
+            })?;
+            notes.push((*index, entry.file_name(), path));
+        }
+    }
+
+    notes.sort();
+    let width = notes.len().to_string().len().max(3);
+
+    for (index, (_, file_name, path)) in notes.into_iter().enumerate() {
+        let prefixed = format!("{:0width$} - {}", index + 1, file_name.to_string_lossy());
+        fs::rename(&path, dir.join(prefixed))?;
+    }
+
+    Ok(())
+}
+
+/// Records the notes under `dir` larger than `limit` bytes. `relative` is the
+/// path of `dir` within the export.
+fn find_large_notes(
+    dir: &Path,
+    relative: &Path,
+    limit: u64,
+    large_notes: &mut Vec<(PathBuf, u64)>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+
+        if metadata.is_dir() {
+            find_large_notes(&path, &relative.join(entry.file_name()), limit, large_notes)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") && metadata.len() > limit {
+            large_notes.push((relative.join(entry.file_name()), metadata.len()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Shortens the titles of the notes under `dir` whose paths would be longer
+/// than `max_len` bytes once moved to `output_dir`, recording each renamed
+/// note. `relative` is the path of `dir` within the export.
+fn truncate_note_names(
+    dir: &Path,
+    output_dir: &Path,
+    relative: &Path,
+    max_len: usize,
+    truncated: &mut Vec<TruncatedNote>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_name = entry.file_name();
+
+        if entry.file_type()?.is_dir() {
+            truncate_note_names(
+                &path,
+                output_dir,
+                &relative.join(&file_name),
+                max_len,
+                truncated,
+            )?;
+            continue;
+        }
+
+        if path.extension().is_none_or(|ext| ext != "html") {
+            continue;
+        }
+        let Some(name) = file_name.to_str() else {
+            continue;
+        };
+
+        let full_len = output_dir.join(relative).join(name).as_os_str().len();
+        if full_len <= max_len {
+            continue;
+        }
+
+        // Names without an id suffix could collide once shortened
+        let shortened = shorten_note_file_name(name, full_len - max_len);
+        if shortened != name && !dir.join(&shortened).exists() {
+            fs::rename(&path, dir.join(&shortened))?;
+            truncated.push(TruncatedNote {
+                original: relative.join(name),
+                path: relative.join(shortened),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes at least `excess` bytes from the title of a note filename
+/// (`<title> -- <id>.html`), keeping the id suffix and extension.
+fn shorten_note_file_name(name: &str, excess: usize) -> String {
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    let (title, suffix) = match stem.rsplit_once(" -- ") {
+        Some((title, id)) => (title, format!(" -- {id}.{extension}")),
+        None => (stem, format!(".{extension}")),
+    };
+
+    let mut keep = title.len().saturating_sub(excess);
+    while !title.is_char_boundary(keep) {
+        keep -= 1;
+    }
+
+    format!("{}{suffix}", title[..keep].trim_end())
+}
+
+/// Removes the subdirectories of `dir` that contain no files, directly or in
+/// their own subdirectories, and returns how many were removed.
+fn prune_empty_dirs(dir: &Path) -> Result<usize> {
+    let mut pruned = 0;
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let path = entry.path();
+        pruned += prune_empty_dirs(&path)?;
+
+        if fs::read_dir(&path)?.next().is_none() {
+            fs::remove_dir(&path)?;
+            pruned += 1;
+        }
+    }
+
+    Ok(pruned)
+}
+
+/// Merges each subdirectory of `dir` whose only entry is another directory
+/// with that directory, joining their names with `" - "`.
+fn collapse_single_child_dirs(dir: &Path) -> Result<()> {
+    // Collected first, since merging adds entries to `dir`
+    let entries = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    for entry in entries {
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let mut path = entry.path();
+        while let Some(child) = only_subdirectory(&path)? {
+            let (Some(name), Some(child_name)) = (path.file_name(), child.file_name()) else {
+                break;
+            };
+            let merged = dir.join(format!(
+                "{} - {}",
+                name.to_string_lossy(),
+                child_name.to_string_lossy()
+            ));
+            if merged.exists() {
+                break;
+            }
+
+            fs::rename(&child, &merged)?;
+            // The merged directory stands for the child folder
+            let metadata = path.join(FOLDER_METADATA_FILE);
+            if metadata.exists() {
+                fs::remove_file(metadata)?;
+            }
+            fs::remove_dir(&path)?;
+            path = merged;
+        }
+
+        collapse_single_child_dirs(&path)?;
+    }
+
+    Ok(())
+}
+
+/// Renames each folder directory under `dir` to what `transform` makes of
+/// its name, keeping names that are empty, contain a path separator, or are
+/// already taken.
+fn rename_folder_dirs(dir: &Path, transform: &dyn Fn(&str) -> String) -> Result<()> {
+    // Collected first, since renaming changes the entries of `dir`
+    let entries = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.file_type()?.is_dir() || name.ends_with(DEFAULT_ATTACHMENTS_SUFFIX) {
+            continue;
+        }
+
+        rename_folder_dirs(&entry.path(), transform)?;
+
+        let renamed = transform(&name);
+        if renamed.is_empty()
+            || renamed == "."
+            || renamed == ".."
+            || renamed.contains(['/', '\\'])
+            || renamed == name
+        {
+            continue;
+        }
+        // Renaming only the case finds the directory itself on
+        // case-insensitive filesystems, so that is checked by exact name
+        let target = dir.join(&renamed);
+        let taken = if renamed.to_lowercase() == name.to_lowercase() {
+            fs::read_dir(dir)?.any(|other| other.is_ok_and(|other| other.file_name() == *renamed))
+        } else {
+            target.exists()
+        };
+        if !taken {
+            fs::rename(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the directory in `dir` if it is the only entry besides a
+/// [`FOLDER_METADATA_FILE`], and not an attachment folder.
+fn only_subdirectory(dir: &Path) -> Result<Option<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?.filter(|entry| {
+        entry
+            .as_ref()
+            .map_or(true, |entry| entry.file_name() != FOLDER_METADATA_FILE)
+    });
+    let (Some(entry), None) = (entries.next().transpose()?, entries.next()) else {
+        return Ok(None);
+    };
+
+    let is_attachments = entry
+        .file_name()
+        .to_string_lossy()
+        .ends_with(DEFAULT_ATTACHMENTS_SUFFIX);
+    if entry.file_type()?.is_dir() && !is_attachments {
+        Ok(Some(entry.path()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the first file in `staging` that already exists under `output_dir`.
+fn find_existing_output(staging: &Path, output_dir: &Path) -> Result<Option<PathBuf>> {
+    for entry in fs::read_dir(staging)? {
+        let entry = entry?;
+        let target = output_dir.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            if let Some(existing) = find_existing_output(&entry.path(), &target)? {
+                return Ok(Some(existing));
+            }
+        } else if target.exists() {
+            return Ok(Some(target));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Moves everything in `staging` into `output_dir`, merging directories.
+///
+/// Directories that did not exist before are added to `created_dirs`.
+fn move_staged_output(
+    staging: &Path,
+    output_dir: &Path,
+    on_existing: OnExisting,
+    created_dirs: &mut Vec<PathBuf>,
+) -> Result<()> {
+    if !output_dir.exists() {
+        fs::create_dir_all(output_dir)?;
+        created_dirs.push(output_dir.to_path_buf());
+    }
+
+    for entry in fs::read_dir(staging)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = output_dir.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            move_staged_output(&source, &target, on_existing, created_dirs)?;
+        } else if on_existing == OnExisting::Skip && target.exists() {
+            continue;
+        } else {
+            fs::rename(&source, &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Exports a folder as Markdown files with YAML frontmatter.
+///
+/// This is a convenience function that uses the embedded AppleScript and
+/// [`MarkdownOptions::default()`]. For more control, use
+/// [`Exporter::export_folder_as_markdown`].
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::export_folder_as_markdown;
+///
+/// export_folder_as_markdown("My Notes", "./markdown").expect("Failed to export");
+/// ```
+pub fn export_folder_as_markdown<P: AsRef<Path>>(folder: &str, output_dir: P) -> Result<()> {
+    Exporter::new().export_folder_as_markdown(folder, output_dir, &MarkdownOptions::default())
+}
+
+/// Exports a folder's notes into a single Markdown file.
+///
+/// This is a convenience function that uses the embedded AppleScript. See
+/// [`Exporter::export_folder_as_single_markdown`] for details.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::export_folder_as_single_markdown;
+///
+/// export_folder_as_single_markdown("My Notes", "./my-notes.md").expect("Failed to export");
+/// ```
+pub fn export_folder_as_single_markdown<P: AsRef<Path>>(
+    folder: &str,
+    output_path: P,
+) -> Result<()> {
+    Exporter::new().export_folder_as_single_markdown(folder, output_path)
+}
+
+/// Exports a folder as RTF and RTFD files.
+///
+/// This is a convenience function that uses the embedded AppleScript. See
+/// [`Exporter::export_folder_as_rtfd`] for details.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::export_folder_as_rtfd;
+///
+/// export_folder_as_rtfd("My Notes", "./rtfd").expect("Failed to export");
+/// ```
+pub fn export_folder_as_rtfd<P: AsRef<Path>>(folder: &str, output_dir: P) -> Result<()> {
+    Exporter::new().export_folder_as_rtfd(folder, output_dir)
+}
+
+/// Lists all available top-level folders across all Apple Notes accounts.
+///
+/// This is a convenience function that uses the embedded AppleScript.
+/// For more control, use the [`Exporter`] struct.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::list_folders;
+///
+/// list_folders().expect("Failed to list folders");
+/// ```
+pub fn list_folders() -> Result<()> {
+    Exporter::new().list_folders()
+}
+
+/// Exports a folder recursively to HTML files.
+///
+/// This is a convenience function that uses the embedded AppleScript.
+/// For more control, use the [`Exporter`] struct.
+///
+/// This function searches all accounts for the folder. If a folder with the
+/// same name exists in multiple accounts, use [`export_folder_from_account`]
+/// to specify which account to use.
+///
+/// # Arguments
+///
+/// * `folder` - The folder name to export.
+/// * `output_dir` - The directory where exported notes will be saved.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::export_folder;
+///
+/// export_folder("My Notes", "./exports").expect("Failed to export");
+/// ```
+pub fn export_folder<P: AsRef<Path>>(folder: &str, output_dir: P) -> Result<()> {
+    Exporter::new().export_folder(folder, output_dir)
+}
+
+/// Exports a folder from a specific account recursively to HTML files.
+///
+/// This is a convenience function that uses the embedded AppleScript.
+/// For more control, use the [`Exporter`] struct.
+///
+/// This is useful when a folder with the same name exists in multiple accounts.
+///
+/// # Arguments
+///
+/// * `account` - The account name (e.g., "iCloud", "Google"), or
+///   [`AccountSelector::LocalAccount`] for the on-device account.
+/// * `folder` - The folder name to export.
+/// * `output_dir` - The directory where exported notes will be saved.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::export_folder_from_account;
+///
+/// // Export "Work" folder from iCloud account
+/// export_folder_from_account("iCloud", "Work", "./exports").expect("Failed to export");
+///
+/// // Export "Work" folder from Google account
+/// export_folder_from_account("Google", "Work", "./google_exports").expect("Failed to export");
+/// ```
+pub fn export_folder_from_account<A: Into<AccountSelector>, P: AsRef<Path>>(
+    account: A,
+    folder: &str,
+    output_dir: P,
+) -> Result<()> {
+    Exporter::new().export_folder_from_account(account, folder, output_dir)
+}
+
+/// Exports a folder into a single self-contained HTML file.
+///
+/// This is a convenience function that uses the embedded AppleScript.
+/// For more control, use the [`Exporter`] struct.
+///
+/// See [`Exporter::export_folder_as_single_html`] for details.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::export_folder_as_single_html;
+///
+/// export_folder_as_single_html("My Notes", "./my-notes.html").expect("Failed to export");
+/// ```
+pub fn export_folder_as_single_html<P: AsRef<Path>>(folder: &str, output_path: P) -> Result<()> {
+    Exporter::new().export_folder_as_single_html(folder, output_path)
+}
+
+/// Exports a folder as an EPUB book.
+///
+/// This is a convenience function that uses the embedded AppleScript.
+/// For more control, use the [`Exporter`] struct.
+///
+/// See [`Exporter::export_folder_as_epub`] for details.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::export_folder_as_epub;
+///
+/// export_folder_as_epub("My Notes", "./my-notes.epub").expect("Failed to export");
+/// ```
+pub fn export_folder_as_epub<P: AsRef<Path>>(folder: &str, epub_path: P) -> Result<()> {
+    Exporter::new().export_folder_as_epub(folder, epub_path)
+}
+
+// =============================================================================
+// Attachment Extraction
+// =============================================================================
+
+/// Information about an extracted attachment.
+///
+/// Serializes without the original data URL, which can be megabytes.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct ExtractedAttachment {
+    /// The file path where the attachment was saved.
+    pub path: PathBuf,
+    /// The original data URL that was replaced, or the URL of a downloaded image.
+    #[serde(skip)]
+    pub original_data_url: String,
+    /// The MIME type of the attachment (e.g., "image/png").
+    pub mime_type: String,
+    /// The size of the saved file in bytes.
+    #[serde(rename = "bytes")]
+    pub size: u64,
+}
+
+/// An image left inline as a data URL because it is smaller than
+/// [`ExtractOptions::inline_threshold_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InlineAttachment {
+    /// The MIME type of the image (e.g., "image/png").
+    pub mime_type: String,
+    /// The decoded size of the image in bytes.
+    pub size: u64,
+}
+
+/// Result of extracting attachments from an HTML file.
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct ExtractionResult {
+    /// The HTML file that was processed.
+    pub html_path: PathBuf,
+    /// The attachments that were extracted.
+    pub attachments: Vec<ExtractedAttachment>,
+    /// Whether the HTML file was modified.
+    pub html_modified: bool,
+    /// The folder this note's attachments are extracted to, if it exists,
+    /// including when they were extracted by an earlier run.
+    ///
+    /// [`consolidate_attachments`] later moves attachments into a shared
+    /// folder instead.
+    pub attachments_dir: Option<PathBuf>,
+    /// Remote images that could not be downloaded. Always empty unless
+    /// `ExtractOptions::download_remote` is set (requires the `download` feature).
+    pub failed_downloads: Vec<FailedDownload>,
+    /// Images kept inline because they are smaller than
+    /// [`ExtractOptions::inline_threshold_bytes`], in order of appearance.
+    pub kept_inline: Vec<InlineAttachment>,
+    /// Images the extractor left alone, and why, in order of appearance.
+    /// Images that reference a file by relative path, such as ones extracted
+    /// by an earlier run, are not listed.
+    pub skipped: Vec<SkippedAttachment>,
+}
+
+/// An image left in the note during extraction; see [`ExtractionResult::skipped`].
+///
+/// # Example
+///
+/// ```
+/// use std::fs;
+///
+/// use apple_notes_exporter_rs::{
+///     extract_attachments_from_html_with_options, ExtractOptions, SkipReason,
+/// };
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let html_path = dir.path().join("Note -- abc.html");
+/// fs::write(
+///     &html_path,
+///     r#"<img src="data:image/png;base64,iVBORw0KGgo=">
+///        <img src="data:image/png;base64,!!!!">
+///        <img src="data:application/pdf;base64,JVBERi0=">
+///        <img src="file:///Users/me/photo.jpg">
+///        <img src="Note -- abc-attachments/attachment-001.png">"#,
+/// )
+/// .unwrap();
+///
+/// let options = ExtractOptions {
+///     max_attachment_bytes: Some(4),
+///     ..Default::default()
+/// };
+/// let result = extract_attachments_from_html_with_options(&html_path, &options).unwrap();
+///
+/// let skipped: Vec<_> = result
+///     .skipped
+///     .iter()
+///     .map(|skipped| (skipped.source.as_str(), skipped.reason))
+///     .collect();
+/// assert_eq!(
+///     skipped,
+///     [
+///         ("data:image/png;base64,", SkipReason::TooLarge),
+///         ("data:image/png;base64,", SkipReason::DecodeFailed),
+///         ("data:application/pdf;base64,", SkipReason::Filtered),
+///         ("file:///Users/me/photo.jpg", SkipReason::UnknownScheme),
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedAttachment {
+    /// The image's `src`. For a data URL, only the part before the payload
+    /// (e.g. `data:image/png;base64,`), since the payload can be megabytes.
+    pub source: String,
+    /// Why the image was not extracted.
+    pub reason: SkipReason,
+}
+
+/// Why an image was not extracted; see [`SkippedAttachment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The `src` is a URL the extractor does not fetch, such as an `https`
+    /// URL without `ExtractOptions::download_remote` (requires the `download`
+    /// feature) or a `file` URL.
+    UnknownScheme,
+    /// The data URL is larger than [`ExtractOptions::max_attachment_bytes`].
+    TooLarge,
+    /// The data URL is not an image, or its MIME type is excluded by
+    /// [`ExtractOptions::allowed_mime_types`] or
+    /// [`ExtractOptions::blocked_mime_types`].
+    Filtered,
+    /// The data URL's payload could not be decoded.
+    DecodeFailed,
+}
+
+/// A remote image that could not be downloaded during extraction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedDownload {
+    /// The image's URL.
+    pub url: String,
+    /// Why the download failed, e.g. the HTTP status or network error.
+    pub reason: String,
+}
+
+/// The suffix of the folder a note's attachments are extracted to, by default.
+pub const DEFAULT_ATTACHMENTS_SUFFIX: &str = "-attachments";
+
+/// Options controlling how attachments are extracted from exported HTML.
+///
+/// Use [`ExtractOptions::default()`] for the same behavior as
+/// [`extract_attachments_from_html`].
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    /// Skip attachments whose decoded size would exceed this many bytes.
+    ///
+    /// The size is bounded before decoding, with [`estimated_decoded_len`] for
+    /// base64 payloads and the payload length for percent-encoded ones, so
+    /// oversized or corrupt data URLs never get allocated, and checked again
+    /// after decoding. Skipped attachments are left inline in the HTML and
+    /// listed in [`ExtractionResult::skipped`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use apple_notes_exporter_rs::{
+    ///     extract_attachments_from_html_with_options, ExtractOptions, SkipReason,
+    /// };
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let html_path = dir.path().join("Note -- abc.html");
+    /// // Five bytes, not base64-encoded, and four bytes in base64
+    /// fs::write(
+    ///     &html_path,
+    ///     r#"<img src="data:image/png,PNG12"><img src="data:image/png;base64,iVBORw==">"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let options = ExtractOptions {
+    ///     max_attachment_bytes: Some(4),
+    ///     ..Default::default()
+    /// };
+    /// let result = extract_attachments_from_html_with_options(&html_path, &options).unwrap();
+    ///
+    /// assert_eq!(result.skipped.len(), 1);
+    /// assert_eq!(result.skipped[0].reason, SkipReason::TooLarge);
+    /// assert_eq!(result.attachments.len(), 1);
+    /// assert_eq!(result.attachments[0].size, 4);
+    /// ```
+    pub max_attachment_bytes: Option<usize>,
+
+    /// Keep embedded images smaller than this many bytes inline as data URLs,
+    /// and extract only the larger ones.
+    ///
+    /// Useful for archives that should stay mostly self-contained, with small
+    /// icons inline and large photos on disk. Images kept inline are listed in
+    /// [`ExtractionResult::kept_inline`]. Downloaded remote images are always
+    /// extracted. `None` (the default) extracts every image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use apple_notes_exporter_rs::{extract_attachments_from_html_with_options, ExtractOptions};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let html_path = dir.path().join("Note -- abc.html");
+    /// let icon = "data:image/png;base64,iVBORw0KGgo=";
+    /// let photo = format!("data:image/jpeg;base64,{}", "/9j/".repeat(100));
+    /// fs::write(&html_path, format!(r#"<img src="{icon}"><img src="{photo}">"#)).unwrap();
+    ///
+    /// let options = ExtractOptions {
+    ///     inline_threshold_bytes: Some(100),
+    ///     ..Default::default()
+    /// };
+    /// let result = extract_attachments_from_html_with_options(&html_path, &options).unwrap();
+    ///
+    /// assert_eq!(result.attachments.len(), 1);
+    /// assert_eq!(result.attachments[0].mime_type, "image/jpeg");
+    /// assert_eq!(result.kept_inline.len(), 1);
+    /// assert_eq!(result.kept_inline[0].size, 8);
+    /// assert!(fs::read_to_string(&html_path).unwrap().contains(icon));
+    /// ```
+    pub inline_threshold_bytes: Option<usize>,
+
+    /// Extract only embedded images with one of these MIME types, such as
+    /// `image/png`, or `image/*` for a whole family. Empty (the default)
+    /// extracts every type.
+    ///
+    /// Images that don't match are left inline in the HTML, untouched, and
+    /// listed in [`ExtractionResult::skipped`] with [`SkipReason::Filtered`].
+    /// Only images are ever extracted, so other embedded data such as PDFs
+    /// always stays inline, and is listed the same way.
+    /// Matching ignores case. Applies to data URLs only; downloaded remote
+    /// images are always extracted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use apple_notes_exporter_rs::{extract_attachments_from_html_with_options, ExtractOptions};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let html_path = dir.path().join("Note -- abc.html");
+    /// let png = "data:image/png;base64,iVBORw0KGgo=";
+    /// let gif = "data:image/gif;base64,R0lGODlh";
+    /// fs::write(&html_path, format!(r#"<img src="{png}"><img src="{gif}">"#)).unwrap();
+    ///
+    /// let options = ExtractOptions {
+    ///     allowed_mime_types: vec!["image/png".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let result = extract_attachments_from_html_with_options(&html_path, &options).unwrap();
+    ///
+    /// assert_eq!(result.attachments.len(), 1);
+    /// assert_eq!(result.attachments[0].mime_type, "image/png");
+    /// assert!(fs::read_to_string(&html_path).unwrap().contains(gif));
+    /// assert_eq!(result.skipped[0].source, "data:image/gif;base64,");
+    /// ```
+    pub allowed_mime_types: Vec<String>,
+
+    /// Leave embedded images with one of these MIME types inline, such as
+    /// `image/gif`, or `image/*` for a whole family. Checked after
+    /// [`allowed_mime_types`](Self::allowed_mime_types), in the same way, and
+    /// blocked images are likewise listed in [`ExtractionResult::skipped`]
+    /// with [`SkipReason::Filtered`]. Empty by default.
+    pub blocked_mime_types: Vec<String>,
+
+    /// How the extracted files are named. Defaults to numbering them in order
+    /// of appearance (`attachment-001.png`, ...).
+    pub naming: AttachmentNaming,
+
+    /// The suffix appended to a note's file stem to name its attachment
+    /// folder, e.g. `_media` or `.assets`. Must not be empty. Defaults to
+    /// [`DEFAULT_ATTACHMENTS_SUFFIX`] (`-attachments`).
+    ///
+    /// Directory runs skip folders with this suffix, so extracting again
+    /// with the same suffix does not reprocess them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use apple_notes_exporter_rs::{extract_attachments_from_directory_with_options, ExtractOptions};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// fs::write(
+    ///     dir.path().join("Note -- abc.html"),
+    ///     r#"<img src="data:image/png;base64,iVBORw0KGgo=">"#,
+    /// ).unwrap();
+    ///
+    /// let options = ExtractOptions {
+    ///     attachments_suffix: "_media".to_string(),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let results = extract_attachments_from_directory_with_options(dir.path(), &options).unwrap();
+    /// assert_eq!(results[0].attachments.len(), 1);
+    /// assert!(dir.path().join("Note -- abc_media/attachment-001.png").is_file());
+    ///
+    /// // The "_media" folder is skipped and the note now references the file
+    /// let results = extract_attachments_from_directory_with_options(dir.path(), &options).unwrap();
+    /// assert_eq!(results.len(), 1);
+    /// assert!(results[0].attachments.is_empty());
+    /// ```
+    pub attachments_suffix: String,
+
+    /// Convert HEIC/HEIF images (common for iPhone photos) to JPEG so that
+    /// browsers can display them.
+    ///
+    /// Conversion uses the macOS `sips` tool. If it is unavailable or fails,
+    /// the original `.heic` file is written, which shows in the attachment's
+    /// [`mime_type`](ExtractedAttachment::mime_type).
+    #[cfg(feature = "heic")]
+    pub convert_heic: bool,
+
+    /// Re-encode matching images to a different format before writing them.
+    ///
+    /// Images that fail to re-encode are written unchanged.
+    #[cfg(feature = "image")]
+    pub reencode: Option<ReencodeOptions>,
+
+    /// Download images that a note references by `http` or `https` URL into
+    /// its attachment folder, and point the note at the local copy, so the
+    /// export can be read offline.
+    ///
+    /// Downloads use `curl` and respect [`max_attachment_bytes`](Self::max_attachment_bytes).
+    /// An image that cannot be downloaded keeps its URL and is recorded in
+    /// [`ExtractionResult::failed_downloads`].
+    #[cfg(feature = "download")]
+    pub download_remote: bool,
+
+    /// Give extracted attachments the modification time of the note they
+    /// came from, and keep the note's own modification time when its HTML is
+    /// rewritten, so photo archives stay sortable by file date.
+    ///
+    /// Notes don't record when attachments were added, so the note's
+    /// modification time stands in for it. Use this with
+    /// [`ExportOptions::preserve_timestamps`] so that is the note's
+    /// modification date in Notes rather than its export time. Off by default.
+    pub preserve_timestamps: bool,
+
+    /// A URL prefix for the rewritten `src` of extracted images, such as
+    /// `/static/notes/`, for exports served from a web root under a prefix.
+    ///
+    /// It is the URL of the note's directory: images are referenced as
+    /// `<url_base><attachment folder>/<file>` instead of relative to the
+    /// note, and directory runs add the path of each note's folder below the
+    /// directory. A missing trailing `/` is added. The files are written to
+    /// the same place either way. `None` (the default) keeps relative paths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use apple_notes_exporter_rs::{extract_attachments_from_directory_with_options, ExtractOptions};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// fs::create_dir(dir.path().join("Recipes")).unwrap();
+    /// let html_path = dir.path().join("Recipes/Cake -- abc.html");
+    /// fs::write(&html_path, r#"<img src="data:image/png;base64,iVBORw0KGgo=">"#).unwrap();
+    ///
+    /// let options = ExtractOptions {
+    ///     url_base: Some("/static/notes".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// extract_attachments_from_directory_with_options(dir.path(), &options).unwrap();
+    ///
+    /// let html = fs::read_to_string(&html_path).unwrap();
+    /// assert!(html.contains(r#"src="/static/notes/Recipes/Cake -- abc-attachments/attachment-001.png""#));
+    /// assert!(dir.path().join("Recipes/Cake -- abc-attachments/attachment-001.png").is_file());
+    /// ```
+    pub url_base: Option<String>,
+
+    /// A counter to number [`AttachmentNaming::Counter`] names from across
+    /// notes, instead of starting at `attachment-001` in every note.
+    ///
+    /// Clones of the options share the counter, so notes extracted into one
+    /// folder with [`extract_attachments_to_with_options`], even from several
+    /// threads at once, never overwrite each other's attachments.
+    /// [`AttachmentNaming::ContentHash`] names are safe to share without it:
+    /// identical images get the same name, and writing the same file from
+    /// several threads leaves one intact copy. `None` (the default) numbers
+    /// each note's attachments separately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    /// use std::fs;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::AtomicUsize;
+    /// use std::thread;
+    ///
+    /// use apple_notes_exporter_rs::{
+    ///     extract_attachments_to_with_options, AttachmentNaming, ExtractOptions,
+    /// };
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let shared = dir.path().join("attachments");
+    /// let options = ExtractOptions {
+    ///     shared_counter: Some(Arc::new(AtomicUsize::new(0))),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let image = r#"<img src="data:image/png;base64,iVBORw0KGgo=">"#;
+    /// let handles: Vec<_> = (0..8)
+    ///     .map(|i| {
+    ///         let html_path = dir.path().join(format!("Note {i} -- p{i}.html"));
+    ///         fs::write(&html_path, image.repeat(5)).unwrap();
+    ///         let (shared, options) = (shared.clone(), options.clone());
+    ///         thread::spawn(move || {
+    ///             extract_attachments_to_with_options(&html_path, &shared, &options).unwrap()
+    ///         })
+    ///     })
+    ///     .collect();
+    ///
+    /// let mut names = HashSet::new();
+    /// for handle in handles {
+    ///     for attachment in handle.join().unwrap().attachments {
+    ///         assert!(names.insert(attachment.path.file_name().unwrap().to_owned()));
+    ///     }
+    /// }
+    /// assert_eq!(names.len(), 40);
+    /// assert_eq!(fs::read_dir(&shared).unwrap().count(), 40);
+    ///
+    /// // Content hashes give the same image one name, however many notes share it
+    /// let options = ExtractOptions {
+    ///     naming: AttachmentNaming::ContentHash,
+    ///     ..Default::default()
+    /// };
+    /// let hashed = dir.path().join("hashed");
+    /// let handles: Vec<_> = (0..8)
+    ///     .map(|i| {
+    ///         let html_path = dir.path().join(format!("Copy {i} -- c{i}.html"));
+    ///         fs::write(&html_path, image.repeat(5)).unwrap();
+    ///         let (hashed, options) = (hashed.clone(), options.clone());
+    ///         thread::spawn(move || {
+    ///             extract_attachments_to_with_options(&html_path, &hashed, &options).unwrap()
+    ///         })
+    ///     })
+    ///     .collect();
+    /// for handle in handles {
+    ///     assert_eq!(handle.join().unwrap().attachments.len(), 5);
+    /// }
+    /// let files: Vec<_> = fs::read_dir(&hashed).unwrap().collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(files.len(), 1);
+    /// assert_eq!(fs::read(files[0].path()).unwrap(), [0x89, b'P', b'N', b'G', 13, 10, 26, 10]);
+    /// ```
+    pub shared_counter: Option<Arc<AtomicUsize>>,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            max_attachment_bytes: None,
+            inline_threshold_bytes: None,
+            allowed_mime_types: Vec::new(),
+            blocked_mime_types: Vec::new(),
+            naming: AttachmentNaming::default(),
+            attachments_suffix: DEFAULT_ATTACHMENTS_SUFFIX.to_string(),
+            #[cfg(feature = "heic")]
+            convert_heic: false,
+            #[cfg(feature = "image")]
+            reencode: None,
+            #[cfg(feature = "download")]
+            download_remote: false,
+            preserve_timestamps: false,
+            url_base: None,
+            shared_counter: None,
+        }
+    }
+}
+
+impl ExtractOptions {
+    /// Returns whether embedded images of `mime_type` pass the
+    /// [`allowed_mime_types`](Self::allowed_mime_types) and
+    /// [`blocked_mime_types`](Self::blocked_mime_types) filters.
+    fn extracts_mime_type(&self, mime_type: &str) -> bool {
+        let matching = |pattern: &String| match pattern.strip_suffix("/*") {
+            Some(family) => mime_type
+                .split_once('/')
+                .is_some_and(|(kind, _)| kind.eq_ignore_ascii_case(family)),
+            None => pattern.eq_ignore_ascii_case(mime_type),
+        };
+        (self.allowed_mime_types.is_empty() || self.allowed_mime_types.iter().any(matching))
+            && !self.blocked_mime_types.iter().any(matching)
+    }
+
+    /// Returns the `src` of an extracted image `path`, relative to the note.
+    fn image_src(&self, path: &str) -> String {
+        match &self.url_base {
+            Some(base) if base.ends_with('/') => format!("{base}{path}"),
+            Some(base) => format!("{base}/{path}"),
+            None => path.to_string(),
+        }
+    }
+
+    /// Returns the options for the notes in the subdirectory `name`, with
+    /// [`url_base`](Self::url_base) extended by it.
+    fn for_subdirectory(&self, name: &str) -> Option<Self> {
+        self.url_base.as_ref()?;
+        Some(Self {
+            url_base: Some(format!("{}/", self.image_src(name))),
+            ..self.clone()
+        })
     }
 
-    fn run_script(&self, args: &[&str]) -> Result<()> {
-        check_platform()?;
+    /// Applies any configured conversions, returning the data and MIME type to write.
+    fn transform<'a>(&self, data: Vec<u8>, mime_type: &'a str) -> (Vec<u8>, &'a str) {
+        #[cfg(feature = "heic")]
+        let (data, mime_type) = if self.convert_heic && heic::is_heic(mime_type) {
+            match heic::convert_to_jpeg(&data) {
+                Ok(jpeg) => (jpeg, "image/jpeg"),
+                Err(_) => (data, mime_type),
+            }
+        } else {
+            (data, mime_type)
+        };
+
+        #[cfg(feature = "image")]
+        let (data, mime_type) = match &self.reencode {
+            Some(reencode) => reencode.apply(data, mime_type),
+            None => (data, mime_type),
+        };
+
+        (data, mime_type)
+    }
+
+    /// Downloads `src` if it is a remote image that should be downloaded,
+    /// returning its data and MIME type or why the download failed.
+    #[cfg(feature = "download")]
+    fn download(&self, src: &str) -> Option<std::result::Result<(Vec<u8>, String), String>> {
+        (self.download_remote && download::is_remote(src))
+            .then(|| download::fetch_image(src.trim(), self.max_attachment_bytes))
+    }
+
+    #[cfg(not(feature = "download"))]
+    fn download(&self, _src: &str) -> Option<std::result::Result<(Vec<u8>, String), String>> {
+        None
+    }
+}
+
+/// A parsed `data:` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataUrl {
+    /// The lowercase MIME type without parameters (e.g. `"image/png"`).
+    ///
+    /// Defaults to `"text/plain"` when the URL does not specify one.
+    pub mime_type: String,
+    /// Whether the payload was base64-encoded (rather than percent-encoded).
+    pub is_base64: bool,
+    /// The decoded payload.
+    pub data: Vec<u8>,
+}
+
+/// Parses a `data:` URL such as the `src` of an image embedded in a note.
+///
+/// Both base64 (`data:image/png;base64,...`) and percent-encoded
+/// (`data:image/svg+xml,%3Csvg...`) payloads are supported, and whitespace
+/// inside base64 payloads is ignored. Base64 payloads may use the standard
+/// or the URL-safe alphabet (`-` and `_` instead of `+` and `/`), with or
+/// without padding. Returns `None` if `src` is not a data URL or its payload
+/// cannot be decoded.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::parse_data_url;
+///
+/// let data_url = parse_data_url("data:image/png;base64,aGVs\nbG8=").unwrap();
+/// assert_eq!(data_url.mime_type, "image/png");
+/// assert!(data_url.is_base64);
+/// assert_eq!(data_url.data, b"hello");
+///
+/// let standard = parse_data_url("data:image/png;base64,+/8=").unwrap();
+/// let url_safe = parse_data_url("data:image/png;base64,-_8").unwrap();
+/// assert_eq!(standard.data, [0xfb, 0xff]);
+/// assert_eq!(url_safe.data, [0xfb, 0xff]);
+///
+/// // Unpadded, and without `-` or `_` to give the alphabet away
+/// let unpadded = parse_data_url("data:image/png;base64,AAE").unwrap();
+/// assert_eq!(unpadded.data, [0x00, 0x01]);
+///
+/// let data_url = parse_data_url("data:text/plain;charset=utf-8,a%20b").unwrap();
+/// assert_eq!(data_url.mime_type, "text/plain");
+/// assert!(!data_url.is_base64);
+/// assert_eq!(data_url.data, b"a b");
+///
+/// assert!(parse_data_url("https://example.com/image.png").is_none());
+/// ```
+pub fn parse_data_url(src: &str) -> Option<DataUrl> {
+    let raw = split_data_url(src)?;
+    let data = decode_data_url_payload(raw.payload, raw.is_base64).ok()?;
 
-        match &self.script_source {
-            ScriptSource::Embedded => self.run_embedded_script(args),
-            ScriptSource::Path(path) => self.run_script_file(path, args),
-        }
+    Some(DataUrl {
+        mime_type: raw.mime_type,
+        is_base64: raw.is_base64,
+        data,
+    })
+}
+
+/// A `data:` URL split into its parts, with the payload not yet decoded.
+struct RawDataUrl<'a> {
+    mime_type: String,
+    is_base64: bool,
+    payload: &'a str,
+}
+
+fn split_data_url(src: &str) -> Option<RawDataUrl<'_>> {
+    let src = src.trim();
+    let scheme = src.get(..5)?;
+    if !scheme.eq_ignore_ascii_case("data:") {
+        return None;
     }
 
-    fn run_embedded_script(&self, args: &[&str]) -> Result<()> {
-        // Create a temporary file for the embedded script
-        let mut temp_file = tempfile::NamedTempFile::with_suffix(".applescript")?;
-        temp_file.write_all(EMBEDDED_SCRIPT.as_bytes())?;
-        temp_file.flush()?;
+    let (header, payload) = src[5..].split_once(',')?;
+    let mut params = header.split(';').map(str::trim);
 
-        let status = Command::new("osascript")
-            .arg(temp_file.path())
-            .args(args)
-            .status()
-            .map_err(ExportError::LaunchError)?;
+    let mime_type = params.next().unwrap_or_default().to_ascii_lowercase();
+    let is_base64 = params.any(|param| param.eq_ignore_ascii_case("base64"));
 
-        if !status.success() {
-            return Err(ExportError::ScriptFailed(status.code().unwrap_or(-1)));
-        }
+    Some(RawDataUrl {
+        mime_type: if mime_type.is_empty() {
+            "text/plain".to_string()
+        } else {
+            mime_type
+        },
+        is_base64,
+        payload,
+    })
+}
 
-        Ok(())
-    }
+/// The standard base64 alphabet, with or without padding.
+const BASE64_STANDARD_ANY_PADDING: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
 
-    fn run_script_file(&self, script_path: &Path, args: &[&str]) -> Result<()> {
-        let script = script_path.canonicalize()?;
+/// The URL-safe base64 alphabet (`-` and `_` instead of `+` and `/`), with
+/// or without padding, as some embedded data uses it.
+const BASE64_URL_SAFE_ANY_PADDING: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
 
-        let status = Command::new("osascript")
-            .arg(&script)
-            .args(args)
-            .status()
-            .map_err(ExportError::LaunchError)?;
+fn decode_data_url_payload(payload: &str, is_base64: bool) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    decode_data_url_payload_into(payload, is_base64, &mut Vec::new(), &mut decoded)?;
+    Ok(decoded)
+}
 
-        if !status.success() {
-            return Err(ExportError::ScriptFailed(status.code().unwrap_or(-1)));
-        }
+/// Decodes a data URL's payload into `decoded`, replacing its contents,
+/// using `scratch` for the payload if it needs unescaping first. Both keep
+/// their capacity, so they can be reused for the next payload.
+fn decode_data_url_payload_into(
+    payload: &str,
+    is_base64: bool,
+    scratch: &mut Vec<u8>,
+    decoded: &mut Vec<u8>,
+) -> Result<()> {
+    decoded.clear();
+    if !is_base64 {
+        percent_decode_into(payload, decoded);
+        return Ok(());
+    }
 
-        Ok(())
+    let mut bytes = payload.as_bytes();
+    if payload.contains('%') || bytes.iter().any(u8::is_ascii_whitespace) {
+        scratch.clear();
+        percent_decode_into(payload, scratch);
+        scratch.retain(|b| !b.is_ascii_whitespace());
+        bytes = scratch;
     }
+
+    // Allocate the output once, since images can be several megabytes
+    let unpadded_len = bytes.iter().rposition(|&b| b != b'=').map_or(0, |i| i + 1);
+    decoded.reserve(estimated_decoded_len(unpadded_len));
+    // Payloads in either alphabet can lack the characters that tell them apart
+    let standard = BASE64_STANDARD_ANY_PADDING.decode_vec(bytes, decoded);
+    if standard.is_err() {
+        decoded.clear();
+        BASE64_URL_SAFE_ANY_PADDING.decode_vec(bytes, decoded)?;
+    }
+
+    Ok(())
 }
 
-/// Lists all available top-level folders across all Apple Notes accounts.
-///
-/// This is a convenience function that uses the embedded AppleScript.
-/// For more control, use the [`Exporter`] struct.
-///
-/// # Example
-///
-/// ```no_run
-/// use apple_notes_exporter_rs::list_folders;
-///
-/// list_folders().expect("Failed to list folders");
-/// ```
-pub fn list_folders() -> Result<()> {
-    Exporter::new().list_folders()
+/// Appends `input` to `decoded` with its `%XX` escapes decoded.
+fn percent_decode_into(input: &str, decoded: &mut Vec<u8>) {
+    let bytes = input.as_bytes();
+    decoded.reserve(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = input
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
 }
 
-/// Exports a folder recursively to HTML files.
-///
-/// This is a convenience function that uses the embedded AppleScript.
-/// For more control, use the [`Exporter`] struct.
-///
-/// This function searches all accounts for the folder. If a folder with the
-/// same name exists in multiple accounts, use [`export_folder_from_account`]
-/// to specify which account to use.
-///
-/// # Arguments
+/// Returns the number of bytes that `base64_len` characters of base64 decode to.
 ///
-/// * `folder` - The folder name to export.
-/// * `output_dir` - The directory where exported notes will be saved.
+/// The result is exact for unpadded input. For input that still has its
+/// trailing `=` padding it is at most 2 bytes too large, which is close
+/// enough for size limits and progress estimates.
 ///
 /// # Example
 ///
-/// ```no_run
-/// use apple_notes_exporter_rs::export_folder;
+/// ```
+/// use apple_notes_exporter_rs::estimated_decoded_len;
 ///
-/// export_folder("My Notes", "./exports").expect("Failed to export");
+/// assert_eq!(estimated_decoded_len("aGVsbG8".len()), 5); // "hello"
+/// assert_eq!(estimated_decoded_len("aGVsbG8=".len()), 6);
+/// assert_eq!(estimated_decoded_len(0), 0);
 /// ```
-pub fn export_folder<P: AsRef<Path>>(folder: &str, output_dir: P) -> Result<()> {
-    Exporter::new().export_folder(folder, output_dir)
+pub fn estimated_decoded_len(base64_len: usize) -> usize {
+    // Every 4 characters carry 3 bytes, and a trailing 2 or 3 carry 1 or 2
+    base64_len / 4 * 3 + (base64_len % 4 * 3 / 4)
 }
 
-/// Exports a folder from a specific account recursively to HTML files.
-///
-/// This is a convenience function that uses the embedded AppleScript.
-/// For more control, use the [`Exporter`] struct.
-///
-/// This is useful when a folder with the same name exists in multiple accounts.
-///
-/// # Arguments
+/// The image MIME types given their own file extension when extracted, and
+/// that extension.
+const IMAGE_TYPES: [(&str, &str); 10] = [
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/jpg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/svg+xml", "svg"),
+    ("image/bmp", "bmp"),
+    ("image/tiff", "tiff"),
+    ("image/heic", "heic"),
+    ("image/heif", "heif"),
+];
+
+/// The MIME types of [`IMAGE_TYPES`], for [`supported_image_mime_types`].
+static IMAGE_MIME_TYPES: [&str; IMAGE_TYPES.len()] = {
+    let mut mime_types = [""; IMAGE_TYPES.len()];
+    let mut i = 0;
+    while i < IMAGE_TYPES.len() {
+        mime_types[i] = IMAGE_TYPES[i].0;
+        i += 1;
+    }
+    mime_types
+};
+
+/// Returns the image MIME types the extractor recognizes, such as
+/// `image/png` and `image/heic`.
 ///
-/// * `account` - The account name (e.g., "iCloud", "Google", "On My Mac").
-/// * `folder` - The folder name to export.
-/// * `output_dir` - The directory where exported notes will be saved.
+/// Images of these types are written with a matching file extension. Other
+/// `image/*` data URLs are still extracted, with a `.bin` extension, and
+/// data URLs that are not images are left inline.
 ///
 /// # Example
 ///
-/// ```no_run
-/// use apple_notes_exporter_rs::export_folder_from_account;
-///
-/// // Export "Work" folder from iCloud account
-/// export_folder_from_account("iCloud", "Work", "./exports").expect("Failed to export");
+/// ```
+/// use apple_notes_exporter_rs::supported_image_mime_types;
 ///
-/// // Export "Work" folder from Google account
-/// export_folder_from_account("Google", "Work", "./google_exports").expect("Failed to export");
+/// assert!(supported_image_mime_types().contains(&"image/png"));
+/// assert!(supported_image_mime_types().contains(&"image/heic"));
+/// assert!(!supported_image_mime_types().contains(&"application/pdf"));
 /// ```
-pub fn export_folder_from_account<P: AsRef<Path>>(
-    account: &str,
-    folder: &str,
-    output_dir: P,
-) -> Result<()> {
-    Exporter::new().export_folder_from_account(account, folder, output_dir)
-}
-
-// =============================================================================
-// Attachment Extraction
-// =============================================================================
-
-/// Information about an extracted attachment.
-#[derive(Debug, Clone)]
-pub struct ExtractedAttachment {
-    /// The file path where the attachment was saved.
-    pub path: PathBuf,
-    /// The original data URL that was replaced.
-    pub original_data_url: String,
-    /// The MIME type of the attachment (e.g., "image/png").
-    pub mime_type: String,
+pub fn supported_image_mime_types() -> &'static [&'static str] {
+    &IMAGE_MIME_TYPES
 }
 
-/// Result of extracting attachments from an HTML file.
-#[derive(Debug)]
-pub struct ExtractionResult {
-    /// The HTML file that was processed.
-    pub html_path: PathBuf,
-    /// The attachments that were extracted.
-    pub attachments: Vec<ExtractedAttachment>,
-    /// Whether the HTML file was modified.
-    pub html_modified: bool,
+/// Returns the file extension used for an attachment with the given MIME type.
+fn extension_for_mime_type(mime_type: &str) -> &'static str {
+    IMAGE_TYPES
+        .iter()
+        .find(|(image_type, _)| *image_type == mime_type)
+        .map_or("bin", |(_, extension)| extension)
 }
 
 /// Extracts base64-encoded images from an HTML file and saves them to an attachments folder.
@@ -467,6 +4802,7 @@ pub struct ExtractionResult {
 /// `My Note -- abc123-attachments/attachment-001.png`, etc.
 ///
 /// The HTML file is updated in-place to reference the local files instead of data URLs.
+/// Images may be encoded with the standard or the URL-safe base64 alphabet.
 ///
 /// # Arguments
 ///
@@ -486,17 +4822,82 @@ pub struct ExtractionResult {
 ///
 /// println!("Extracted {} attachments", result.attachments.len());
 /// ```
+///
+/// Both base64 alphabets decode to the same image:
+///
+/// ```
+/// use apple_notes_exporter_rs::extract_attachments_from_html;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let html_path = dir.path().join("Note -- p1.html");
+/// std::fs::write(
+///     &html_path,
+///     r#"<img src="data:image/png;base64,+/8="><img src="data:image/png;base64,-_8">"#,
+/// )
+/// .unwrap();
+///
+/// let result = extract_attachments_from_html(&html_path).unwrap();
+/// assert_eq!(result.attachments.len(), 2);
+/// for attachment in &result.attachments {
+///     assert_eq!(std::fs::read(&attachment.path).unwrap(), [0xfb, 0xff]);
+/// }
+/// ```
 pub fn extract_attachments_from_html<P: AsRef<Path>>(html_path: P) -> Result<ExtractionResult> {
-    let html_path = html_path.as_ref();
-    let html_content = fs::read_to_string(html_path)?;
-
-    let document = Html::parse_document(&html_content);
-    let img_selector = Selector::parse("img").unwrap();
+    extract_attachments_from_html_with_options(html_path, &ExtractOptions::default())
+}
 
-    let mut attachments = Vec::new();
-    let mut modified_html = html_content.clone();
-    let mut attachment_count = 0;
+/// Extracts base64-encoded images from an HTML file using the given options.
+///
+/// See [`extract_attachments_from_html`] for details.
+///
+/// Attachments and the rewritten HTML are written to a temporary file that
+/// is then renamed into place, so an interrupted or failed run never leaves
+/// the note or an attachment partially written.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(unix)]
+/// # {
+/// use std::fs;
+///
+/// use apple_notes_exporter_rs::{extract_attachments_from_html_with_options, ExtractOptions};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let html_path = dir.path().join("Note -- abc.html");
+/// let image = r#"<img src="data:image/png;base64,iVBORw0KGgo=">"#;
+/// let html = format!("<html><body>{image}{image}</body></html>");
+/// fs::write(&html_path, &html).unwrap();
+///
+/// // Something in the way of the second attachment makes extraction fail
+/// fs::create_dir_all(dir.path().join("Note -- abc-attachments/attachment-002.png")).unwrap();
+/// let options = ExtractOptions::default();
+/// assert!(extract_attachments_from_html_with_options(&html_path, &options).is_err());
+///
+/// // The note is untouched, and no temporary files are left behind
+/// assert_eq!(fs::read_to_string(&html_path).unwrap(), html);
+/// let names: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+/// assert_eq!(names.len(), 2);
+/// # }
+/// ```
+pub fn extract_attachments_from_html_with_options<P: AsRef<Path>>(
+    html_path: P,
+    options: &ExtractOptions,
+) -> Result<ExtractionResult> {
+    extract_attachments_in_place(
+        html_path.as_ref(),
+        options,
+        &mut extractor::ExtractBuffers::default(),
+    )
+}
 
+/// Extracts the images of the note at `html_path` into its attachments
+/// folder and points the note at them, reusing `buffers`.
+fn extract_attachments_in_place(
+    html_path: &Path,
+    options: &ExtractOptions,
+    buffers: &mut extractor::ExtractBuffers,
+) -> Result<ExtractionResult> {
     // Determine the attachments folder name based on the HTML file stem
     let html_stem = html_path
         .file_stem()
@@ -505,84 +4906,465 @@ pub fn extract_attachments_from_html<P: AsRef<Path>>(html_path: P) -> Result<Ext
     let attachments_dir = html_path
         .parent()
         .unwrap_or(Path::new("."))
-        .join(format!("{html_stem}-attachments"));
+        .join(format!("{html_stem}{}", options.attachments_suffix));
 
-    for element in document.select(&img_selector) {
-        let Some(src) = element.value().attr("src") else {
-            continue;
-        };
+    extract_attachments_impl(html_path, &attachments_dir, true, options, buffers)
+}
 
-        // Check if this is a data URL
-        if !src.starts_with("data:image/") {
-            continue;
+/// Extracts base64-encoded images from an HTML file into `out_dir`, leaving
+/// the HTML file untouched.
+///
+/// This is for read-only pipelines: the images are written directly into
+/// `out_dir` (created if needed) instead of an attachments folder next to
+/// the note, and each [`ExtractedAttachment`] pairs the original data URL
+/// with the file it was written to. [`ExtractionResult::html_modified`] is
+/// always `false`.
+///
+/// Files are named as with [`extract_attachments_from_html`], so give each
+/// note its own `out_dir`, or use [`AttachmentNaming::ContentHash`] through
+/// [`extract_attachments_to_with_options`], to keep notes from overwriting
+/// each other's attachments.
+///
+/// # Example
+///
+/// ```
+/// use std::fs;
+///
+/// use apple_notes_exporter_rs::extract_attachments_to;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let html_path = dir.path().join("Note -- abc.html");
+/// let html = r#"<img src="data:image/png;base64,iVBORw0KGgo=">"#;
+/// fs::write(&html_path, html).unwrap();
+///
+/// let out_dir = dir.path().join("images");
+/// let result = extract_attachments_to(&html_path, &out_dir).unwrap();
+///
+/// let attachment = &result.attachments[0];
+/// assert_eq!(attachment.original_data_url, "data:image/png;base64,iVBORw0KGgo=");
+/// assert_eq!(attachment.path, out_dir.join("attachment-001.png"));
+/// assert!(!result.html_modified);
+/// assert_eq!(fs::read_to_string(&html_path).unwrap(), html);
+/// ```
+pub fn extract_attachments_to<P: AsRef<Path>, Q: AsRef<Path>>(
+    html_path: P,
+    out_dir: Q,
+) -> Result<ExtractionResult> {
+    extract_attachments_to_with_options(html_path, out_dir, &ExtractOptions::default())
+}
+
+/// Extracts base64-encoded images from an HTML file into `out_dir` using the
+/// given options, leaving the HTML file untouched.
+///
+/// See [`extract_attachments_to`] for details.
+/// [`ExtractOptions::attachments_suffix`] is not used.
+pub fn extract_attachments_to_with_options<P: AsRef<Path>, Q: AsRef<Path>>(
+    html_path: P,
+    out_dir: Q,
+    options: &ExtractOptions,
+) -> Result<ExtractionResult> {
+    extract_attachments_impl(
+        html_path.as_ref(),
+        out_dir.as_ref(),
+        false,
+        options,
+        &mut extractor::ExtractBuffers::default(),
+    )
+}
+
+/// Extracts base64-encoded images from `html` into `out_dir`, and returns
+/// the HTML pointing at the extracted files along with them.
+///
+/// For HTML that isn't in a file, such as a note read with
+/// [`Exporter::get_note_html`]. The images are referenced as
+/// `<out_dir name>/<file>`, so save the HTML in `out_dir`'s parent
+/// directory for the references to resolve. `out_dir` is created if any
+/// image is extracted; HTML without images is returned unchanged.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::extract_attachments_from_html_str;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let html = r#"<p>Logo</p><img src="data:image/png;base64,iVBORw0KGgo=">"#;
+///
+/// let out_dir = dir.path().join("Logo-attachments");
+/// let (html, attachments) = extract_attachments_from_html_str(html, &out_dir).unwrap();
+///
+/// assert_eq!(attachments[0].path, out_dir.join("attachment-001.png"));
+/// assert_eq!(
+///     html,
+///     r#"<html><head></head><body><p>Logo</p><img src="Logo-attachments/attachment-001.png"></body></html>"#
+/// );
+/// ```
+pub fn extract_attachments_from_html_str<P: AsRef<Path>>(
+    html: &str,
+    out_dir: P,
+) -> Result<(String, Vec<ExtractedAttachment>)> {
+    extract_attachments_from_html_str_with_options(html, out_dir, &ExtractOptions::default())
+}
+
+/// Extracts base64-encoded images from `html` into `out_dir` using the given
+/// options, and returns the HTML pointing at the extracted files along with
+/// them.
+///
+/// See [`extract_attachments_from_html_str`] for details.
+/// [`ExtractOptions::attachments_suffix`] and
+/// [`ExtractOptions::preserve_timestamps`] are not used.
+pub fn extract_attachments_from_html_str_with_options<P: AsRef<Path>>(
+    html: &str,
+    out_dir: P,
+    options: &ExtractOptions,
+) -> Result<(String, Vec<ExtractedAttachment>)> {
+    let images = extract_images(
+        html,
+        "note",
+        out_dir.as_ref(),
+        true,
+        options,
+        &mut extractor::DecodeBuffers::default(),
+    )?;
+    let html = images.html.unwrap_or_else(|| html.to_string());
+    Ok((html, images.attachments))
+}
+
+/// Extracts the images of the note at `html_path` into `attachments_dir`,
+/// pointing the note at the extracted files if `rewrite_html` is set.
+fn extract_attachments_impl(
+    html_path: &Path,
+    attachments_dir: &Path,
+    rewrite_html: bool,
+    options: &ExtractOptions,
+    buffers: &mut extractor::ExtractBuffers,
+) -> Result<ExtractionResult> {
+    let started = Instant::now();
+    buffers.html.clear();
+    fs::File::open(html_path)?.read_to_string(&mut buffers.html)?;
+    let note_modified = if options.preserve_timestamps {
+        Some(fs::metadata(html_path)?.modified()?)
+    } else {
+        None
+    };
+
+    let html_stem = html_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("note");
+    let images = extract_images(
+        &buffers.html,
+        html_stem,
+        attachments_dir,
+        rewrite_html,
+        options,
+        &mut buffers.decode,
+    )?;
+
+    // Write the edited document if any attachments were extracted
+    let html_modified = images.html.is_some();
+    if let Some(html) = &images.html {
+        write_atomically(html_path, html.as_bytes())?;
+    }
+
+    if let Some(note_modified) = note_modified {
+        if html_modified {
+            timestamps::set_modified(html_path, note_modified)?;
         }
+        for attachment in &images.attachments {
+            timestamps::set_modified(&attachment.path, note_modified)?;
+        }
+    }
 
-        // Parse the data URL: data:image/png;base64,iVBORw0...
-        let Some((mime_part, base64_data)) = src.strip_prefix("data:").and_then(|s| s.split_once(",")) else {
-            continue;
-        };
+    let stats = &mut buffers.stats;
+    stats.files += 1;
+    stats.html_bytes += buffers.html.len() as u64;
+    stats.attachments += images.attachments.len();
+    stats.attachment_bytes += images.attachments.iter().map(|a| a.size).sum::<u64>();
+    stats.elapsed += started.elapsed();
 
-        // Extract MIME type (e.g., "image/png;base64" -> "image/png")
-        let mime_type = mime_part.split(';').next().unwrap_or("image/png");
+    Ok(ExtractionResult {
+        html_path: html_path.to_path_buf(),
+        attachments: images.attachments,
+        html_modified,
+        attachments_dir: attachments_dir
+            .is_dir()
+            .then(|| attachments_dir.to_path_buf()),
+        failed_downloads: images.failed_downloads,
+        kept_inline: images.kept_inline,
+        skipped: images.skipped,
+    })
+}
 
-        // Determine file extension from MIME type
-        let extension = match mime_type {
-            "image/png" => "png",
-            "image/jpeg" | "image/jpg" => "jpg",
-            "image/gif" => "gif",
-            "image/webp" => "webp",
-            "image/svg+xml" => "svg",
-            "image/bmp" => "bmp",
-            "image/tiff" => "tiff",
-            _ => "bin",
+/// What [`extract_images`] did with the images in a note's HTML.
+struct ExtractedImages {
+    /// The HTML pointing at the extracted files, if any were extracted and
+    /// the HTML was to be rewritten.
+    html: Option<String>,
+    attachments: Vec<ExtractedAttachment>,
+    failed_downloads: Vec<FailedDownload>,
+    kept_inline: Vec<InlineAttachment>,
+    skipped: Vec<SkippedAttachment>,
+}
+
+/// Extracts the images in `html_content` into `attachments_dir`, naming them
+/// for the note `html_stem`, and returns the HTML pointing at the extracted
+/// files if `rewrite_html` is set.
+fn extract_images(
+    html_content: &str,
+    html_stem: &str,
+    attachments_dir: &Path,
+    rewrite_html: bool,
+    options: &ExtractOptions,
+    buffers: &mut extractor::DecodeBuffers,
+) -> Result<ExtractedImages> {
+    let extractor::DecodeBuffers { payload, decoded } = buffers;
+    let mut document = Html::parse_document(html_content);
+    let img_selector = Selector::parse("img").unwrap();
+
+    let mut attachments = Vec::new();
+    let mut failed_downloads = Vec::new();
+    let mut kept_inline = Vec::new();
+    let mut skipped = Vec::new();
+    let mut attachment_count = 0;
+    let mut used_names = HashSet::new();
+
+    // Collect the images up front so the document can be edited while extracting
+    let images: Vec<_> = document
+        .select(&img_selector)
+        .filter_map(|element| {
+            let src = element.value().attr("src")?.to_string();
+            let alt = element.value().attr("alt").map(str::to_string);
+            Some((element.id(), src, alt))
+        })
+        .collect();
+
+    for (node_id, src, alt) in images {
+        let (data, mime_type) = match split_data_url(&src) {
+            // Parse the data URL: data:image/png;base64,iVBORw0...
+            Some(data_url) => {
+                let skip = |reason| SkippedAttachment {
+                    source: format!("{},", src.trim().split(',').next().unwrap_or_default()),
+                    reason,
+                };
+                if !data_url.mime_type.starts_with("image/")
+                    || !options.extracts_mime_type(&data_url.mime_type)
+                {
+                    skipped.push(skip(SkipReason::Filtered));
+                    continue;
+                }
+
+                // Refuse to decode anything above the size limit. Percent-encoded
+                // payloads decode to at most one byte per character
+                if let Some(limit) = options.max_attachment_bytes {
+                    let max_len = if data_url.is_base64 {
+                        estimated_decoded_len(data_url.payload.trim_end_matches('=').len())
+                    } else {
+                        data_url.payload.len()
+                    };
+                    if max_len > limit {
+                        skipped.push(skip(SkipReason::TooLarge));
+                        continue;
+                    }
+                }
+
+                if decode_data_url_payload_into(
+                    data_url.payload,
+                    data_url.is_base64,
+                    payload,
+                    decoded,
+                )
+                .is_err()
+                {
+                    skipped.push(skip(SkipReason::DecodeFailed));
+                    continue;
+                }
+                if let Some(limit) = options.max_attachment_bytes
+                    && decoded.len() > limit
+                {
+                    skipped.push(skip(SkipReason::TooLarge));
+                    continue;
+                }
+                if let Some(threshold) = options.inline_threshold_bytes
+                    && decoded.len() < threshold
+                {
+                    kept_inline.push(InlineAttachment {
+                        mime_type: data_url.mime_type,
+                        size: decoded.len() as u64,
+                    });
+                    continue;
+                }
+                (std::mem::take(decoded), data_url.mime_type)
+            }
+            None => match options.download(&src) {
+                Some(Ok(image)) => image,
+                Some(Err(reason)) => {
+                    failed_downloads.push(FailedDownload { url: src, reason });
+                    continue;
+                }
+                None => {
+                    if has_url_scheme(&src) {
+                        skipped.push(SkippedAttachment {
+                            source: src,
+                            reason: SkipReason::UnknownScheme,
+                        });
+                    }
+                    continue;
+                }
+            },
         };
+        let (decoded_data, mime_type) = options.transform(data, &mime_type);
 
-        // Decode base64 data
-        let decoded_data = BASE64_STANDARD.decode(base64_data)?;
+        // Determine file extension from MIME type
+        let extension = extension_for_mime_type(mime_type);
 
         // Create attachments directory if needed
         if !attachments_dir.exists() {
-            fs::create_dir_all(&attachments_dir)?;
+            fs::create_dir_all(attachments_dir)?;
         }
 
         // Generate filename
         attachment_count += 1;
-        let filename = format!("attachment-{attachment_count:03}.{extension}");
+        let context = AttachmentContext {
+            note_stem: html_stem,
+            index: attachment_count,
+            mime_type,
+            extension,
+            byte_len: decoded_data.len(),
+        };
+        let number = match &options.shared_counter {
+            Some(counter) => counter.fetch_add(1, Ordering::Relaxed) + 1,
+            None => attachment_count,
+        };
+        let filename = options.naming.file_name(
+            &context,
+            number,
+            alt.as_deref(),
+            &decoded_data,
+            &mut used_names,
+        );
         let attachment_path = attachments_dir.join(&filename);
 
         // Write the attachment file
-        fs::write(&attachment_path, &decoded_data)?;
+        write_atomically(&attachment_path, &decoded_data)?;
 
-        // Calculate relative path from HTML file to attachment
-        let attachments_folder_name = attachments_dir
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("attachments");
-        let relative_path = format!("{attachments_folder_name}/{filename}");
+        // Point this image element at the extracted file, relative to the HTML file
+        if rewrite_html
+            && let Some(mut node) = document.tree.get_mut(node_id)
+            && let Node::Element(element) = node.value()
+        {
+            let attachments_folder_name = attachments_dir
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("attachments");
+            set_attribute(
+                element,
+                "src",
+                &options.image_src(&format!("{attachments_folder_name}/{filename}")),
+            );
+        }
 
-        // Replace the data URL with the relative path in the HTML
-        modified_html = modified_html.replace(src, &relative_path);
+        let mime_type = mime_type.to_string();
 
         attachments.push(ExtractedAttachment {
             path: attachment_path,
-            original_data_url: src.to_string(),
-            mime_type: mime_type.to_string(),
+            original_data_url: src,
+            mime_type,
+            size: decoded_data.len() as u64,
         });
+        // Kept for the next image
+        *decoded = decoded_data;
     }
 
-    // Write modified HTML if any attachments were extracted
-    let html_modified = !attachments.is_empty();
-    if html_modified {
-        fs::write(html_path, &modified_html)?;
-    }
+    // Serialize the edited document if any attachments were extracted
+    let html = (rewrite_html && !attachments.is_empty()).then(|| {
+        let mut html = document.html();
+        // The parser drops a byte order mark, which the note may need
+        if html_content.starts_with(encoding::BOM) {
+            html.insert(0, encoding::BOM);
+        }
+        html
+    });
 
-    Ok(ExtractionResult {
-        html_path: html_path.to_path_buf(),
+    Ok(ExtractedImages {
+        html,
         attachments,
-        html_modified,
+        failed_downloads,
+        kept_inline,
+        skipped,
+    })
+}
+
+/// Returns whether `src` starts with a URL scheme such as `https:`, rather
+/// than being a relative path.
+fn has_url_scheme(src: &str) -> bool {
+    src.trim().split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
     })
 }
 
+/// Writes `contents` to `path` through a temporary file in the same
+/// directory, so an interrupted write leaves either the old file or the new
+/// one, never a truncated file. An existing file keeps its permissions.
+///
+/// Each call uses its own temporary file, so threads writing the same path
+/// at once don't interfere; the last rename wins.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let temp_path = dir.join(format!(
+        ".{name}.{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let file = fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)?;
+    // Only clean up once the file is ours
+    let result = write_and_rename(file, &temp_path, path, contents);
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Writes `contents` to `file`, newly created at `temp_path`, and renames it
+/// to `path`.
+fn write_and_rename(
+    mut file: fs::File,
+    temp_path: &Path,
+    path: &Path,
+    contents: &[u8],
+) -> io::Result<()> {
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp_path, metadata.permissions())?;
+    }
+    fs::rename(temp_path, path)
+}
+
+/// Sets the value of an existing attribute on a parsed element.
+pub(crate) fn set_attribute(element: &mut scraper::node::Element, name: &str, value: &str) {
+    if let Some((_, existing)) = element
+        .attrs
+        .iter_mut()
+        .find(|(attr, _)| &*attr.local == name)
+    {
+        *existing = StrTendril::from_slice(value);
+    }
+}
+
 /// Extracts attachments from all HTML files in a directory (recursively).
 ///
 /// # Arguments
@@ -605,15 +5387,36 @@ pub fn extract_attachments_from_html<P: AsRef<Path>>(html_path: P) -> Result<Ext
 /// println!("Extracted {total_attachments} attachments from {} files", results.len());
 /// ```
 pub fn extract_attachments_from_directory<P: AsRef<Path>>(dir: P) -> Result<Vec<ExtractionResult>> {
+    extract_attachments_from_directory_with_options(dir, &ExtractOptions::default())
+}
+
+/// Extracts attachments from all HTML files in a directory (recursively) using
+/// the given options.
+///
+/// See [`extract_attachments_from_directory`] for details.
+pub fn extract_attachments_from_directory_with_options<P: AsRef<Path>>(
+    dir: P,
+    options: &ExtractOptions,
+) -> Result<Vec<ExtractionResult>> {
     let dir = dir.as_ref();
     let mut results = Vec::new();
 
-    extract_attachments_recursive(dir, &mut results)?;
+    extract_attachments_recursive(
+        dir,
+        options,
+        &mut extractor::ExtractBuffers::default(),
+        &mut results,
+    )?;
 
     Ok(results)
 }
 
-fn extract_attachments_recursive(dir: &Path, results: &mut Vec<ExtractionResult>) -> Result<()> {
+fn extract_attachments_recursive(
+    dir: &Path,
+    options: &ExtractOptions,
+    buffers: &mut extractor::ExtractBuffers,
+    results: &mut Vec<ExtractionResult>,
+) -> Result<()> {
     if !dir.is_dir() {
         return Ok(());
     }
@@ -627,16 +5430,59 @@ fn extract_attachments_recursive(dir: &Path, results: &mut Vec<ExtractionResult>
             if path
                 .file_name()
                 .and_then(|s| s.to_str())
-                .is_some_and(|name| name.ends_with("-attachments"))
+                .is_some_and(|name| name.ends_with(&options.attachments_suffix))
             {
                 continue;
             }
-            extract_attachments_recursive(&path, results)?;
+            let name = entry.file_name();
+            match options.for_subdirectory(&name.to_string_lossy()) {
+                Some(options) => extract_attachments_recursive(&path, &options, buffers, results)?,
+                None => extract_attachments_recursive(&path, options, buffers, results)?,
+            }
         } else if path.extension().is_some_and(|ext| ext == "html") {
-            let result = extract_attachments_from_html(&path)?;
+            let result = extract_attachments_in_place(&path, options, buffers)?;
             results.push(result);
         }
     }
 
     Ok(())
 }
+
+/// Counts the extracted attachments by MIME type.
+///
+/// Returns the number of attachments and their total size in bytes for each
+/// MIME type, sorted by MIME type.
+///
+/// # Example
+///
+/// ```
+/// use std::fs;
+///
+/// use apple_notes_exporter_rs::{extract_attachments_from_html, summarize_by_mime};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let html_path = dir.path().join("Note -- p1.html");
+/// fs::write(
+///     &html_path,
+///     r#"<img src="data:image/png;base64,iVBORw0KGgo=">
+///        <img src="data:image/jpeg;base64,/9j/">
+///        <img src="data:image/png;base64,iVBORw0KGgo=">"#,
+/// )
+/// .unwrap();
+/// let results = [extract_attachments_from_html(&html_path).unwrap()];
+///
+/// let summary = summarize_by_mime(&results);
+/// assert_eq!(summary["image/png"], (2, 16));
+/// assert_eq!(summary["image/jpeg"], (1, 3));
+/// ```
+pub fn summarize_by_mime(results: &[ExtractionResult]) -> BTreeMap<String, (usize, u64)> {
+    let mut summary: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+
+    for attachment in results.iter().flat_map(|result| &result.attachments) {
+        let (count, bytes) = summary.entry(attachment.mime_type.clone()).or_default();
+        *count += 1;
+        *bytes += attachment.size;
+    }
+
+    summary
+}
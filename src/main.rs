@@ -1,19 +1,35 @@
 //! Apple Notes Exporter CLI
 //!
 //! A command-line tool for exporting Apple Notes folders to the file system via AppleScript.
-//! 
+//!
 //! ## Quick Start
-//! 
+//!
 //! ```bash
 //! cargo run -- export 'My Notes' ./exports
 //! ```
+//!
+//! ## Exit Codes
+//!
+//! - `0` - success
+//! - `1` - any other error
+//! - `2` - permission denied (e.g. the output directory is not writable, or
+//!   Automation permission for Notes was not granted)
+//! - `3` - the folder to export was not found
+//! - `4` - unsupported platform (not macOS)
 
-use std::path::PathBuf;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Local};
+use clap::{Parser, Subcommand, ValueEnum};
 
-use apple_notes_exporter_rs::{extract_attachments_from_directory, Exporter};
+use apple_notes_exporter_rs::{
+    DEFAULT_DATE_FORMAT, DEFAULT_FILENAME_TEMPLATE, DEFAULT_LARGE_NOTE_BYTES, ExportError,
+    ExportLayout, ExportOptions, ExportSummary, Exporter, ExtractOptions, FailedNote,
+    NoteCountCheck, OnExisting, Stylesheet, TitleFilter, TruncatedNote, export_summary_json,
+    extract_attachments_from_directory_with_options, write_extraction_report,
+};
 
 /// Relative path to the vendored AppleScript (used when running from source).
 const VENDORED_SCRIPT_PATH: &str = "vendor/apple-notes-exporter/scripts/export_notes.applescript";
@@ -21,6 +37,14 @@ const VENDORED_SCRIPT_PATH: &str = "vendor/apple-notes-exporter/scripts/export_n
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Export Apple Notes folders via AppleScript")]
 struct Cli {
+    /// Append everything the AppleScript prints to this file, with timestamps
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Don't wait for Notes to finish starting up before the first command
+    #[arg(long, global = true)]
+    no_wait_for_ready: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -31,6 +55,15 @@ enum Commands {
     #[command(alias = "ls")]
     List,
 
+    /// List the notes in a folder and its subfolders, without exporting them
+    ///
+    /// Prints one note per line: its title, id, and modification date, separated by tabs.
+    ListNotes {
+        /// Apple Notes folder name, or "AccountName:FolderName"
+        #[arg(value_name = "FOLDER")]
+        folder: String,
+    },
+
     /// Export a folder recursively to HTML files
     ///
     /// The folder search uses breadth-first search and searches recursively at ALL levels
@@ -38,8 +71,9 @@ enum Commands {
     /// If a folder name exists in multiple accounts, use "AccountName:FolderName" format
     /// (e.g., "iCloud:My Notes").
     Export {
-        /// Apple Notes folder name to export recursively.
+        /// Apple Notes folder name to export recursively, or "-" to read one per line from stdin.
         /// Use "AccountName:FolderName" format for folders in specific accounts.
+        /// Write a colon in a folder name as "\:" when no account is given.
         #[arg(value_name = "FOLDER")]
         folder: String,
 
@@ -51,6 +85,165 @@ enum Commands {
         /// By default, images are extracted to "<note-name>-attachments/" subdirectories.
         #[arg(long)]
         no_extract_attachments: bool,
+
+        /// What to do when an exported file already exists in OUTPUT_DIR
+        #[arg(long, value_enum, default_value_t = OnExistingArg::Overwrite)]
+        on_existing: OnExistingArg,
+
+        /// Leave embedded images larger than this many bytes inline instead of extracting them
+        #[arg(long, value_name = "BYTES")]
+        max_attachment_bytes: Option<usize>,
+
+        /// Leave embedded images smaller than this many bytes inline instead of extracting them
+        #[arg(long, value_name = "BYTES")]
+        inline_threshold_bytes: Option<usize>,
+
+        /// Name notes with a template using {title}, {id}, {date}, and {created}
+        #[arg(long, value_name = "TEMPLATE", default_value = DEFAULT_FILENAME_TEMPLATE)]
+        filename_template: String,
+
+        /// Format {date} and {created} in filenames with a strftime-style format (e.g. "%d.%m.%Y")
+        #[arg(long, value_name = "FORMAT", default_value = DEFAULT_DATE_FORMAT)]
+        date_format: String,
+
+        /// Prefix note filenames with their position in the folder (e.g. "007 - Title -- id.html");
+        /// needs a custom script that prints `exported:` lines
+        #[arg(long)]
+        index_prefix: bool,
+
+        /// Don't create directories for subfolders that contain no notes
+        #[arg(long)]
+        prune_empty: bool,
+
+        /// Join chains of folders that only contain one subfolder, e.g. "A - B - C"
+        #[arg(long)]
+        collapse_single_child: bool,
+
+        /// Shorten note titles in filenames so no exported path is longer than this many bytes
+        #[arg(long, value_name = "BYTES")]
+        max_path_len: Option<usize>,
+
+        /// Leave checklists as plain bullets instead of adding checkboxes
+        #[arg(long)]
+        plain_checklists: bool,
+
+        /// Export only the notes directly in the folder, not its subfolders
+        #[arg(long)]
+        no_recursive: bool,
+
+        /// Allow exporting the "Recently Deleted" folder, which is skipped by default
+        #[arg(long)]
+        include_recently_deleted: bool,
+
+        /// Export only the notes pinned in Notes
+        #[arg(long)]
+        pinned_only: bool,
+
+        /// Group notes by modification date instead of by folder, into directories
+        /// named with this strftime pattern (e.g. "%Y/%m")
+        #[arg(long, value_name = "PATTERN")]
+        by_date: Option<String>,
+
+        /// Export only notes whose titles match this glob (e.g. "Meeting *"); repeatable
+        #[arg(long, value_name = "PATTERN")]
+        include: Vec<String>,
+
+        /// Skip notes whose titles match this glob; repeatable
+        #[arg(long, value_name = "PATTERN")]
+        exclude: Vec<String>,
+
+        /// Embed the bundled stylesheet in each note for easier reading in a browser
+        #[arg(long)]
+        styled: bool,
+
+        /// Embed this CSS file in each note instead of the bundled stylesheet
+        #[arg(long, value_name = "PATH")]
+        css: Option<PathBuf>,
+
+        /// Remove Apple inline styles, classes and empty tags from each note
+        #[arg(long)]
+        clean_html: bool,
+
+        /// Start each note with a UTF-8 byte order mark, for Windows tools that need one
+        #[arg(long)]
+        utf8_bom: bool,
+
+        /// Write each note with LF line endings, for exports kept in git
+        #[arg(long)]
+        normalize_line_endings: bool,
+
+        /// Also write each note's text to a .txt file next to it, for search indexing
+        #[arg(long)]
+        text_twin: bool,
+
+        /// Mark shared notes with data-shared and data-participants attributes on their body
+        #[arg(long)]
+        mark_shared: bool,
+
+        /// Read notes that came out empty again in case iCloud is still syncing them, and skip
+        /// the ones that stay empty
+        #[arg(long)]
+        wait_for_sync: bool,
+
+        /// Fail if fewer notes were exported than the folder contains
+        #[arg(long)]
+        verify_count: bool,
+
+        /// Date notes and extracted attachments with the note's modification date in Notes
+        #[arg(long)]
+        preserve_timestamps: bool,
+
+        /// Describe the Notes folder each directory came from in a folder.json file
+        #[arg(long)]
+        folder_metadata: bool,
+
+        /// Record where the export came from in EXPORT_INFO.txt in the output directory
+        #[arg(long)]
+        provenance: bool,
+
+        /// Print a summary of the export to stdout in this format when done.
+        /// Any other output of the script goes to stderr then
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        summary: Option<SummaryFormat>,
+    },
+
+    /// Export every folder of every account recursively
+    ///
+    /// Each top-level folder is exported into "<OUTPUT_DIR>/<account>/<folder>/".
+    ExportAll {
+        /// Output directory for exported notes
+        #[arg(value_name = "OUTPUT_DIR")]
+        output_dir: PathBuf,
+
+        /// Skip extracting embedded images from HTML files.
+        #[arg(long)]
+        no_extract_attachments: bool,
+
+        /// Export up to this many folders at once (higher values may destabilize Notes)
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        max_concurrency: usize,
+    },
+
+    /// Export notes that failed in an earlier export again
+    ///
+    /// Each note is written where exporting FOLDER to OUTPUT_DIR puts it, so
+    /// the tree looks as if the earlier export had succeeded.
+    Retry {
+        /// The folder that was exported, as passed to "export"
+        #[arg(value_name = "FOLDER")]
+        folder: String,
+
+        /// Output directory of the earlier export
+        #[arg(value_name = "OUTPUT_DIR")]
+        output_dir: PathBuf,
+
+        /// Titles (or ids) of the notes to export, as reported by the earlier export
+        #[arg(value_name = "NOTE", required = true)]
+        notes: Vec<String>,
+
+        /// Skip extracting embedded images from HTML files.
+        #[arg(long)]
+        no_extract_attachments: bool,
     },
 
     /// Extract embedded images from previously exported HTML files
@@ -58,24 +251,107 @@ enum Commands {
     /// Scans a directory for HTML files and extracts base64-encoded images
     /// to "<note-name>-attachments/" subdirectories. Updates the HTML files
     /// to reference the extracted images.
+    #[command(visible_alias = "extract")]
     ExtractAttachments {
         /// Directory containing exported HTML files
         #[arg(value_name = "DIR")]
         dir: PathBuf,
+
+        /// Leave embedded images larger than this many bytes inline instead of extracting them
+        #[arg(long, value_name = "BYTES")]
+        max_attachment_bytes: Option<usize>,
+
+        /// Leave embedded images smaller than this many bytes inline instead of extracting them
+        #[arg(long, value_name = "BYTES")]
+        inline_threshold_bytes: Option<usize>,
+
+        /// Write a JSON manifest of the extracted attachments to this file
+        #[arg(long, value_name = "PATH")]
+        report: Option<PathBuf>,
+
+        /// Give attachments their note's modification time and keep the note's own
+        #[arg(long)]
+        preserve_timestamps: bool,
+
+        /// Reference extracted images under this URL prefix instead of relative to the note
+        #[arg(long, value_name = "URL")]
+        url_base: Option<String>,
+
+        /// Extract only images of this MIME type (e.g. "image/png" or "image/*"); repeatable
+        #[arg(long, value_name = "TYPE")]
+        mime_type: Vec<String>,
+
+        /// Leave images of this MIME type inline; repeatable
+        #[arg(long, value_name = "TYPE")]
+        skip_mime_type: Vec<String>,
     },
+
+    /// Check that everything needed for exporting is set up
+    ///
+    /// Checks the platform, that osascript is available, that the Notes app is
+    /// installed, and that Automation permission for Notes has been granted.
+    /// Exits with a non-zero status if any check fails.
+    Doctor,
+}
+
+/// CLI counterpart of [`OnExisting`].
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OnExistingArg {
+    /// Replace existing files
+    Overwrite,
+    /// Keep existing files and skip the exported copy
+    Skip,
+    /// Abort without writing anything if a file already exists
+    Error,
+}
+
+/// Formats for `export --summary`.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SummaryFormat {
+    /// The exported folders, notes, failures, and attachments as JSON
+    Json,
+}
+
+impl From<OnExistingArg> for OnExisting {
+    fn from(arg: OnExistingArg) -> Self {
+        match arg {
+            OnExistingArg::Overwrite => OnExisting::Overwrite,
+            OnExistingArg::Skip => OnExisting::Skip,
+            OnExistingArg::Error => OnExisting::Error,
+        }
+    }
 }
 
 fn main() -> ExitCode {
     let cli = Cli::parse();
 
+    if let Commands::Doctor = cli.command {
+        return doctor();
+    }
+
     if let Err(error) = run(cli) {
         eprintln!("Error: {error}");
-        return ExitCode::FAILURE;
+        return exit_code(&error);
     }
 
     ExitCode::SUCCESS
 }
 
+/// Maps an error to the exit code documented at the top of this file.
+fn exit_code(error: &ExportError) -> ExitCode {
+    match error {
+        ExportError::OutputNotWritable(..) | ExportError::NotAuthorized => ExitCode::from(2),
+        ExportError::TempFileError(error) | ExportError::LaunchError(error)
+            if error.kind() == io::ErrorKind::PermissionDenied =>
+        {
+            ExitCode::from(2)
+        }
+        ExportError::FolderNotFound(_) => ExitCode::from(3),
+        ExportError::UnsupportedPlatform(_) => ExitCode::from(4),
+        _ => ExitCode::FAILURE,
+    }
+}
+
 fn run(cli: Cli) -> apple_notes_exporter_rs::Result<()> {
     // Try to use vendored script if available (when running from source),
     // otherwise fall back to embedded script
@@ -83,34 +359,355 @@ fn run(cli: Cli) -> apple_notes_exporter_rs::Result<()> {
         Ok(e) => e,
         Err(_) => Exporter::new(),
     };
+    // Only show progress to a person watching the terminal
+    let mut exporter = exporter
+        .with_progress(io::stderr().is_terminal())
+        .with_wait_for_ready(!cli.no_wait_for_ready);
+    if let Some(log_file) = cli.log_file {
+        exporter = exporter.with_log_file(log_file);
+    }
 
     match cli.command {
         Commands::List => exporter.list_folders(),
+        Commands::ListNotes { folder } => {
+            for note in exporter.list_notes(&folder)? {
+                let modified = note
+                    .modified
+                    .map(|modified| {
+                        DateTime::<Local>::from(modified)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                println!("{}\t{}\t{modified}", note.title, note.id);
+            }
+            Ok(())
+        }
         Commands::Export {
             folder,
             output_dir,
             no_extract_attachments,
+            on_existing,
+            max_attachment_bytes,
+            inline_threshold_bytes,
+            filename_template,
+            date_format,
+            index_prefix,
+            prune_empty,
+            collapse_single_child,
+            max_path_len,
+            plain_checklists,
+            no_recursive,
+            include_recently_deleted,
+            pinned_only,
+            by_date,
+            include,
+            exclude,
+            styled,
+            css,
+            clean_html,
+            utf8_bom,
+            normalize_line_endings,
+            text_twin,
+            mark_shared,
+            wait_for_sync,
+            verify_count,
+            preserve_timestamps,
+            folder_metadata,
+            provenance,
+            summary: summary_format,
         } => {
-            if no_extract_attachments {
-                exporter.export_folder(&folder, &output_dir)
+            let stylesheet = match css {
+                Some(path) => Stylesheet::Custom(path),
+                None if styled => Stylesheet::Default,
+                None => Stylesheet::None,
+            };
+            let options = ExportOptions {
+                on_existing: on_existing.into(),
+                layout: match by_date {
+                    Some(pattern) => ExportLayout::ByDate { pattern },
+                    None => ExportLayout::Folders,
+                },
+                recursive: !no_recursive,
+                include_recently_deleted,
+                pinned_only,
+                title_filter: TitleFilter { include, exclude },
+                filename_template,
+                date_format,
+                index_prefix,
+                prune_empty,
+                collapse_single_child,
+                max_path_len,
+                export_checklists_as_inputs: !plain_checklists,
+                stylesheet,
+                clean_html,
+                utf8_bom,
+                normalize_line_endings,
+                text_twin,
+                mark_shared_notes: mark_shared,
+                wait_for_sync,
+                note_count_check: if verify_count {
+                    NoteCountCheck::Error
+                } else {
+                    NoteCountCheck::Off
+                },
+                preserve_timestamps,
+                folder_metadata,
+                write_provenance: provenance,
+                // The images are extracted below unless asked not to
+                large_note_bytes: no_extract_attachments.then_some(DEFAULT_LARGE_NOTE_BYTES),
+                ..Default::default()
+            };
+            // Keep stdout for the summary alone, so it can be parsed
+            let exporter = exporter
+                .with_script_output_to_stderr(matches!(summary_format, Some(SummaryFormat::Json)));
+            let summaries = if folder == "-" {
+                export_batch(&exporter, io::stdin().lock(), &output_dir, &options)?
             } else {
-                let results = exporter.export_folder_with_attachments(&folder, &output_dir)?;
+                let summary =
+                    exporter.export_folder_with_options(&folder, &output_dir, &options)?;
+                if !summary.matched_path.is_empty() {
+                    eprintln!("Exported {}", summary.matched_path.join(" / "));
+                }
+                if summary.pruned_dirs > 0 {
+                    eprintln!("Skipped {} empty directories", summary.pruned_dirs);
+                }
+                report_truncated_notes(&summary.truncated_notes);
+                report_failed_notes(&summary.failed_notes);
+                report_large_notes(&summary.large_notes);
+                report_empty_notes(&summary.empty_notes);
+                vec![summary]
+            };
+
+            let results = if no_extract_attachments {
+                Vec::new()
+            } else {
+                let extract_options = extract_options(
+                    max_attachment_bytes,
+                    inline_threshold_bytes,
+                    preserve_timestamps,
+                );
+                let results =
+                    extract_attachments_from_directory_with_options(&output_dir, &extract_options)?;
+                let total: usize = results.iter().map(|r| r.attachments.len()).sum();
+                if total > 0 {
+                    eprintln!("Extracted {total} attachments from {} files", results.len());
+                }
+                results
+            };
+
+            if let Some(SummaryFormat::Json) = summary_format {
+                print!("{}", export_summary_json(&summaries, &results));
+            }
+            Ok(())
+        }
+        Commands::ExportAll {
+            output_dir,
+            no_extract_attachments,
+            max_concurrency,
+        } => {
+            let exporter = exporter.with_max_concurrency(max_concurrency);
+            for (folder, summary) in exporter.export_all(&output_dir)? {
+                eprintln!("Exported {}", folder.spec());
+                report_failed_notes(&summary.failed_notes);
+            }
+
+            if !no_extract_attachments {
+                let results = extract_attachments_from_directory_with_options(
+                    &output_dir,
+                    &ExtractOptions::default(),
+                )?;
                 let total: usize = results.iter().map(|r| r.attachments.len()).sum();
                 if total > 0 {
                     eprintln!("Extracted {total} attachments from {} files", results.len());
                 }
-                Ok(())
             }
+            Ok(())
         }
-        Commands::ExtractAttachments { dir } => {
-            let results = extract_attachments_from_directory(&dir)?;
+        Commands::Retry {
+            folder,
+            output_dir,
+            notes,
+            no_extract_attachments,
+        } => {
+            let still_failed = exporter.retry_failed(&folder, &notes, &output_dir)?;
+            eprintln!(
+                "Exported {} of {} notes",
+                notes.len() - still_failed.len(),
+                notes.len()
+            );
+            report_failed_notes(&still_failed);
+
+            if !no_extract_attachments {
+                extract_attachments_from_directory_with_options(
+                    &output_dir,
+                    &ExtractOptions::default(),
+                )?;
+            }
+            Ok(())
+        }
+        Commands::Doctor => unreachable!("handled in main"),
+        Commands::ExtractAttachments {
+            dir,
+            max_attachment_bytes,
+            inline_threshold_bytes,
+            report,
+            preserve_timestamps,
+            url_base,
+            mime_type,
+            skip_mime_type,
+        } => {
+            let extract_options = ExtractOptions {
+                url_base,
+                allowed_mime_types: mime_type,
+                blocked_mime_types: skip_mime_type,
+                ..extract_options(
+                    max_attachment_bytes,
+                    inline_threshold_bytes,
+                    preserve_timestamps,
+                )
+            };
+            let results = extract_attachments_from_directory_with_options(&dir, &extract_options)?;
+            if let Some(report) = report {
+                write_extraction_report(&results, &report)?;
+            }
             let total: usize = results.iter().map(|r| r.attachments.len()).sum();
+            let bytes: u64 = results
+                .iter()
+                .flat_map(|r| &r.attachments)
+                .map(|a| a.size)
+                .sum();
             let modified: usize = results.iter().filter(|r| r.html_modified).count();
             eprintln!(
-                "Extracted {total} attachments from {modified} files ({} files scanned)",
+                "Extracted {total} attachments ({bytes} bytes) from {modified} files ({} files scanned)",
                 results.len()
             );
             Ok(())
         }
     }
 }
+
+/// Exports each folder spec read from `input`, one per line, printing a
+/// summary line per folder.
+///
+/// Blank lines and lines starting with `#` are skipped. A folder that fails
+/// to export doesn't stop the rest; the first error is returned at the end.
+fn export_batch(
+    exporter: &Exporter,
+    input: impl BufRead,
+    output_dir: &Path,
+    options: &ExportOptions,
+) -> apple_notes_exporter_rs::Result<Vec<ExportSummary>> {
+    let mut summaries = Vec::new();
+    let mut first_error = None;
+
+    for line in input.lines() {
+        let line = line?;
+        let spec = line.trim();
+        if spec.is_empty() || spec.starts_with('#') {
+            continue;
+        }
+
+        match exporter.export_folder_with_options(spec, output_dir, options) {
+            Ok(summary) => {
+                eprintln!("{spec}: exported {} notes", summary.actual_notes);
+                report_truncated_notes(&summary.truncated_notes);
+                report_failed_notes(&summary.failed_notes);
+                report_large_notes(&summary.large_notes);
+                report_empty_notes(&summary.empty_notes);
+                summaries.push(summary);
+            }
+            Err(error) => {
+                eprintln!("{spec}: {error}");
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+
+    first_error.map_or(Ok(summaries), Err)
+}
+
+/// Runs the setup checks, printing one line per check.
+fn doctor() -> ExitCode {
+    println!("apple-notes-exporter {}", env!("CARGO_PKG_VERSION"));
+
+    let checks = Exporter::new().diagnose();
+    for check in &checks {
+        let status = if check.passed { "PASS" } else { "FAIL" };
+        println!("[{status}] {}: {}", check.name, check.detail);
+    }
+
+    if checks.iter().all(|check| check.passed) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Builds the attachment extraction options shared by `export` and `extract-attachments`.
+fn extract_options(
+    max_attachment_bytes: Option<usize>,
+    inline_threshold_bytes: Option<usize>,
+    preserve_timestamps: bool,
+) -> ExtractOptions {
+    ExtractOptions {
+        max_attachment_bytes,
+        inline_threshold_bytes,
+        preserve_timestamps,
+        ..Default::default()
+    }
+}
+
+/// Prints the notes an export renamed to fit the path length limit.
+fn report_truncated_notes(truncated_notes: &[TruncatedNote]) {
+    if truncated_notes.is_empty() {
+        return;
+    }
+
+    eprintln!("Shortened {} long filenames:", truncated_notes.len());
+    for note in truncated_notes {
+        eprintln!("  {} -> {}", note.original.display(), note.path.display());
+    }
+}
+
+/// Prints a hint to extract the images of notes that are large because
+/// their images are inline.
+fn report_large_notes(large_notes: &[(PathBuf, u64)]) {
+    if large_notes.is_empty() {
+        return;
+    }
+
+    eprintln!(
+        "Hint: {} notes are over {DEFAULT_LARGE_NOTE_BYTES} bytes because their images are inline; \
+         export without --no-extract-attachments to extract them",
+        large_notes.len()
+    );
+}
+
+/// Prints the notes that were exported without any content.
+fn report_empty_notes(empty_notes: &[PathBuf]) {
+    if empty_notes.is_empty() {
+        return;
+    }
+
+    eprintln!(
+        "{} notes have no content; if Notes is still syncing them, export again with \
+         --wait-for-sync:",
+        empty_notes.len()
+    );
+    for path in empty_notes {
+        eprintln!("  {}", path.display());
+    }
+}
+
+/// Prints the notes an export skipped because they could not be exported.
+fn report_failed_notes(failed_notes: &[FailedNote]) {
+    if failed_notes.is_empty() {
+        return;
+    }
+
+    eprintln!("Failed to export {} notes:", failed_notes.len());
+    for failure in failed_notes {
+        eprintln!("  {}: {}", failure.note, failure.reason);
+    }
+}
@@ -0,0 +1,562 @@
+//! Converting exported notes to Markdown with YAML frontmatter.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use scraper::{ElementRef, Html, Node, Selector};
+
+use crate::Result;
+use crate::encoding::normalize_line_endings;
+use crate::export_tree::{note_title_from_stem, read_export_tree};
+
+/// A key that can be written to the YAML frontmatter of a Markdown note.
+///
+/// Keys are written in the order they are declared here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FrontmatterKey {
+    /// `title`: the note's title.
+    Title,
+    /// `created`: when the note was created.
+    Created,
+    /// `updated`: when the note was last modified.
+    Updated,
+    /// `tags`: the note's `#hashtags`, without the `#`.
+    Tags,
+}
+
+impl FrontmatterKey {
+    /// Every frontmatter key.
+    pub const ALL: [Self; 4] = [Self::Title, Self::Created, Self::Updated, Self::Tags];
+}
+
+/// Options for [`Exporter::export_folder_as_markdown`](crate::Exporter::export_folder_as_markdown).
+#[derive(Debug, Clone)]
+pub struct MarkdownOptions {
+    /// The keys to include in each note's frontmatter. All keys by default.
+    ///
+    /// If this is empty, no frontmatter is written. `created` and `updated`
+    /// need the script's `note-metadata` command, which is only run when one
+    /// of them is enabled.
+    pub frontmatter: BTreeSet<FrontmatterKey>,
+
+    /// Write the Markdown files with LF line endings even where a note's
+    /// HTML had CRLF or CR ones. Off by default.
+    pub normalize_line_endings: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            frontmatter: FrontmatterKey::ALL.into_iter().collect(),
+            normalize_line_endings: false,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    pub(crate) fn needs_dates(&self) -> bool {
+        self.frontmatter.contains(&FrontmatterKey::Created)
+            || self.frontmatter.contains(&FrontmatterKey::Updated)
+    }
+}
+
+/// The creation and modification dates of a note, and whether it is shared.
+pub(crate) struct NoteDates {
+    created: String,
+    updated: String,
+    shared: bool,
+    participants: Vec<String>,
+}
+
+impl NoteDates {
+    /// Returns the note's creation date as printed by the script.
+    pub(crate) fn created(&self) -> &str {
+        &self.created
+    }
+
+    /// Returns the note's modification date as printed by the script.
+    pub(crate) fn updated(&self) -> &str {
+        &self.updated
+    }
+
+    /// Returns whether the note is shared with other people.
+    pub(crate) fn shared(&self) -> bool {
+        self.shared
+    }
+
+    /// Returns the names of the people the note is shared with, if known.
+    pub(crate) fn participants(&self) -> &[String] {
+        &self.participants
+    }
+}
+
+/// Parses the output of the `note-metadata` script command, keyed by note id.
+pub(crate) fn parse_note_dates(output: &str) -> HashMap<String, NoteDates> {
+    output
+        .lines()
+        .filter_map(parse_note_metadata_line)
+        .collect()
+}
+
+/// Parses one `<id>\t<created>\t<updated>[\t<shared>[\t<participant>...]]`
+/// line of `note-metadata` output.
+pub(crate) fn parse_note_metadata_line(line: &str) -> Option<(String, NoteDates)> {
+    let mut fields = line.split('\t');
+    let id = fields.next()?;
+    let created = fields.next()?;
+    let updated = fields.next()?;
+    let shared = fields.next() == Some("true");
+    let participants = fields
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some((
+        id.to_string(),
+        NoteDates {
+            created: created.to_string(),
+            updated: updated.to_string(),
+            shared,
+            participants,
+        },
+    ))
+}
+
+/// Converts every note in `export_dir` (with attachments already extracted)
+/// to Markdown in `output_dir`, keeping the directory structure and copying
+/// the attachment folders alongside.
+pub(crate) fn write_markdown_tree(
+    export_dir: &Path,
+    output_dir: &Path,
+    options: &MarkdownOptions,
+    dates: &HashMap<String, NoteDates>,
+) -> Result<()> {
+    fs::create_dir_all(output_dir)?;
+
+    for entry in fs::read_dir(export_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = output_dir.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with("-attachments")
+            {
+                copy_dir(&path, &target)?;
+            } else {
+                write_markdown_tree(&path, &target, options, dates)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("note");
+            let html = fs::read_to_string(&path)?;
+            let mut markdown = note_to_markdown(&html, stem, options, dates);
+            if options.normalize_line_endings {
+                markdown = normalize_line_endings(&markdown).into_owned();
+            }
+            fs::write(target.with_extension("md"), markdown)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the notes in `export_dir` (with attachments already extracted)
+/// as one Markdown document titled `title`, with a `##` section per note.
+///
+/// The notes must have index prefixes, which give their order and are left
+/// out of the section titles. Each note's headings are moved down two levels
+/// to fit under its section, and a first line repeating the title is
+/// dropped. Subfolders are not included.
+pub(crate) fn render_single_markdown(export_dir: &Path, title: &str) -> Result<String> {
+    let folder = read_export_tree(export_dir, title)?;
+    let body_selector = Selector::parse("body").unwrap();
+
+    let mut markdown = format!("# {}\n", escape_text(&folder.name));
+    for note in &folder.notes {
+        let title = note
+            .title
+            .split_once(" - ")
+            .map_or(note.title.as_str(), |(_, title)| title);
+        let heading = format!("## {}", escape_text(title));
+
+        let html = fs::read_to_string(&note.path)?;
+        let document = Html::parse_document(&html);
+        let body = document
+            .select(&body_selector)
+            .next()
+            .unwrap_or_else(|| document.root_element());
+
+        let mut blocks: Vec<String> = render_blocks(body)
+            .into_iter()
+            .map(|block| demote_heading(&block))
+            .collect();
+        if blocks
+            .first()
+            .is_some_and(|first| first.trim_start_matches('#').trim() == escape_text(title))
+        {
+            blocks.remove(0);
+        }
+
+        markdown.push('\n');
+        markdown.push_str(&heading);
+        markdown.push('\n');
+        for block in blocks {
+            markdown.push('\n');
+            markdown.push_str(&block);
+            markdown.push('\n');
+        }
+    }
+
+    Ok(markdown)
+}
+
+/// Moves a Markdown heading down two levels, to at most `######`.
+fn demote_heading(block: &str) -> String {
+    let level = block.chars().take_while(|&c| c == '#').count();
+    match block[level..].strip_prefix(' ') {
+        Some(text) if (1..=6).contains(&level) => {
+            format!("{} {text}", "#".repeat((level + 2).min(6)))
+        }
+        _ => block.to_string(),
+    }
+}
+
+/// Copies the attachment folders under `export_dir` to `target`, keeping
+/// their paths within the export.
+pub(crate) fn copy_attachment_dirs(export_dir: &Path, target: &Path) -> Result<()> {
+    for entry in fs::read_dir(export_dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let target = target.join(entry.file_name());
+        if entry
+            .file_name()
+            .to_string_lossy()
+            .ends_with("-attachments")
+        {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            copy_attachment_dirs(&entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies the directory `source` and everything in it to `target`.
+pub(crate) fn copy_dir(source: &Path, target: &Path) -> Result<()> {
+    fs::create_dir_all(target)?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), target.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts a note exported as `<stem>.html` to Markdown with frontmatter.
+fn note_to_markdown(
+    html: &str,
+    stem: &str,
+    options: &MarkdownOptions,
+    dates: &HashMap<String, NoteDates>,
+) -> String {
+    let document = Html::parse_document(html);
+    let body_selector = Selector::parse("body").unwrap();
+    let body = document
+        .select(&body_selector)
+        .next()
+        .unwrap_or_else(|| document.root_element());
+
+    let mut markdown = String::new();
+
+    if !options.frontmatter.is_empty() {
+        let note_dates = stem.rsplit_once(" -- ").and_then(|(_, id)| dates.get(id));
+
+        markdown.push_str("---\n");
+        for key in &options.frontmatter {
+            match key {
+                FrontmatterKey::Title => {
+                    let title = note_title_from_stem(stem);
+                    let _ = writeln!(markdown, "title: {}", yaml_string(title));
+                }
+                FrontmatterKey::Created => {
+                    if let Some(note_dates) = note_dates {
+                        let _ = writeln!(markdown, "created: {}", note_dates.created);
+                    }
+                }
+                FrontmatterKey::Updated => {
+                    if let Some(note_dates) = note_dates {
+                        let _ = writeln!(markdown, "updated: {}", note_dates.updated);
+                    }
+                }
+                FrontmatterKey::Tags => {
+                    let tags = hashtags(&body.text().collect::<Vec<_>>().join(" "));
+                    let tags: Vec<_> = tags.iter().map(|tag| yaml_string(tag)).collect();
+                    let _ = writeln!(markdown, "tags: [{}]", tags.join(", "));
+                }
+            }
+        }
+        markdown.push_str("---\n\n");
+    }
+
+    markdown.push_str(&render_blocks(body).join("\n\n"));
+    markdown.push('\n');
+    markdown
+}
+
+/// Quotes `value` as a double-quoted YAML string.
+fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Returns the distinct `#hashtags` in `text`, in order of appearance.
+fn hashtags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut previous = ' ';
+
+    for (index, c) in text.char_indices() {
+        if c == '#' && !previous.is_alphanumeric() {
+            let tag: String = text[index + 1..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+                .collect();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        previous = c;
+    }
+
+    tags
+}
+
+/// Markdown blocks collected from a container element, with the inline
+/// content that has not yet been closed off as a paragraph.
+#[derive(Default)]
+struct Blocks {
+    blocks: Vec<String>,
+    inline: String,
+}
+
+impl Blocks {
+    fn flush(&mut self) {
+        let paragraph = self.inline.trim();
+        if !paragraph.is_empty() {
+            self.blocks.push(paragraph.to_string());
+        }
+        self.inline.clear();
+    }
+
+    fn push_block(&mut self, block: String) {
+        self.flush();
+        if !block.trim().is_empty() {
+            self.blocks.push(block);
+        }
+    }
+}
+
+/// Renders the children of `element` as Markdown blocks.
+fn render_blocks(element: ElementRef) -> Vec<String> {
+    let mut blocks = Blocks::default();
+    render_block_children(element, &mut blocks);
+    blocks.flush();
+    blocks.blocks
+}
+
+fn render_block_children(element: ElementRef, blocks: &mut Blocks) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => blocks.inline.push_str(&escape_text(text)),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    render_block_element(child, blocks);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn render_block_element(element: ElementRef, blocks: &mut Blocks) {
+    match element.value().name() {
+        "head" | "style" | "script" | "title" => {}
+        name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+            let level = usize::from(name.as_bytes()[1] - b'0');
+            let text = render_inline(element);
+            blocks.push_block(format!("{} {}", "#".repeat(level), text.trim()));
+        }
+        "p" | "div" | "section" | "article" | "header" | "footer" | "main" | "body" => {
+            blocks.flush();
+            render_block_children(element, blocks);
+            blocks.flush();
+        }
+        "ul" => blocks.push_block(render_list(element, false)),
+        "ol" => blocks.push_block(render_list(element, true)),
+        "blockquote" => {
+            let quoted = render_blocks(element).join("\n\n");
+            blocks.push_block(prefix_lines(&quoted, "> ", "> "));
+        }
+        "pre" => {
+            let code: String = element.text().collect();
+            blocks.push_block(format!("```\n{}\n```", code.trim_end_matches('\n')));
+        }
+        "hr" => blocks.push_block("---".to_string()),
+        "table" => blocks.push_block(render_table(element)),
+        _ => blocks.inline.push_str(&render_inline_element(element)),
+    }
+}
+
+fn render_list(element: ElementRef, ordered: bool) -> String {
+    let items = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|child| child.value().name() == "li");
+
+    let mut lines = Vec::new();
+    for (index, item) in items.enumerate() {
+        let marker = if ordered {
+            format!("{}. ", index + 1)
+        } else {
+            "- ".to_string()
+        };
+        let indent = " ".repeat(marker.len());
+        let content = render_blocks(item).join("\n");
+        lines.push(prefix_lines(&content, &marker, &indent));
+    }
+
+    lines.join("\n")
+}
+
+fn render_table(element: ElementRef) -> String {
+    let row_selector = Selector::parse("tr").unwrap();
+
+    let mut lines = Vec::new();
+    for (index, row) in element.select(&row_selector).enumerate() {
+        let cells: Vec<String> = row
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+            .map(|cell| {
+                render_inline(cell)
+                    .trim()
+                    .replace('|', "\\|")
+                    .replace('\n', " ")
+            })
+            .collect();
+
+        lines.push(format!("| {} |", cells.join(" | ")));
+        if index == 0 {
+            lines.push(format!("|{}", " --- |".repeat(cells.len())));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Prefixes the first line of `text` with `first` and the rest with `rest`.
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let prefix = if index == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders the children of `element` as inline Markdown.
+fn render_inline(element: ElementRef) -> String {
+    let mut out = String::new();
+
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(&escape_text(text)),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    out.push_str(&render_inline_element(child));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+fn render_inline_element(element: ElementRef) -> String {
+    match element.value().name() {
+        "head" | "style" | "script" | "title" => String::new(),
+        "br" => "  \n".to_string(),
+        "b" | "strong" => wrap_inline(element, "**"),
+        "i" | "em" => wrap_inline(element, "*"),
+        "s" | "strike" | "del" => wrap_inline(element, "~~"),
+        "code" | "tt" => format!("`{}`", element.text().collect::<String>()),
+        "a" => {
+            let text = render_inline(element);
+            match element.value().attr("href") {
+                Some(href) => format!("[{}]({})", text.trim(), href.replace(' ', "%20")),
+                None => text,
+            }
+        }
+        "img" => {
+            let alt = element.value().attr("alt").unwrap_or("");
+            let src = element.value().attr("src").unwrap_or("");
+            format!("![{alt}]({})", src.replace(' ', "%20"))
+        }
+        _ => render_inline(element),
+    }
+}
+
+/// Wraps the inline content of `element` in `marker`, keeping surrounding
+/// whitespace outside the markers.
+fn wrap_inline(element: ElementRef, marker: &str) -> String {
+    let inner = render_inline(element);
+    let trimmed = inner.trim();
+    if trimmed.is_empty() {
+        return inner;
+    }
+
+    let leading = &inner[..inner.len() - inner.trim_start().len()];
+    let trailing = &inner[inner.trim_end().len()..];
+    format!("{leading}{marker}{trimmed}{marker}{trailing}")
+}
+
+/// Collapses whitespace in HTML text and escapes Markdown syntax characters.
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                out.push(' ');
+            }
+            in_whitespace = true;
+            continue;
+        }
+
+        in_whitespace = false;
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+
+    out
+}
@@ -0,0 +1,145 @@
+//! Choosing file names for extracted attachments.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// What is known about an attachment when choosing its file name.
+#[derive(Debug, Clone, Copy)]
+pub struct AttachmentContext<'a> {
+    /// The file stem of the note's HTML file (e.g. `"My Note -- abc123"`).
+    pub note_stem: &'a str,
+    /// The attachment's position in the note, starting at 1.
+    pub index: usize,
+    /// The MIME type of the data being written (e.g. `"image/png"`).
+    pub mime_type: &'a str,
+    /// The extension for the MIME type, without the dot (e.g. `"png"`).
+    pub extension: &'a str,
+    /// The size of the data being written in bytes.
+    pub byte_len: usize,
+}
+
+/// A function that returns the file name for an attachment, including its extension.
+pub type AttachmentNamer = Arc<dyn Fn(&AttachmentContext) -> String + Send + Sync>;
+
+/// How extracted attachments are named within a note's attachment folder.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use apple_notes_exporter_rs::{
+///     extract_attachments_from_directory_with_options, AttachmentNaming, ExtractOptions,
+/// };
+///
+/// let options = ExtractOptions {
+///     naming: AttachmentNaming::Custom(Arc::new(|attachment| {
+///         format!("image_{}.{}", attachment.index, attachment.extension)
+///     })),
+///     ..Default::default()
+/// };
+///
+/// extract_attachments_from_directory_with_options("./exports", &options)
+///     .expect("Failed to extract attachments");
+/// ```
+#[derive(Clone, Default)]
+pub enum AttachmentNaming {
+    /// Numbered in order of appearance: `attachment-001.png`, `attachment-002.jpg`, ...
+    #[default]
+    Counter,
+    /// Named after a hash of the content, e.g. `3f2a9c01d4b7e865.png`, so
+    /// identical images share a name across exports.
+    ContentHash,
+    /// Named after the image's `alt` text, falling back to [`Counter`](Self::Counter)
+    /// names for images without one.
+    OriginalName,
+    /// Named by a caller-provided function.
+    ///
+    /// Path separators in the returned name are replaced with `_`.
+    Custom(AttachmentNamer),
+}
+
+impl fmt::Debug for AttachmentNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Counter => f.write_str("Counter"),
+            Self::ContentHash => f.write_str("ContentHash"),
+            Self::OriginalName => f.write_str("OriginalName"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl AttachmentNaming {
+    /// Returns the file name for an attachment, made unique among `used`.
+    ///
+    /// `number` is used for [`Counter`](Self::Counter) names; it is the
+    /// attachment's index unless a counter is shared between notes.
+    pub(crate) fn file_name(
+        &self,
+        context: &AttachmentContext,
+        number: usize,
+        original_name: Option<&str>,
+        data: &[u8],
+        used: &mut HashSet<String>,
+    ) -> String {
+        let counter_name = || format!("attachment-{number:03}.{}", context.extension);
+
+        let name = match self {
+            Self::Counter => counter_name(),
+            Self::ContentHash => {
+                // Identical content gets the same name, so there is nothing to disambiguate
+                let name = format!("{:016x}.{}", fnv1a(data), context.extension);
+                used.insert(name.clone());
+                return name;
+            }
+            Self::OriginalName => original_name
+                .map(sanitize_file_stem)
+                .filter(|stem| !stem.is_empty())
+                .map_or_else(counter_name, |stem| format!("{stem}.{}", context.extension)),
+            Self::Custom(namer) => namer(context).replace(['/', '\\'], "_"),
+        };
+
+        unique_name(name, used)
+    }
+}
+
+/// Appends `-2`, `-3`, ... before the extension until `name` is not in `used`.
+pub(crate) fn unique_name(name: String, used: &mut HashSet<String>) -> String {
+    if used.insert(name.clone()) {
+        return name;
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) => (stem, format!(".{extension}")),
+        None => (name.as_str(), String::new()),
+    };
+
+    (2..)
+        .map(|n| format!("{stem}-{n}{extension}"))
+        .find(|candidate| used.insert(candidate.clone()))
+        .expect("an unused name exists")
+}
+
+/// Keeps the characters of `name` that are safe in a file name.
+fn sanitize_file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// The 64-bit FNV-1a hash, which is stable across platforms and releases.
+pub(crate) fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,167 @@
+//! Exporting individual notes by id, from any folder.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::{
+    ExportError, ExportSummary, Exporter, HtmlTransform, OnExisting, Result, ScriptLine,
+    check_writable, checklists, count_html_files, move_staged_output, transform_html_files,
+};
+
+impl Exporter {
+    /// Exports the notes with the given ids, wherever they are, keeping the
+    /// path of the folder each one is in, e.g. for a note picker that lets
+    /// users select notes across folders.
+    ///
+    /// Ids are the ones used in exported filenames (`<title> -- <id>.html`),
+    /// as returned by [`list_notes`](Self::list_notes). Each note is written
+    /// to `<account>/<folder>/.../<title> -- <id>.html` under `output_dir`,
+    /// the layout of [`export_all`](Self::export_all), replacing any existing
+    /// file. Checklists are converted and the exporter's HTML transform
+    /// applied as with [`ExportOptions::default()`](crate::ExportOptions::default).
+    ///
+    /// Ids that match no note, and notes the script fails to export, are
+    /// listed in [`ExportSummary::failed_notes`] without failing the rest.
+    ///
+    /// Needs a script with the `export-notes` command, which the embedded
+    /// script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let summary = exporter
+    ///     .export_notes_by_ids(&["x-coredata-p102", "x-coredata-p104", "x-coredata-p999"], dir.path())
+    ///     .unwrap();
+    /// assert_eq!(summary.actual_notes, 2);
+    /// assert!(dir.path().join("iCloud/Recipes/Desserts/Brownies -- x-coredata-p102.html").is_file());
+    /// assert!(dir.path().join("On My Mac/Journal/Monday -- x-coredata-p104.html").is_file());
+    /// assert_eq!(summary.failed_notes[0].note, "x-coredata-p999");
+    /// # }
+    /// ```
+    pub fn export_notes_by_ids<P: AsRef<Path>>(
+        &self,
+        ids: &[&str],
+        output_dir: P,
+    ) -> Result<ExportSummary> {
+        self.export_notes_by_ids_impl(ids, output_dir.as_ref(), false)
+    }
+
+    /// Exports the notes with the given ids like
+    /// [`export_notes_by_ids`](Self::export_notes_by_ids), but writes them
+    /// all directly into `output_dir` instead of their folders' paths.
+    ///
+    /// Needs a script with the `export-notes` command, which the embedded
+    /// script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// exporter
+    ///     .export_notes_by_ids_flat(&["x-coredata-p102", "x-coredata-p104"], dir.path())
+    ///     .unwrap();
+    /// assert!(dir.path().join("Brownies -- x-coredata-p102.html").is_file());
+    /// assert!(dir.path().join("Monday -- x-coredata-p104.html").is_file());
+    /// # }
+    /// ```
+    pub fn export_notes_by_ids_flat<P: AsRef<Path>>(
+        &self,
+        ids: &[&str],
+        output_dir: P,
+    ) -> Result<ExportSummary> {
+        self.export_notes_by_ids_impl(ids, output_dir.as_ref(), true)
+    }
+
+    fn export_notes_by_ids_impl(
+        &self,
+        ids: &[&str],
+        output_dir: &Path,
+        flatten: bool,
+    ) -> Result<ExportSummary> {
+        let mut summary = ExportSummary::default();
+        let mut seen = HashSet::new();
+        let ids: Vec<&str> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+        if ids.is_empty() {
+            return Ok(summary);
+        }
+
+        fs::create_dir_all(output_dir)
+            .map_err(|error| ExportError::OutputNotWritable(output_dir.to_path_buf(), error))?;
+        let output_dir = output_dir.canonicalize()?;
+        check_writable(&output_dir)?;
+
+        let staging = tempfile::Builder::new()
+            .prefix(".apple-notes-export-")
+            .tempdir_in(&output_dir)?;
+        let staging_str = staging
+            .path()
+            .to_str()
+            .ok_or(ExportError::InvalidUtf8Path)?;
+
+        let mut args = vec!["export-notes", staging_str];
+        args.extend(&ids);
+        let exit_code = self.run_script(&args, None, |line| {
+            if let ScriptLine::Failed(failure) = line {
+                summary.failed_notes.push(failure);
+            }
+        })?;
+        summary.exit_code = Some(exit_code);
+
+        if flatten {
+            flatten_directory(staging.path(), staging.path())?;
+        }
+        summary.actual_notes = count_html_files(staging.path())?;
+
+        self.inline_object_attachments_in_directory(staging.path())?;
+        checklists::convert_directory(staging.path())?;
+        if let Some(HtmlTransform(transform)) = &self.html_transform {
+            transform_html_files(staging.path(), transform.as_ref())?;
+        }
+
+        let mut created_dirs = Vec::new();
+        move_staged_output(
+            staging.path(),
+            &output_dir,
+            OnExisting::Overwrite,
+            &mut created_dirs,
+        )?;
+        summary.created_dirs = created_dirs
+            .into_iter()
+            .filter_map(|dir| dir.strip_prefix(&output_dir).ok().map(Path::to_path_buf))
+            .collect();
+
+        Ok(summary)
+    }
+}
+
+/// Moves every note under `dir` into `root`, removing the emptied
+/// directories.
+fn flatten_directory(dir: &Path, root: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            flatten_directory(&path, root)?;
+            fs::remove_dir(&path)?;
+        } else if dir != root {
+            fs::rename(&path, root.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
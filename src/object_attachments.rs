@@ -0,0 +1,124 @@
+//! Inlining attachments that a note references by object URL.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use scraper::{Html, Node, Selector};
+
+use crate::{Exporter, Result, set_attribute, split_data_url};
+
+/// The scheme of the URLs Notes uses for its own objects, such as
+/// `x-coredata://<store>/ICAttachment/p12`.
+const OBJECT_URL_SCHEME: &str = "x-coredata:";
+
+/// Elements that embed an attachment by object URL.
+const REFERENCES: &str = r#"img[src^="x-coredata:" i], object[data^="x-coredata:" i]"#;
+
+impl Exporter {
+    /// Inlines the attachments referenced by object URL in every note under
+    /// `dir`; see [`inline_object_attachments`](Self::inline_object_attachments).
+    pub(crate) fn inline_object_attachments_in_directory(&self, dir: &Path) -> Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if entry.file_type()?.is_dir() {
+                self.inline_object_attachments_in_directory(&path)?;
+            } else if path.extension().is_some_and(|ext| ext == "html") {
+                let html = fs::read_to_string(&path)?;
+                if let Some(inlined) = self.inline_object_attachments(&html) {
+                    fs::write(&path, inlined)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the `<img>` and `<object>` elements that reference an
+    /// attachment by `x-coredata:` URL with `<img>` elements holding its data
+    /// as a data URL, read with the script's `attachment` command, so they
+    /// are extracted like any other image.
+    ///
+    /// An attachment the script can't read keeps its reference, with a
+    /// warning logged; see [`Exporter::with_log_file`]. Returns `None` if the note references no
+    /// attachments.
+    pub(crate) fn inline_object_attachments(&self, html: &str) -> Option<String> {
+        if !html.contains(OBJECT_URL_SCHEME) {
+            return None;
+        }
+
+        let selector = Selector::parse(REFERENCES).unwrap();
+        let mut document = Html::parse_document(html);
+        let references: Vec<_> = document
+            .select(&selector)
+            .filter_map(|element| {
+                let url = match element.value().name() {
+                    "img" => element.value().attr("src"),
+                    _ => element.value().attr("data"),
+                }?;
+                Some((element.id(), url.trim().to_string()))
+            })
+            .collect();
+        if references.is_empty() {
+            return None;
+        }
+
+        let mut data_urls = HashMap::new();
+        for (id, url) in references {
+            let data_url = data_urls
+                .entry(url.clone())
+                .or_insert_with(|| self.read_attachment(&url));
+            let (Some(data_url), Some(mut node)) = (data_url.as_deref(), document.tree.get_mut(id))
+            else {
+                continue;
+            };
+
+            match node.value() {
+                Node::Element(element) if element.name() == "img" => {
+                    set_attribute(element, "src", data_url);
+                }
+                _ => {
+                    let Some(image) = image_element(data_url) else {
+                        continue;
+                    };
+                    // Replaced by an image, without the object's fallback content
+                    *node.value() = image;
+                    while let Some(mut child) = node.first_child() {
+                        child.detach();
+                    }
+                }
+            }
+        }
+
+        Some(document.html())
+    }
+
+    /// Reads the attachment at `url` as a data URL, or returns `None` with a
+    /// logged warning if the script can't.
+    fn read_attachment(&self, url: &str) -> Option<String> {
+        match self.run_script_capture(&["attachment", url]) {
+            Ok(output) if split_data_url(output.trim()).is_some() => {
+                Some(output.trim().to_string())
+            }
+            Ok(_) => {
+                self.log_warning(&format!("could not read attachment {url}: not a data URL"));
+                None
+            }
+            Err(error) => {
+                self.log_warning(&format!("could not read attachment {url}: {error}"));
+                None
+            }
+        }
+    }
+}
+
+/// Returns an `<img>` element showing `data_url`.
+fn image_element(data_url: &str) -> Option<Node> {
+    let fragment = Html::parse_fragment(r#"<img src="">"#);
+    let selector = Selector::parse("img").unwrap();
+    let mut image = fragment.select(&selector).next()?.value().clone();
+    set_attribute(&mut image, "src", data_url);
+    Some(Node::Element(image))
+}
@@ -0,0 +1,119 @@
+//! Detecting notes that Notes has not finished syncing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use scraper::{Html, Selector};
+
+use crate::{CancellationToken, Exporter, FailedNote, Result, html_to_text};
+
+/// How many more times to read a placeholder note with
+/// [`ExportOptions::wait_for_sync`](crate::ExportOptions::wait_for_sync).
+const SYNC_ATTEMPTS: usize = 3;
+
+/// How long to wait before reading a placeholder note again.
+const SYNC_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Elements that give a note content even without any text.
+const CONTENT_ELEMENTS: &str = "img, object, embed, iframe, video, audio, input";
+
+/// Returns whether `html` has no text and nothing embedded, which is what
+/// Notes exports for a note whose body iCloud has not synced yet.
+pub(crate) fn is_placeholder(html: &str) -> bool {
+    if !html_to_text(html).trim().is_empty() {
+        return false;
+    }
+
+    let selector = Selector::parse(CONTENT_ELEMENTS).expect("valid selector");
+    Html::parse_document(html)
+        .select(&selector)
+        .next()
+        .is_none()
+}
+
+impl Exporter {
+    /// Reads each placeholder note under `dir` again from `folder` (a script
+    /// argument) up to [`SYNC_ATTEMPTS`] times. Those that are still empty
+    /// are removed and returned as failed notes, so an unsynced note isn't
+    /// mistaken for a successful export.
+    pub(crate) fn remove_placeholders(
+        &self,
+        folder: &str,
+        dir: &Path,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Vec<FailedNote>> {
+        let mut placeholders = Vec::new();
+        find_placeholders(dir, Path::new(""), &mut placeholders)?;
+
+        let mut failed = Vec::new();
+        for path in placeholders {
+            let path = dir.join(path);
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let note = stem.rsplit_once(" -- ").map_or(stem, |(_, id)| id);
+
+            if self.wait_for_sync(folder, note, &path, cancellation)? {
+                continue;
+            }
+
+            fs::remove_file(&path)?;
+            failed.push(FailedNote {
+                note: note.to_string(),
+                reason: "note has no content; Notes may still be syncing it".to_string(),
+            });
+        }
+
+        Ok(failed)
+    }
+
+    /// Reads `note` again until it has content, writing it to `path`.
+    /// Returns whether it synced.
+    fn wait_for_sync(
+        &self,
+        folder: &str,
+        note: &str,
+        path: &Path,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<bool> {
+        for _ in 0..SYNC_ATTEMPTS {
+            thread::sleep(SYNC_RETRY_DELAY);
+            if cancellation.is_some_and(CancellationToken::is_cancelled) {
+                break;
+            }
+
+            // A failed read is retried like an empty one
+            if let Ok(html) = self.run_script_capture(&["note-html", folder, note])
+                && !is_placeholder(&html)
+            {
+                fs::write(path, html)?;
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// Collects the placeholder notes under `dir`, as paths under `relative`.
+pub(crate) fn find_placeholders(
+    dir: &Path,
+    relative: &Path,
+    placeholders: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            find_placeholders(&path, &relative, placeholders)?;
+        } else if path.extension().is_some_and(|ext| ext == "html")
+            && is_placeholder(&fs::read_to_string(&path)?)
+        {
+            placeholders.push(relative);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Reporting the progress of an export.
+
+use std::path::Path;
+
+use crate::{ExportOptions, ExportSummary, Exporter, Result, parse_folder_spec};
+
+/// How far an export has got, as reported to [`ProgressReporter::on_note`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportProgress {
+    /// The number of notes exported so far.
+    pub done: usize,
+    /// The number of notes being exported.
+    pub total: usize,
+}
+
+/// Receives the progress of an export; see
+/// [`Exporter::export_folder_with_progress`].
+///
+/// Implement it for reporters that keep state between events, such as one
+/// that limits how often it prints or estimates the time left.
+pub trait ProgressReporter {
+    /// Called each time the script reports that another note was exported.
+    fn on_note(&mut self, progress: &ExportProgress);
+
+    /// Called once the export has finished successfully, with its summary.
+    /// Does nothing by default.
+    fn on_done(&mut self, summary: &ExportSummary) {
+        let _ = summary;
+    }
+}
+
+/// A [`ProgressReporter`] that ignores every event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn on_note(&mut self, _progress: &ExportProgress) {}
+}
+
+/// A [`ProgressReporter`] that prints an `Exported N/M notes` line to stderr,
+/// updated in place, as [`Exporter::with_progress`] does.
+#[derive(Debug, Default)]
+pub struct StderrProgress {
+    line_open: bool,
+}
+
+impl StderrProgress {
+    /// Creates a reporter that hasn't printed anything yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ends the progress line, if one was printed.
+    fn end_line(&mut self) {
+        if self.line_open {
+            eprintln!();
+            self.line_open = false;
+        }
+    }
+}
+
+impl ProgressReporter for StderrProgress {
+    fn on_note(&mut self, progress: &ExportProgress) {
+        eprint!("\rExported {}/{} notes", progress.done, progress.total);
+        self.line_open = true;
+        if progress.done >= progress.total {
+            self.end_line();
+        }
+    }
+
+    fn on_done(&mut self, _summary: &ExportSummary) {
+        self.end_line();
+    }
+}
+
+impl Drop for StderrProgress {
+    // Keeps later output off the progress line when an export fails
+    fn drop(&mut self) {
+        self.end_line();
+    }
+}
+
+impl Exporter {
+    /// Exports a folder like
+    /// [`export_folder_with_options`](Self::export_folder_with_options),
+    /// reporting its progress to `reporter` instead of the exporter's own
+    /// [`with_progress`](Self::with_progress) setting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{
+    ///     ExportOptions, ExportProgress, ExportSummary, Exporter, ProgressReporter,
+    /// };
+    ///
+    /// #[derive(Default)]
+    /// struct Recorder {
+    ///     events: Vec<String>,
+    /// }
+    ///
+    /// impl ProgressReporter for Recorder {
+    ///     fn on_note(&mut self, progress: &ExportProgress) {
+    ///         self.events.push(format!("{}/{}", progress.done, progress.total));
+    ///     }
+    ///
+    ///     fn on_done(&mut self, summary: &ExportSummary) {
+    ///         self.events.push(format!("done: {} notes", summary.actual_notes));
+    ///     }
+    /// }
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let mut recorder = Recorder::default();
+    /// exporter
+    ///     .export_folder_with_progress("Recipes", dir.path(), &ExportOptions::default(), &mut recorder)
+    ///     .unwrap();
+    /// assert_eq!(recorder.events, ["1/2", "2/2", "done: 2 notes"]);
+    /// # }
+    /// ```
+    pub fn export_folder_with_progress<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        output_dir: P,
+        options: &ExportOptions,
+        reporter: &mut impl ProgressReporter,
+    ) -> Result<ExportSummary> {
+        self.export_folder_reporting(
+            &parse_folder_spec(folder),
+            output_dir.as_ref(),
+            options,
+            Some(reporter as &mut dyn ProgressReporter),
+        )
+    }
+}
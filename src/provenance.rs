@@ -0,0 +1,70 @@
+//! Recording where an export came from.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{SecondsFormat, Utc};
+
+use crate::naming::fnv1a;
+use crate::{EMBEDDED_SCRIPT, ExportSummary, Exporter, FolderSpec, Result, ScriptSource};
+
+/// The name of the provenance file written at the export root.
+pub const PROVENANCE_FILE: &str = "EXPORT_INFO.txt";
+
+impl Exporter {
+    /// Appends a section describing an export of `folder` to
+    /// [`PROVENANCE_FILE`] in `output_dir`, so a directory that several
+    /// folders were exported into lists all of them.
+    pub(crate) fn write_provenance(
+        &self,
+        output_dir: &Path,
+        folder: &FolderSpec,
+        summary: &ExportSummary,
+    ) -> Result<()> {
+        let path = output_dir.join(PROVENANCE_FILE);
+        let mut section = String::new();
+        if path.exists() {
+            section.push('\n');
+        }
+
+        section.push_str(&format!(
+            "Exported by: apple-notes-exporter {}\n",
+            env!("CARGO_PKG_VERSION")
+        ));
+        section.push_str(&format!(
+            "Exported at: {}\n",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+        ));
+        section.push_str(&format!("Requested folder: {folder}\n"));
+        section.push_str(&format!("Source: {}\n", summary.matched_path.join(" > ")));
+        section.push_str(&format!("Notes: {}\n", summary.actual_notes));
+        if !summary.failed_notes.is_empty() {
+            section.push_str(&format!("Failed notes: {}\n", summary.failed_notes.len()));
+        }
+        section.push_str(&format!("Script: {}\n", self.script_description()?));
+
+        fs::File::options()
+            .create(true)
+            .append(true)
+            .open(&path)?
+            .write_all(section.as_bytes())?;
+        Ok(())
+    }
+
+    /// Describes the AppleScript in use, with a hash of its contents that
+    /// identifies the exact version.
+    fn script_description(&self) -> Result<String> {
+        Ok(match &self.script_source {
+            ScriptSource::Embedded => format!(
+                "embedded (fnv1a {:016x})",
+                fnv1a(EMBEDDED_SCRIPT.as_bytes())
+            ),
+            ScriptSource::Path(path) => format!(
+                "{} (fnv1a {:016x})",
+                path.display(),
+                fnv1a(&fs::read(path)?)
+            ),
+        })
+    }
+}
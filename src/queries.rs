@@ -0,0 +1,438 @@
+//! Read-only queries against the Notes app using small inline AppleScripts.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::markdown::parse_note_metadata_line;
+use crate::text_twin::count_text;
+use crate::timestamps::parse_date;
+use crate::{
+    AccountSelector, ExportError, Exporter, FolderSpec, Result, ScriptLine, parse_folder_spec,
+};
+
+/// Prints `account<TAB>folder` for every top-level folder of every account.
+const LIST_TOP_LEVEL_FOLDERS: &str = r#"
+set output to ""
+tell application "Notes"
+    repeat with anAccount in accounts
+        repeat with aFolder in folders of anAccount
+            set output to output & (name of anAccount) & tab & (name of aFolder) & linefeed
+        end repeat
+    end repeat
+end tell
+return output
+"#;
+
+/// Prints `account<TAB>folder<TAB>count` for every top-level folder of every
+/// account, counting the notes in the folder and all its subfolders.
+const COUNT_TOP_LEVEL_FOLDER_NOTES: &str = r#"
+on countNotes(aFolder)
+    tell application "Notes"
+        set total to count of notes of aFolder
+        repeat with subfolder in folders of aFolder
+            set total to total + (my countNotes(subfolder))
+        end repeat
+    end tell
+    return total
+end countNotes
+
+set output to ""
+tell application "Notes"
+    repeat with anAccount in accounts
+        repeat with aFolder in folders of anAccount
+            set noteCount to my countNotes(aFolder)
+            set output to output & (name of anAccount) & tab & (name of aFolder) & tab & noteCount & linefeed
+        end repeat
+    end repeat
+end tell
+return output
+"#;
+
+/// Prints the version of the Notes app, e.g. `4.11`.
+const NOTES_APP_VERSION: &str = r#"tell application "Notes" to return version"#;
+
+/// A folder in an Apple Notes account.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FolderInfo {
+    /// The account the folder belongs to (e.g. "iCloud").
+    pub account: String,
+    /// The folder name.
+    pub name: String,
+}
+
+impl FolderInfo {
+    /// Returns the `"AccountName:FolderName"` spec that selects this folder
+    /// when exporting, with colons in either name escaped.
+    pub fn spec(&self) -> String {
+        FolderSpec {
+            account: Some(AccountSelector::Named(self.account.clone())),
+            folder: self.name.clone(),
+        }
+        .to_string()
+    }
+}
+
+/// A note in an Apple Notes folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteInfo {
+    /// The note's title.
+    pub title: String,
+    /// The note's id, as used in exported filenames (`<title> -- <id>.html`).
+    pub id: String,
+    /// When the note was last modified, if the script reported a readable date.
+    pub modified: Option<SystemTime>,
+}
+
+/// The dates and sharing state of a note in an Apple Notes folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteMetadata {
+    /// The note's id, as used in exported filenames (`<title> -- <id>.html`).
+    pub id: String,
+    /// When the note was created, if the script reported a readable date.
+    pub created: Option<SystemTime>,
+    /// When the note was last modified, if the script reported a readable date.
+    pub modified: Option<SystemTime>,
+    /// Whether the note is shared with other people.
+    pub shared: bool,
+    /// The names of the people a shared note is shared with, if Notes
+    /// reports them. Empty for notes that are not shared.
+    pub participants: Vec<String>,
+    /// The number of words in the note's text, if counted; see
+    /// [`Exporter::note_metadata_with_counts`].
+    pub word_count: Option<usize>,
+    /// The number of characters in the note's text, including spaces but
+    /// not line breaks, if counted; see
+    /// [`Exporter::note_metadata_with_counts`].
+    pub char_count: Option<usize>,
+}
+
+impl NoteMetadata {
+    /// Estimates how long the note takes to read at `words_per_minute`,
+    /// rounded up to the second, or `None` if its words were not counted.
+    ///
+    /// Adults typically read 200 to 250 words per minute.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use apple_notes_exporter_rs::NoteMetadata;
+    ///
+    /// let note = NoteMetadata {
+    ///     id: "x-coredata-p101".to_string(),
+    ///     created: None,
+    ///     modified: None,
+    ///     shared: false,
+    ///     participants: Vec::new(),
+    ///     word_count: Some(500),
+    ///     char_count: Some(2800),
+    /// };
+    /// assert_eq!(note.reading_time(200), Some(Duration::from_secs(150)));
+    /// ```
+    pub fn reading_time(&self, words_per_minute: u32) -> Option<Duration> {
+        let words = self.word_count? as u64;
+        let words_per_minute = u64::from(words_per_minute.max(1));
+        Some(Duration::from_secs((words * 60).div_ceil(words_per_minute)))
+    }
+}
+
+impl Exporter {
+    /// Returns all top-level folders across all Apple Notes accounts.
+    ///
+    /// Unlike [`list_folders`](Self::list_folders), which prints the folders,
+    /// this returns them for programmatic use.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new();
+    /// for folder in exporter.list_folders_structured().expect("Failed to list folders") {
+    ///     println!("{} / {}", folder.account, folder.name);
+    /// }
+    /// ```
+    pub fn list_folders_structured(&self) -> Result<Vec<FolderInfo>> {
+        let output = self.eval_applescript(LIST_TOP_LEVEL_FOLDERS)?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(account, name)| FolderInfo {
+                account: account.to_string(),
+                name: name.to_string(),
+            })
+            .collect())
+    }
+
+    /// Returns every top-level folder across all accounts, as in
+    /// [`list_folders_structured`](Self::list_folders_structured), with the
+    /// number of notes in it.
+    ///
+    /// Only top-level folders are listed, and each count includes the notes
+    /// in the folder's subfolders. All folders are counted in a single run of
+    /// inline AppleScript, which is much faster than calling
+    /// [`count_notes`](Self::count_notes) for each folder, e.g. for an
+    /// overview of what is worth exporting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let counts = exporter.folder_note_counts().unwrap();
+    ///
+    /// let recipes = counts.iter().find(|(folder, _)| folder.name == "Recipes").unwrap();
+    /// assert_eq!(recipes.0.account, "iCloud");
+    /// assert_eq!(recipes.1, 2);
+    /// # }
+    /// ```
+    pub fn folder_note_counts(&self) -> Result<Vec<(FolderInfo, usize)>> {
+        let output = self.eval_applescript(COUNT_TOP_LEVEL_FOLDER_NOTES)?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let account = fields.next()?;
+                let name = fields.next()?;
+                let count = fields.next()?.trim().parse().ok()?;
+                let folder = FolderInfo {
+                    account: account.to_string(),
+                    name: name.to_string(),
+                };
+                Some((folder, count))
+            })
+            .collect())
+    }
+
+    /// Returns the names of the folders directly inside the folder at exactly
+    /// `parent_path` from the account's root, without their subfolders, e.g.
+    /// to expand one level of a folder tree on demand. An empty
+    /// `parent_path` returns the account's top-level folders, leaving out
+    /// "Recently Deleted".
+    ///
+    /// Fails with [`ExportError::FolderNotFound`] if any folder along the
+    /// path is missing.
+    ///
+    /// Needs a script with the `subfolders` command, which the embedded script
+    /// lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{AccountSelector, ExportError, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    ///
+    /// assert_eq!(exporter.list_subfolders("iCloud", &[]).unwrap(), ["Recipes", "Work"]);
+    /// assert_eq!(exporter.list_subfolders("iCloud", &["Recipes"]).unwrap(), ["Desserts"]);
+    /// assert!(exporter.list_subfolders("iCloud", &["Recipes", "Desserts"]).unwrap().is_empty());
+    /// assert_eq!(
+    ///     exporter.list_subfolders(AccountSelector::LocalAccount, &["Projects"]).unwrap(),
+    ///     ["Projects"]
+    /// );
+    ///
+    /// let error = exporter.list_subfolders("iCloud", &["Desserts"]).unwrap_err();
+    /// assert!(matches!(error, ExportError::FolderNotFound(_)));
+    /// # }
+    /// ```
+    pub fn list_subfolders<A: Into<AccountSelector>>(
+        &self,
+        account: A,
+        parent_path: &[&str],
+    ) -> Result<Vec<String>> {
+        let folder = account.into().folder_path(parent_path).script_arg();
+
+        let mut subfolders = Vec::new();
+        let mut not_found = None;
+        self.run_script_raw(
+            &["subfolders", &folder],
+            None,
+            |line| match ScriptLine::parse(&line) {
+                Some(ScriptLine::NotFound(folder)) => not_found = Some(folder),
+                _ if line.is_empty() => {}
+                _ => subfolders.push(line),
+            },
+        )?;
+
+        match not_found {
+            Some(folder) => Err(ExportError::FolderNotFound(folder)),
+            None => Ok(subfolders),
+        }
+    }
+
+    /// Returns the version of the Notes app, e.g. `"4.11"`.
+    ///
+    /// Notes versions differ in the HTML they produce, so include this in bug
+    /// reports about exported notes. Fails with
+    /// [`ExportError::ScriptFailed`](crate::ExportError::ScriptFailed) if
+    /// Notes can't be reached.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let version = Exporter::new().notes_app_version().expect("Failed to reach Notes");
+    /// println!("Notes {version}");
+    /// ```
+    pub fn notes_app_version(&self) -> Result<String> {
+        let output = self.eval_applescript(NOTES_APP_VERSION)?;
+        let version = output.trim();
+        if version.is_empty() {
+            return Err(ExportError::UnexpectedOutput(output));
+        }
+        Ok(version.to_string())
+    }
+
+    /// Returns the notes in a folder and its subfolders, in the order they
+    /// are exported, without exporting anything.
+    ///
+    /// The folder is found the same way as in
+    /// [`export_folder`](Self::export_folder). Use this to show a folder's
+    /// contents before exporting it, or to pick notes for
+    /// [`get_note_html`](Self::get_note_html).
+    ///
+    /// Needs a script with the `list-notes` command, which the embedded script
+    /// lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let notes = exporter.list_notes("Recipes").unwrap();
+    ///
+    /// let titles: Vec<_> = notes.iter().map(|note| note.title.as_str()).collect();
+    /// assert_eq!(titles, ["Brownies", "Pancakes"]);
+    /// assert_eq!(notes[1].id, "x-coredata-p101");
+    /// # }
+    /// ```
+    pub fn list_notes(&self, folder: &str) -> Result<Vec<NoteInfo>> {
+        let folder = parse_folder_spec(folder).script_arg();
+        let output = self.run_script_capture(&["list-notes", &folder])?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let id = fields.next()?;
+                let modified = fields.next()?;
+                let title = fields.next()?;
+                Some(NoteInfo {
+                    title: title.to_string(),
+                    id: id.to_string(),
+                    modified: parse_date(modified).map(SystemTime::from),
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the dates and sharing state of the notes in a folder and its
+    /// subfolders, without exporting anything.
+    ///
+    /// The folder is found the same way as in
+    /// [`export_folder`](Self::export_folder). Set
+    /// [`ExportOptions::mark_shared_notes`](crate::ExportOptions::mark_shared_notes)
+    /// to record the sharing state in the exported notes instead.
+    ///
+    /// Needs a script with the `note-metadata` command, which the embedded
+    /// script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let notes = exporter.note_metadata("Recipes").unwrap();
+    ///
+    /// let pancakes = notes.iter().find(|note| note.id == "x-coredata-p101").unwrap();
+    /// assert!(pancakes.shared);
+    /// assert_eq!(pancakes.participants, ["Alex Kim", "Sam Lee"]);
+    /// assert!(pancakes.created.is_some());
+    ///
+    /// let brownies = notes.iter().find(|note| note.id == "x-coredata-p102").unwrap();
+    /// assert!(!brownies.shared);
+    /// assert!(brownies.participants.is_empty());
+    /// # }
+    /// ```
+    pub fn note_metadata(&self, folder: &str) -> Result<Vec<NoteMetadata>> {
+        let folder = parse_folder_spec(folder).script_arg();
+        let output = self.run_script_capture(&["note-metadata", &folder])?;
+
+        Ok(output
+            .lines()
+            .filter_map(parse_note_metadata_line)
+            .map(|(id, note)| NoteMetadata {
+                id,
+                created: parse_date(note.created()).map(SystemTime::from),
+                modified: parse_date(note.updated()).map(SystemTime::from),
+                shared: note.shared(),
+                participants: note.participants().to_vec(),
+                word_count: None,
+                char_count: None,
+            })
+            .collect())
+    }
+
+    /// Returns the same as [`note_metadata`](Self::note_metadata), with the
+    /// words and characters in each note's text counted, e.g. to find the
+    /// folders with the most content.
+    ///
+    /// Every note's HTML is read from Notes and reduced to text as in
+    /// [`html_to_text`](crate::html_to_text), so this takes about as long as
+    /// exporting the folder, but nothing is written to disk. Notes whose HTML
+    /// the script fails to export are left uncounted.
+    ///
+    /// Needs a script with the `note-metadata` and `note-html` commands, which
+    /// the embedded script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::time::Duration;
+    ///
+    /// use apple_notes_exporter_rs::Exporter;
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let notes = exporter.note_metadata_with_counts("Recipes").unwrap();
+    ///
+    /// // "Pancakes", "Flour, milk, eggs. #breakfast", and two checklist items
+    /// let pancakes = notes.iter().find(|note| note.id == "x-coredata-p101").unwrap();
+    /// assert_eq!(pancakes.word_count, Some(9));
+    /// assert_eq!(pancakes.char_count, Some(53));
+    /// assert_eq!(pancakes.reading_time(220), Some(Duration::from_secs(3)));
+    /// # }
+    /// ```
+    pub fn note_metadata_with_counts(&self, folder: &str) -> Result<Vec<NoteMetadata>> {
+        let mut counts = HashMap::new();
+        for note in self.export_folder_iter(folder)? {
+            let note = note?;
+            counts.insert(note.id, count_text(&note.html));
+        }
+
+        let mut notes = self.note_metadata(folder)?;
+        for note in &mut notes {
+            if let Some(&(words, chars)) = counts.get(&note.id) {
+                note.word_count = Some(words);
+                note.char_count = Some(chars);
+            }
+        }
+        Ok(notes)
+    }
+}
@@ -0,0 +1,52 @@
+//! Waiting for the Notes app to finish starting up.
+
+use std::thread;
+use std::time::Duration;
+
+use crate::Exporter;
+
+/// A trivial query Notes can only answer once it is running.
+const READY_PROBE: &str = r#"tell application "Notes" to count of accounts"#;
+
+/// How many times to ask Notes before running the command anyway.
+const READY_ATTEMPTS: usize = 5;
+
+/// How long to wait between attempts.
+const READY_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+impl Exporter {
+    /// Waits for Notes before the first command this exporter (or any of its
+    /// clones) runs, unless turned off with
+    /// [`with_wait_for_ready`](Self::with_wait_for_ready).
+    pub(crate) fn ensure_ready(&self) {
+        if self.wait_for_ready {
+            self.ready.call_once(|| self.wait_until_ready());
+        }
+    }
+
+    /// Waits until Notes answers [`READY_PROBE`] the same way twice in a row.
+    ///
+    /// A freshly launched Notes can answer before it has loaded every account,
+    /// so one answer is not enough. Gives up quietly after [`READY_ATTEMPTS`]
+    /// tries and lets the real command report any error.
+    fn wait_until_ready(&self) {
+        let mut previous = None;
+        for attempt in 0..READY_ATTEMPTS {
+            if attempt > 0 {
+                thread::sleep(READY_RETRY_DELAY);
+            }
+
+            let answer = match self.osascript_eval(READY_PROBE) {
+                Ok(output) if output.status.success() => output.stdout,
+                _ => {
+                    previous = None;
+                    continue;
+                }
+            };
+            if previous.as_ref() == Some(&answer) {
+                return;
+            }
+            previous = Some(answer);
+        }
+    }
+}
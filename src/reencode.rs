@@ -0,0 +1,101 @@
+//! Re-encoding of extracted images (requires the `image` feature).
+
+use std::io::Cursor;
+
+use image::ImageFormat;
+use image::codecs::jpeg::JpegEncoder;
+
+/// The format extracted images are re-encoded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReencodeFormat {
+    /// JPEG at the given quality (1-100).
+    Jpeg {
+        /// Encoder quality, from 1 (smallest) to 100 (best).
+        quality: u8,
+    },
+    /// Lossless PNG.
+    Png,
+}
+
+impl ReencodeFormat {
+    fn mime_type(self) -> &'static str {
+        match self {
+            Self::Jpeg { .. } => "image/jpeg",
+            Self::Png => "image/png",
+        }
+    }
+}
+
+/// Re-encodes extracted images of selected types to a different format.
+///
+/// The written file gets the extension of the target format and the HTML
+/// references it accordingly. Images that cannot be decoded or re-encoded are
+/// written unchanged.
+///
+/// # Example
+///
+/// ```no_run
+/// use apple_notes_exporter_rs::{
+///     extract_attachments_from_directory_with_options, ExtractOptions, ReencodeFormat,
+///     ReencodeOptions,
+/// };
+///
+/// let options = ExtractOptions {
+///     reencode: Some(ReencodeOptions {
+///         source_mime_types: vec!["image/png".to_string()],
+///         target: ReencodeFormat::Jpeg { quality: 80 },
+///         min_bytes: 512 * 1024,
+///     }),
+///     ..Default::default()
+/// };
+///
+/// extract_attachments_from_directory_with_options("./exports", &options)
+///     .expect("Failed to extract attachments");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReencodeOptions {
+    /// MIME types of the images to re-encode (e.g. `"image/png"`).
+    pub source_mime_types: Vec<String>,
+    /// The format to re-encode to.
+    pub target: ReencodeFormat,
+    /// Only re-encode images whose decoded size is at least this many bytes.
+    pub min_bytes: usize,
+}
+
+impl ReencodeOptions {
+    /// Re-encodes `data` if it matches these options, returning the data and
+    /// MIME type to write. Falls back to the original on any failure.
+    pub(crate) fn apply<'a>(&self, data: Vec<u8>, mime_type: &'a str) -> (Vec<u8>, &'a str) {
+        if data.len() < self.min_bytes
+            || !self
+                .source_mime_types
+                .iter()
+                .any(|source| source == mime_type)
+        {
+            return (data, mime_type);
+        }
+
+        match self.encode(&data) {
+            Ok(encoded) => (encoded, self.target.mime_type()),
+            Err(_) => (data, mime_type),
+        }
+    }
+
+    fn encode(&self, data: &[u8]) -> image::ImageResult<Vec<u8>> {
+        let image = image::load_from_memory(data)?;
+        let mut encoded = Vec::new();
+
+        match self.target {
+            ReencodeFormat::Jpeg { quality } => {
+                // JPEG has no alpha channel
+                let encoder = JpegEncoder::new_with_quality(&mut encoded, quality);
+                image.to_rgb8().write_with_encoder(encoder)?;
+            }
+            ReencodeFormat::Png => {
+                image.write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)?;
+            }
+        }
+
+        Ok(encoded)
+    }
+}
@@ -0,0 +1,241 @@
+//! Writing JSON reports of exports and extracted attachments.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{ExportSummary, ExtractedAttachment, ExtractionResult, Result};
+
+/// One attachment in the manifest written by [`write_extraction_report`].
+#[derive(Serialize)]
+struct ReportEntry<'a> {
+    source_note: &'a Path,
+    #[serde(flatten)]
+    attachment: &'a ExtractedAttachment,
+}
+
+/// Writes a JSON manifest of every attachment in `results` to `path`.
+///
+/// The manifest is an array with one object per attachment, in the order
+/// they were extracted:
+///
+/// ```json
+/// [
+///   {
+///     "source_note": "exports/My Notes/Trip -- abc123.html",
+///     "path": "exports/My Notes/Trip -- abc123-attachments/attachment-001.png",
+///     "mime_type": "image/png",
+///     "bytes": 48213
+///   }
+/// ]
+/// ```
+///
+/// Paths are written as they appear in `results`, and each object has the
+/// fields [`ExtractedAttachment`] serializes to. Notes without attachments
+/// are left out.
+///
+/// # Example
+///
+/// ```
+/// use std::fs;
+///
+/// use apple_notes_exporter_rs::{extract_attachments_from_html, write_extraction_report};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let html_path = dir.path().join("Trip -- abc123.html");
+/// fs::write(&html_path, r#"<img src="data:image/png;base64,iVBORw0KGgo=">"#).unwrap();
+/// let results = vec![extract_attachments_from_html(&html_path).unwrap()];
+///
+/// let report = dir.path().join("attachments.json");
+/// write_extraction_report(&results, &report).unwrap();
+///
+/// let json = fs::read_to_string(&report).unwrap();
+/// assert!(json.contains("Trip -- abc123.html"));
+/// assert!(json.contains("Trip -- abc123-attachments/attachment-001.png"));
+/// assert!(json.contains(r#""mime_type": "image/png""#));
+/// assert!(json.contains(r#""bytes": 8"#));
+/// ```
+pub fn write_extraction_report(results: &[ExtractionResult], path: &Path) -> Result<()> {
+    let entries: Vec<ReportEntry> = results
+        .iter()
+        .flat_map(|result| {
+            result.attachments.iter().map(|attachment| ReportEntry {
+                source_note: &result.html_path,
+                attachment,
+            })
+        })
+        .collect();
+    let mut json = serde_json::to_string_pretty(&entries).map_err(io::Error::from)?;
+    json.push('\n');
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, json)?;
+
+    Ok(())
+}
+
+/// Formats what an export did as JSON, for scripts wrapping the CLI.
+///
+/// `summaries` are the exported folders and `extracted` the results of
+/// extracting their attachments afterwards, if any:
+///
+/// ```json
+/// {
+///   "folders": [
+///     {
+///       "path": ["iCloud", "Recipes"],
+///       "notes": 2,
+///       "expected_notes": null,
+///       "failed_notes": [{"note": "Trip", "reason": "timed out"}],
+///       "truncated_notes": [],
+///       "large_notes": [],
+///       "empty_notes": [],
+///       "created_dirs": ["Recipes"],
+///       "pruned_dirs": 0
+///     }
+///   ],
+///   "attachments": {"count": 3, "bytes": 48213, "notes": 2}
+/// }
+/// ```
+///
+/// Paths are relative to the output directory, and `attachments.notes` is
+/// the number of notes whose images were extracted.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::{export_summary_json, ExportSummary, FailedNote};
+///
+/// let summary = ExportSummary {
+///     matched_path: vec!["iCloud".to_string(), "Recipes".to_string()],
+///     actual_notes: 2,
+///     failed_notes: vec![FailedNote {
+///         note: "Trip".to_string(),
+///         reason: "timed out".to_string(),
+///     }],
+///     ..Default::default()
+/// };
+///
+/// let json = export_summary_json(&[summary], &[]);
+/// assert!(json.contains(r#""path": ["iCloud", "Recipes"]"#));
+/// assert!(json.contains(r#""notes": 2,"#));
+/// assert!(json.contains(r#"{"note": "Trip", "reason": "timed out"}"#));
+/// assert!(json.contains(r#""attachments": {"count": 0, "bytes": 0, "notes": 0}"#));
+/// ```
+pub fn export_summary_json(summaries: &[ExportSummary], extracted: &[ExtractionResult]) -> String {
+    let folders: Vec<String> = summaries.iter().map(folder_json).collect();
+    let folders = if folders.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n  ]", folders.join(",\n"))
+    };
+
+    let attachments = extracted.iter().flat_map(|result| &result.attachments);
+    let count = attachments.clone().count();
+    let bytes: u64 = attachments.map(|attachment| attachment.size).sum();
+    let notes = extracted
+        .iter()
+        .filter(|result| !result.attachments.is_empty())
+        .count();
+    let attachments = format!(r#"{{"count": {count}, "bytes": {bytes}, "notes": {notes}}}"#);
+
+    format!(
+        "{}\n",
+        json_object(&[("folders", folders), ("attachments", attachments)], 0)
+    )
+}
+
+/// Formats one exported folder for [`export_summary_json`].
+fn folder_json(summary: &ExportSummary) -> String {
+    let path = json_array(summary.matched_path.iter().map(|name| json_string(name)));
+    let expected_notes = summary
+        .expected_notes
+        .map_or("null".to_string(), |expected| expected.to_string());
+    let failed_notes = json_array(summary.failed_notes.iter().map(|failure| {
+        format!(
+            r#"{{"note": {}, "reason": {}}}"#,
+            json_string(&failure.note),
+            json_string(&failure.reason)
+        )
+    }));
+    let truncated_notes = json_array(summary.truncated_notes.iter().map(|note| {
+        format!(
+            r#"{{"original": {}, "path": {}}}"#,
+            json_string(&note.original.to_string_lossy()),
+            json_string(&note.path.to_string_lossy())
+        )
+    }));
+    let large_notes = json_array(summary.large_notes.iter().map(|(path, bytes)| {
+        format!(
+            r#"{{"path": {}, "bytes": {bytes}}}"#,
+            json_string(&path.to_string_lossy())
+        )
+    }));
+    let empty_notes = json_array(
+        summary
+            .empty_notes
+            .iter()
+            .map(|path| json_string(&path.to_string_lossy())),
+    );
+    let created_dirs = json_array(
+        summary
+            .created_dirs
+            .iter()
+            .map(|dir| json_string(&dir.to_string_lossy())),
+    );
+
+    let fields = [
+        ("path", path),
+        ("notes", summary.actual_notes.to_string()),
+        ("expected_notes", expected_notes),
+        ("failed_notes", failed_notes),
+        ("truncated_notes", truncated_notes),
+        ("large_notes", large_notes),
+        ("empty_notes", empty_notes),
+        ("created_dirs", created_dirs),
+        ("pruned_dirs", summary.pruned_dirs.to_string()),
+    ];
+    format!("    {}", json_object(&fields, 2))
+}
+
+/// Formats `fields` as a JSON object with one field per line, indented
+/// `depth` levels.
+pub(crate) fn json_object(fields: &[(&str, String)], depth: usize) -> String {
+    let indent = "  ".repeat(depth);
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{indent}  {}: {value}", json_string(name)))
+        .collect();
+    format!("{{\n{}\n{indent}}}", fields.join(",\n"))
+}
+
+/// Formats JSON `items` as an array on one line.
+pub(crate) fn json_array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(", "))
+}
+
+/// Quotes `text` as a JSON string.
+pub(crate) fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
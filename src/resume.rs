@@ -0,0 +1,210 @@
+//! Exports that pick up where an interrupted run left off.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::markdown::parse_note_dates;
+use crate::{
+    CancellationToken, ExportError, ExportSummary, Exporter, FailedNote, HtmlTransform, Result,
+    check_writable, checklists_to_inputs, note_path, parse_folder_spec, write_atomically,
+};
+
+/// The name of the checkpoint file [`Exporter::export_folder_resumable`]
+/// keeps in the output directory.
+pub const CHECKPOINT_FILE: &str = ".export-checkpoint";
+
+impl Exporter {
+    /// Exports a folder one note at a time, recording each note in
+    /// [`CHECKPOINT_FILE`] as soon as it is written, so an export that is
+    /// interrupted (by a crash, cancellation, or the machine sleeping) can be
+    /// run again and picks up where it left off.
+    ///
+    /// Notes the checkpoint lists with the same modification date are
+    /// skipped if their file is still there; the rest are exported to the
+    /// path a folder export to `output_dir` would use. A note whose title
+    /// changed since it was written is moved to its new name. The checkpoint
+    /// is kept once the export completes, so running it again later only
+    /// exports the notes that changed. Notes deleted from Notes are left in
+    /// place.
+    ///
+    /// Every note takes its own script runs, so this is slower than
+    /// [`export_folder`](Self::export_folder) for a full export but loses at
+    /// most one note's work when interrupted, which makes very large
+    /// libraries practical. Checklists are converted and the exporter's HTML
+    /// transform applied as with [`ExportOptions::default()`](crate::ExportOptions::default).
+    /// Notes the script fails to export are listed in
+    /// [`ExportSummary::failed_notes`] by id and retried on the next run.
+    ///
+    /// Needs a script with the `note-metadata`, `note-path`, and `note-html`
+    /// commands, which the embedded script lacks; see
+    /// [Script Commands](crate#script-commands).
+    ///
+    /// # Arguments
+    ///
+    /// * `folder` - The folder to export (or `"AccountName:FolderName"`).
+    /// * `output_dir` - The output directory, which holds the checkpoint.
+    /// * `cancellation` - Stops the export between notes when triggered,
+    ///   returning [`ExportError::Cancelled`]. Running it again resumes.
+    ///
+    /// # Returns
+    ///
+    /// A summary in which [`ExportSummary::actual_notes`] counts the notes
+    /// written by this run and [`ExportSummary::skipped_notes`] the ones
+    /// already exported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::fs;
+    ///
+    /// use apple_notes_exporter_rs::{CancellationToken, ExportError, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    /// let dir = tempfile::tempdir().unwrap();
+    ///
+    /// let token = CancellationToken::new();
+    /// token.cancel();
+    /// let error = exporter
+    ///     .export_folder_resumable("Recipes", dir.path(), Some(&token))
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ExportError::Cancelled));
+    ///
+    /// let summary = exporter.export_folder_resumable("Recipes", dir.path(), None).unwrap();
+    /// assert_eq!((summary.actual_notes, summary.skipped_notes), (2, 0));
+    ///
+    /// // Only the missing note is exported again
+    /// fs::remove_file(dir.path().join("Recipes/Pancakes -- x-coredata-p101.html")).unwrap();
+    /// let summary = exporter.export_folder_resumable("Recipes", dir.path(), None).unwrap();
+    /// assert_eq!((summary.actual_notes, summary.skipped_notes), (1, 1));
+    /// assert!(dir.path().join("Recipes/Pancakes -- x-coredata-p101.html").is_file());
+    /// # }
+    /// ```
+    pub fn export_folder_resumable<P: AsRef<Path>>(
+        &self,
+        folder: &str,
+        output_dir: P,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<ExportSummary> {
+        self.check_platform()?;
+        self.ensure_ready();
+
+        let output_dir = output_dir.as_ref();
+        fs::create_dir_all(output_dir)
+            .map_err(|error| ExportError::OutputNotWritable(output_dir.to_path_buf(), error))?;
+        let output_dir = output_dir.canonicalize()?;
+        check_writable(&output_dir)?;
+
+        let folder = parse_folder_spec(folder).script_arg();
+        let notes = parse_note_dates(&self.run_script_capture(&["note-metadata", &folder])?);
+        let mut ids: Vec<&String> = notes.keys().collect();
+        ids.sort();
+
+        let checkpoint_path = output_dir.join(CHECKPOINT_FILE);
+        let contents = match fs::read_to_string(&checkpoint_path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error.into()),
+        };
+        let written = parse_checkpoint(&contents);
+        let mut checkpoint = fs::File::options()
+            .create(true)
+            .append(true)
+            .open(&checkpoint_path)?;
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            // End the line an interrupted run was writing
+            checkpoint.write_all(b"\n")?;
+        }
+
+        let mut summary = ExportSummary::default();
+        for id in ids {
+            if cancellation.is_some_and(CancellationToken::is_cancelled) {
+                return Err(ExportError::Cancelled);
+            }
+
+            let updated = notes[id].updated();
+            let previous = written.get(id.as_str());
+            if let Some(&(previous_updated, previous_path)) = previous
+                && previous_updated == updated
+                && output_dir.join(previous_path).is_file()
+            {
+                summary.skipped_notes += 1;
+                continue;
+            }
+
+            match self.export_checkpointed_note(&folder, id, &output_dir) {
+                Ok(relative) => {
+                    if let Some(&(_, previous_path)) = previous
+                        && previous_path != relative
+                    {
+                        let _ = fs::remove_file(output_dir.join(previous_path));
+                    }
+                    checkpoint.write_all(
+                        format!("{id}\t{updated}\t{}\n", relative.display()).as_bytes(),
+                    )?;
+                    checkpoint.sync_data()?;
+                    summary.actual_notes += 1;
+                }
+                Err(
+                    error @ (ExportError::ScriptFailed(_)
+                    | ExportError::UnexpectedOutput(_)
+                    | ExportError::OutputNotUtf8(_)),
+                ) => {
+                    summary.failed_notes.push(FailedNote {
+                        note: id.clone(),
+                        reason: error.to_string(),
+                    });
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Writes the note with `id` of `folder` (a script argument) under
+    /// `output_dir`, returning its path relative to `output_dir`.
+    fn export_checkpointed_note(
+        &self,
+        folder: &str,
+        id: &str,
+        output_dir: &Path,
+    ) -> Result<PathBuf> {
+        let relative = self.run_script_capture(&["note-path", folder, id])?;
+        let relative = note_path(relative.trim())?.to_path_buf();
+
+        let html = self.run_script_capture(&["note-html", folder, id])?;
+        let html = self.inline_object_attachments(&html).unwrap_or(html);
+        let mut html = checklists_to_inputs(&html);
+        if let Some(HtmlTransform(transform)) = &self.html_transform {
+            html = transform(&html);
+        }
+
+        let path = output_dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written atomically, so an interrupted run never leaves half a note
+        write_atomically(&path, html.as_bytes())?;
+
+        Ok(relative)
+    }
+}
+
+/// Parses the lines of a checkpoint file, mapping each note id to the
+/// modification date and relative path it was written with.
+fn parse_checkpoint(contents: &str) -> HashMap<&str, (&str, &Path)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let id = fields.next()?;
+            let updated = fields.next()?;
+            let path = fields.next()?;
+            Some((id, (updated, Path::new(path))))
+        })
+        .collect()
+}
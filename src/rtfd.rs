@@ -0,0 +1,89 @@
+//! RTF/RTFD export.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::Result;
+use crate::markdown::copy_dir;
+
+/// Converts every note in `export_dir` to RTF or RTFD in `output_dir`,
+/// mirroring its folder structure.
+///
+/// Notes with an attachment folder become `.rtfd` bundles with their images
+/// embedded, and the rest become `.rtf` files. A note that cannot be
+/// converted is copied as HTML, with its attachments, and a warning is
+/// passed to `warn`.
+pub(crate) fn write_rtfd_tree(
+    export_dir: &Path,
+    output_dir: &Path,
+    warn: impl Fn(&str) + Copy,
+) -> Result<()> {
+    fs::create_dir_all(output_dir)?;
+
+    for entry in fs::read_dir(export_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = output_dir.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            // Attachment folders are embedded in the RTFD bundles instead
+            if !entry
+                .file_name()
+                .to_string_lossy()
+                .ends_with("-attachments")
+            {
+                write_rtfd_tree(&path, &target, warn)?;
+            }
+            continue;
+        }
+
+        if path.extension().is_none_or(|ext| ext != "html") {
+            continue;
+        }
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("note");
+        let attachments_dir = path.with_file_name(format!("{stem}-attachments"));
+        let format = if attachments_dir.is_dir() {
+            "rtfd"
+        } else {
+            "rtf"
+        };
+
+        if let Err(error) = convert_with_textutil(&path, &target.with_extension(format), format) {
+            warn(&format!(
+                "could not convert {} to {}, keeping it as HTML: {error}",
+                path.display(),
+                format.to_uppercase()
+            ));
+            fs::copy(&path, &target)?;
+            if attachments_dir.is_dir() {
+                copy_dir(
+                    &attachments_dir,
+                    &output_dir.join(format!("{stem}-attachments")),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts the HTML file `input` to `format` (`"rtf"` or `"rtfd"`) using the
+/// macOS `textutil` tool, which resolves relative image paths.
+fn convert_with_textutil(input: &Path, output: &Path, format: &str) -> io::Result<()> {
+    let status = Command::new("textutil")
+        .args(["-convert", format, "-output"])
+        .arg(output)
+        .arg(input)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("textutil exited with {status}")));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Logging what osascript prints to a file.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::Local;
+
+/// A log file that osascript's output is copied to, one timestamped line at a time.
+pub(crate) struct SessionLog {
+    file: Mutex<File>,
+}
+
+impl SessionLog {
+    /// Opens `path` for appending, creating it if needed.
+    pub(crate) fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends `line` from `stream` (e.g. `"stdout"`) with the current time.
+    ///
+    /// Write errors are ignored, so a full disk never fails the export itself.
+    pub(crate) fn line(&self, stream: &str, line: &str) {
+        let entry = format!(
+            "{} [{stream}] {line}\n",
+            Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z")
+        );
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(entry.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Marking notes that are shared with other people.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::Result;
+use crate::markdown::NoteDates;
+use crate::single_html::escape_html;
+
+/// Adds `data-shared` and `data-participants` attributes to the `<body>` of
+/// every shared note under `dir`, found by the id in its file name.
+pub(crate) fn mark_directory(dir: &Path, metadata: &HashMap<String, NoteDates>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            mark_directory(&path, metadata)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let note = stem
+                .rsplit_once(" -- ")
+                .and_then(|(_, id)| metadata.get(id))
+                .filter(|note| note.shared());
+            if let Some(note) = note {
+                let html = fs::read_to_string(&path)?;
+                fs::write(&path, mark(&html, note.participants()))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the sharing attributes to the document's `<body>`, wrapping the
+/// document in one if there is none.
+fn mark(html: &str, participants: &[String]) -> String {
+    let mut attributes = String::from(r#" data-shared="true""#);
+    if !participants.is_empty() {
+        let names = escape_html(&participants.join(", "));
+        attributes.push_str(&format!(r#" data-participants="{names}""#));
+    }
+
+    let lowercase = html.to_ascii_lowercase();
+    let body = lowercase
+        .match_indices("<body")
+        .map(|(position, _)| position + "<body".len())
+        .find(|&end| lowercase[end..].starts_with(['>', '/', ' ', '\t', '\n', '\r']));
+
+    match body {
+        Some(position) => {
+            let mut html = html.to_string();
+            html.insert_str(position, &attributes);
+            html
+        }
+        None => format!("<body{attributes}>\n{html}</body>\n"),
+    }
+}
@@ -0,0 +1,104 @@
+//! Combining an exported folder into a single self-contained HTML document.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use scraper::{Html, Selector};
+
+use crate::Result;
+use crate::export_tree::{TreeFolder, read_export_tree};
+
+/// Renders the export in `export_dir` as one HTML document titled `title`.
+///
+/// Each note becomes an `<article>` with an anchor, each subfolder a nested
+/// `<section>`, and a table of contents links to all of them. Images are left
+/// exactly as exported, so inline data URLs keep the document self-contained.
+pub(crate) fn render_single_html(export_dir: &Path, title: &str) -> Result<String> {
+    let root = read_export_tree(export_dir, title)?;
+
+    let mut toc = String::new();
+    let mut content = String::new();
+    let mut next_id = 0;
+    render_folder(&root, 1, &mut next_id, &mut toc, &mut content)?;
+
+    let title = escape_html(&root.name);
+    Ok(format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n\
+         <style>\n\
+         @media print {{ article {{ break-before: page; }} }}\n\
+         </style>\n\
+         </head>\n\
+         <body>\n\
+         <nav>\n{toc}</nav>\n\
+         {content}\
+         </body>\n\
+         </html>\n"
+    ))
+}
+
+fn render_folder(
+    folder: &TreeFolder,
+    depth: usize,
+    next_id: &mut usize,
+    toc: &mut String,
+    content: &mut String,
+) -> Result<()> {
+    let body_selector = Selector::parse("body").unwrap();
+    let heading = depth.min(6);
+    let note_heading = (depth + 1).min(6);
+
+    *next_id += 1;
+    let folder_id = format!("folder-{next_id}");
+    let name = escape_html(&folder.name);
+
+    let _ = writeln!(toc, "<ul>");
+    let _ = writeln!(content, "<section id=\"{folder_id}\">");
+    let _ = writeln!(content, "<h{heading}>{name}</h{heading}>");
+
+    for note in &folder.notes {
+        *next_id += 1;
+        let note_id = format!("note-{next_id}");
+        let title = escape_html(&note.title);
+
+        let html = fs::read_to_string(&note.path)?;
+        let document = Html::parse_document(&html);
+        let body = document.select(&body_selector).next().map_or_else(
+            || document.root_element().inner_html(),
+            |body| body.inner_html(),
+        );
+
+        let _ = writeln!(toc, "<li><a href=\"#{note_id}\">{title}</a></li>");
+        let _ = writeln!(content, "<article id=\"{note_id}\">");
+        let _ = writeln!(content, "<h{note_heading}>{title}</h{note_heading}>");
+        let _ = writeln!(content, "{body}");
+        let _ = writeln!(content, "</article>");
+    }
+
+    for child in &folder.children {
+        let child_id = *next_id + 1;
+        let _ = writeln!(
+            toc,
+            "<li><a href=\"#folder-{child_id}\">{}</a>",
+            escape_html(&child.name)
+        );
+        render_folder(child, depth + 1, next_id, toc, content)?;
+        let _ = writeln!(toc, "</li>");
+    }
+
+    let _ = writeln!(toc, "</ul>");
+    let _ = writeln!(content, "</section>");
+
+    Ok(())
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,284 @@
+//! Streaming notes from the script as they are exported.
+
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use tempfile::NamedTempFile;
+
+use crate::export_tree::note_title_from_stem;
+use crate::session_log::SessionLog;
+use crate::{
+    ExportError, Exporter, FailedNote, Result, ScriptLine, ScriptSource, forward_line, lossy_lines,
+    note_path, parse_folder_spec, script_exit_code,
+};
+
+/// A note read from [`Exporter::export_folder_iter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedNote {
+    /// The note's title.
+    pub title: String,
+    /// The note's id, as used in exported filenames (`<title> -- <id>.html`).
+    pub id: String,
+    /// The path [`export_folder`](Exporter::export_folder) writes the note
+    /// to, relative to the output directory, starting with the folder name.
+    pub path: PathBuf,
+    /// The note's HTML, exactly as the script printed it.
+    pub html: String,
+}
+
+/// An iterator over the notes of a folder as the script exports them; see
+/// [`Exporter::export_folder_iter`].
+///
+/// Dropping it before the end stops the script.
+pub struct NoteStream {
+    child: Child,
+    stderr: Option<JoinHandle<bool>>,
+    lines: Box<dyn Iterator<Item = io::Result<String>> + Send>,
+    log: Option<Arc<SessionLog>>,
+    matched_path: Vec<String>,
+    failed_notes: Vec<FailedNote>,
+    success_codes: Vec<i32>,
+    script_output_to_stderr: bool,
+    exit_code: Option<i32>,
+    finished: bool,
+    // Kept on disk until osascript exits
+    _script: Option<NamedTempFile>,
+}
+
+impl NoteStream {
+    /// The path of the folder that was matched, starting with the account
+    /// name, like [`ExportSummary::matched_path`](crate::ExportSummary::matched_path).
+    pub fn matched_path(&self) -> &[String] {
+        &self.matched_path
+    }
+
+    /// The notes the script reported as failed so far. They are skipped,
+    /// and the rest of the folder is still read.
+    pub fn failed_notes(&self) -> &[FailedNote] {
+        &self.failed_notes
+    }
+
+    /// The status the script exited with, once every note has been read: 0,
+    /// or one of the codes allowed with
+    /// [`Exporter::with_success_codes`](crate::Exporter::with_success_codes),
+    /// like [`ExportSummary::exit_code`](crate::ExportSummary::exit_code).
+    /// `None` before then, or if the iteration ended with an error.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Returns the next line the script printed to stdout.
+    fn next_line(&mut self) -> Option<Result<String>> {
+        let line = self.lines.next()?;
+        if let (Some(log), Ok(line)) = (&self.log, &line) {
+            log.line("stdout", line);
+        }
+        Some(line.map_err(ExportError::from))
+    }
+
+    /// Reads up to the next note, or `None` at the end of the output.
+    fn read_note(&mut self) -> Result<Option<ExportedNote>> {
+        while let Some(line) = self.next_line() {
+            let line = line?;
+            if let Some(header) = line.strip_prefix("note: ") {
+                return self.read_note_body(header).map(Some);
+            }
+
+            self.handle_line(&line)?;
+        }
+
+        Ok(None)
+    }
+
+    /// Handles a line other than a note, forwarding unstructured ones to
+    /// stdout, or stderr; see [`Exporter::with_script_output_to_stderr`].
+    fn handle_line(&mut self, line: &str) -> Result<()> {
+        match ScriptLine::parse(line) {
+            Some(ScriptLine::Matched(path)) => self.matched_path = path,
+            Some(ScriptLine::Failed(failure)) => self.failed_notes.push(failure),
+            Some(ScriptLine::NotFound(folder)) => return Err(ExportError::FolderNotFound(folder)),
+            Some(ScriptLine::Progress { .. } | ScriptLine::Exported { .. }) => {}
+            None => forward_line(line, self.script_output_to_stderr),
+        }
+        Ok(())
+    }
+
+    /// Reads the HTML following a `note: <path>\t<lines>` header.
+    fn read_note_body(&mut self, header: &str) -> Result<ExportedNote> {
+        let unexpected = || ExportError::UnexpectedOutput(format!("note: {header}"));
+        let (path, line_count) = header.rsplit_once('\t').ok_or_else(unexpected)?;
+        let line_count: usize = line_count.trim().parse().map_err(|_| unexpected())?;
+        let path = note_path(path)?.to_path_buf();
+
+        let mut html = String::new();
+        for _ in 0..line_count {
+            let line = self.next_line().ok_or_else(|| {
+                ExportError::UnexpectedOutput(format!("{} ended early", path.display()))
+            })??;
+            html.push_str(&line);
+            html.push('\n');
+        }
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let id = stem.rsplit_once(" -- ").map_or("", |(_, id)| id);
+        Ok(ExportedNote {
+            title: note_title_from_stem(stem).to_string(),
+            id: id.to_string(),
+            path,
+            html,
+        })
+    }
+
+    /// Waits for the script to exit, failing if its status does not count as
+    /// success; see [`Exporter::with_success_codes`].
+    fn finish(&mut self) -> Result<()> {
+        self.finished = true;
+        let status = self.child.wait().map_err(ExportError::LaunchError)?;
+        if let Some(log) = &self.log {
+            log.line("exit", &status.to_string());
+        }
+        let stderr = self.stderr.take().expect("finished only once");
+        self.exit_code = Some(script_exit_code(status, stderr, &self.success_codes)?);
+        Ok(())
+    }
+
+    /// Stops the script without waiting for the rest of its output.
+    fn stop(&mut self) {
+        self.finished = true;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Iterator for NoteStream {
+    type Item = Result<ExportedNote>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.read_note() {
+            Ok(Some(note)) => Some(Ok(note)),
+            Ok(None) => self.finish().err().map(Err),
+            Err(error) => {
+                self.stop();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl Drop for NoteStream {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.stop();
+        }
+    }
+}
+
+impl std::fmt::Debug for NoteStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NoteStream")
+            .field("matched_path", &self.matched_path)
+            .field("failed_notes", &self.failed_notes)
+            .field("exit_code", &self.exit_code)
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Exporter {
+    /// Exports a folder and its subfolders one note at a time, yielding each
+    /// note's HTML as the script prints it instead of writing files.
+    ///
+    /// Only one note is held in memory at a time, so very large folders can
+    /// be written, indexed, or transformed as they are read. Notes are
+    /// yielded exactly as the script prints them: none of the
+    /// [`ExportOptions`](crate::ExportOptions) are applied. The folder is
+    /// found the same way as in [`export_folder`](Self::export_folder), and
+    /// an unknown folder fails with [`ExportError::FolderNotFound`] before
+    /// any note is read.
+    ///
+    /// Notes the script fails to export are skipped and listed in
+    /// [`NoteStream::failed_notes`]. Other errors, including the script
+    /// failing, end the iteration. The script's exit status is checked as in
+    /// [`export_folder`](Self::export_folder), honoring
+    /// [`with_success_codes`](Self::with_success_codes), and is available
+    /// from [`NoteStream::exit_code`] once the last note has been read.
+    ///
+    /// Needs a script with the `export-stream` command, which the embedded
+    /// script lacks; see [Script Commands](crate#script-commands).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use apple_notes_exporter_rs::{ExportError, Exporter};
+    ///
+    /// let exporter = Exporter::new().with_osascript_path("fixtures/fake-osascript");
+    ///
+    /// let mut paths = Vec::new();
+    /// for note in exporter.export_folder_iter("Recipes").unwrap() {
+    ///     let note = note.unwrap();
+    ///     assert!(note.html.contains(&format!("<h1>{}</h1>", note.title)));
+    ///     paths.push(note.path.display().to_string());
+    /// }
+    /// assert_eq!(
+    ///     paths,
+    ///     [
+    ///         "Recipes/Desserts/Brownies -- x-coredata-p102.html",
+    ///         "Recipes/Pancakes -- x-coredata-p101.html",
+    ///     ]
+    /// );
+    ///
+    /// let mut stream = exporter.export_folder_iter("Recipes").unwrap();
+    /// assert_eq!(stream.by_ref().count(), 2);
+    /// assert_eq!(stream.exit_code(), Some(0));
+    ///
+    /// let error = exporter.export_folder_iter("Nonexistent").unwrap_err();
+    /// assert!(matches!(error, ExportError::FolderNotFound(_)));
+    /// # }
+    /// ```
+    pub fn export_folder_iter(&self, folder: &str) -> Result<NoteStream> {
+        let folder = parse_folder_spec(folder).script_arg();
+        let args = ["export-stream", folder.as_str()];
+        self.check_embedded_support(&args)?;
+        self.check_platform()?;
+        self.ensure_ready();
+
+        let (script, temp_file) = match &self.script_source {
+            ScriptSource::Embedded => self.embedded_script()?,
+            ScriptSource::Path(path) => (path.canonicalize()?, None),
+        };
+
+        let (mut child, log, stderr) = self.spawn_osascript(&script, &args)?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut stream = NoteStream {
+            child,
+            stderr: Some(stderr),
+            lines: Box::new(lossy_lines(BufReader::new(stdout))),
+            log,
+            matched_path: Vec::new(),
+            failed_notes: Vec::new(),
+            success_codes: self.success_codes.clone(),
+            script_output_to_stderr: self.script_output_to_stderr,
+            exit_code: None,
+            finished: false,
+            _script: temp_file,
+        };
+
+        // The script starts with the matched folder, so an unknown one fails here
+        while stream.matched_path.is_empty()
+            && let Some(line) = stream.next_line()
+        {
+            stream.handle_line(&line?)?;
+        }
+
+        Ok(stream)
+    }
+}
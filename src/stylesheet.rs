@@ -0,0 +1,72 @@
+//! Styling exported notes with a CSS stylesheet.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::Result;
+
+/// The stylesheet bundled with the crate, used by [`Stylesheet::Default`].
+pub const DEFAULT_STYLESHEET: &str = include_str!("../assets/notes.css");
+
+/// Which CSS, if any, to add to each exported note.
+///
+/// The CSS is embedded in a `<style>` element in each note's `<head>`, so
+/// notes stay self-contained when moved or shared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Stylesheet {
+    /// Leave the HTML as Notes exported it.
+    #[default]
+    None,
+    /// The bundled [`DEFAULT_STYLESHEET`], with readable line lengths, scaled
+    /// images, and a dark mode.
+    Default,
+    /// The contents of a CSS file.
+    Custom(PathBuf),
+}
+
+impl Stylesheet {
+    /// Returns the CSS to embed, reading it from disk for [`Stylesheet::Custom`].
+    pub(crate) fn css(&self) -> Result<Option<String>> {
+        match self {
+            Self::None => Ok(None),
+            Self::Default => Ok(Some(DEFAULT_STYLESHEET.to_string())),
+            Self::Custom(path) => Ok(Some(fs::read_to_string(path)?)),
+        }
+    }
+}
+
+/// Embeds `css` in every HTML file under `dir`.
+pub(crate) fn inject_directory(dir: &Path, css: &str) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            inject_directory(&path, css)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read_to_string(&path)?;
+            fs::write(&path, inject(&html, css))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Inserts a `<style>` element with `css` at the end of the document's
+/// `<head>`, adding a `<head>` if there is none.
+fn inject(html: &str, css: &str) -> String {
+    let style = format!("<style>\n{css}</style>\n");
+    let lowercase = html.to_ascii_lowercase();
+
+    let (position, insert) = if let Some(position) = lowercase.find("</head>") {
+        (position, style)
+    } else if let Some(position) = lowercase.find("<body") {
+        (position, format!("<head>\n{style}</head>\n"))
+    } else {
+        (0, style)
+    };
+
+    let mut html = html.to_string();
+    html.insert_str(position, &insert);
+    html
+}
@@ -0,0 +1,261 @@
+//! Plain-text copies of exported notes, for search indexing.
+
+use std::fs;
+use std::path::Path;
+
+use scraper::{ElementRef, Html, Node};
+
+use crate::Result;
+use crate::timestamps::set_modified;
+
+/// Elements whose contents are not part of the note's text.
+const SKIPPED_ELEMENTS: &[&str] = &["head", "script", "style", "template", "title"];
+
+/// Elements that start and end a line of text.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Elements followed by a blank line.
+const PARAGRAPH_ELEMENTS: &[&str] = &[
+    "blockquote",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "p",
+    "pre",
+    "table",
+];
+
+/// Extracts the text of a note's HTML for search indexing.
+///
+/// Runs of whitespace are collapsed to one space, and each block (such as a
+/// `<div>` line in a Notes note) gets its own line. Headings and paragraphs
+/// are followed by a blank line, `<br>` starts a new line, list items become
+/// `- ` or `1. ` lines indented by nesting level, and checklist checkboxes
+/// become `[x] ` or `[ ] `. Styles, scripts, and images are left out.
+///
+/// [`ExportOptions::text_twin`](crate::ExportOptions::text_twin) writes this
+/// next to every exported note.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::html_to_text;
+///
+/// let html = "<div><h1>Pancakes</h1></div><div>Flour,   milk,\n eggs.</div>\
+///             <div><br></div><ol><li>Mix</li><li>Fry</li></ol>";
+///
+/// assert_eq!(html_to_text(html), "Pancakes\n\nFlour, milk, eggs.\n\n1. Mix\n2. Fry\n");
+/// ```
+pub fn html_to_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let mut text = TextBuilder::default();
+    text.element(document.root_element(), 0);
+    text.finish()
+}
+
+/// Counts the words and characters in the text of a note's HTML, as
+/// extracted by [`html_to_text`] but without list markers or checkboxes.
+/// Line breaks are not counted as characters.
+pub(crate) fn count_text(html: &str) -> (usize, usize) {
+    let document = Html::parse_document(html);
+    let mut text = TextBuilder {
+        words_only: true,
+        ..Default::default()
+    };
+    text.element(document.root_element(), 0);
+
+    let text = text.finish();
+    let words = text.split_whitespace().count();
+    let chars = text.chars().filter(|&c| c != '\n').count();
+    (words, chars)
+}
+
+/// Writes `<name>.txt` next to every `<name>.html` under `dir`.
+///
+/// With `preserve_timestamps`, each text file gets its note's modification
+/// time.
+pub(crate) fn write_directory(dir: &Path, preserve_timestamps: bool) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            write_directory(&path, preserve_timestamps)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let html = fs::read_to_string(&path)?;
+            let text_path = path.with_extension("txt");
+            fs::write(&text_path, html_to_text(&html))?;
+
+            if preserve_timestamps {
+                set_modified(&text_path, entry.metadata()?.modified()?)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Lines of text collected while walking a document.
+#[derive(Default)]
+struct TextBuilder {
+    lines: Vec<String>,
+    line: String,
+    /// A list marker to start the next line with once it has text.
+    marker: String,
+    /// Leave out list markers and checkboxes, keeping only the note's words.
+    words_only: bool,
+}
+
+impl TextBuilder {
+    fn element(&mut self, element: ElementRef, depth: usize) {
+        let name = element.value().name();
+        if SKIPPED_ELEMENTS.contains(&name) {
+            return;
+        }
+
+        match name {
+            "br" => self.end_line(true),
+            "input" if self.words_only => {}
+            "input" if element.attr("type") == Some("checkbox") => {
+                let checked = element.attr("checked").is_some();
+                self.push(if checked { "[x] " } else { "[ ] " });
+            }
+            _ => {
+                let block = BLOCK_ELEMENTS.contains(&name);
+                if block {
+                    self.end_line(false);
+                }
+                if matches!(name, "ul" | "ol") {
+                    self.list(element, depth);
+                } else {
+                    self.children(element, depth);
+                }
+                if block {
+                    self.end_line(false);
+                }
+                if PARAGRAPH_ELEMENTS.contains(&name) {
+                    self.lines.push(String::new());
+                }
+            }
+        }
+    }
+
+    /// Writes the items of a `<ul>` or `<ol>` nested `depth` lists deep.
+    fn list(&mut self, list: ElementRef, depth: usize) {
+        let ordered = list.value().name() == "ol";
+        let mut number = 0;
+
+        for child in list.children() {
+            match ElementRef::wrap(child) {
+                Some(item) if item.value().name() == "li" => {
+                    number += 1;
+                    self.end_line(false);
+                    let indent = "  ".repeat(depth);
+                    self.marker = if self.words_only {
+                        String::new()
+                    } else if ordered {
+                        format!("{indent}{number}. ")
+                    } else {
+                        format!("{indent}- ")
+                    };
+                    self.children(item, depth + 1);
+                    self.end_line(false);
+                    self.marker.clear();
+                }
+                Some(element) => self.element(element, depth),
+                None => self.node(child.value()),
+            }
+        }
+    }
+
+    fn children(&mut self, element: ElementRef, depth: usize) {
+        for child in element.children() {
+            match ElementRef::wrap(child) {
+                Some(element) => self.element(element, depth),
+                None => self.node(child.value()),
+            }
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        if let Node::Text(text) = node {
+            for (i, word) in text.split(char::is_whitespace).enumerate() {
+                if i > 0 && !self.line.is_empty() && !self.line.ends_with(' ') {
+                    self.line.push(' ');
+                }
+                if !word.is_empty() {
+                    self.push(word);
+                }
+            }
+        }
+    }
+
+    /// Appends `text` to the current line, after any pending list marker.
+    fn push(&mut self, text: &str) {
+        if self.line.is_empty() {
+            self.line.push_str(&std::mem::take(&mut self.marker));
+        }
+        self.line.push_str(text);
+    }
+
+    /// Ends the current line, keeping it even if empty when `force` is set.
+    fn end_line(&mut self, force: bool) {
+        let line = self.line.trim_end();
+        if force || !line.is_empty() {
+            self.lines.push(line.to_string());
+        }
+        self.line.clear();
+    }
+
+    fn finish(mut self) -> String {
+        self.end_line(false);
+
+        // Drop blank lines at the ends and collapse runs of them
+        let mut text = String::new();
+        let mut blank = true;
+        for line in &self.lines {
+            let is_blank = line.is_empty();
+            if is_blank && blank {
+                continue;
+            }
+            text.push_str(line);
+            text.push('\n');
+            blank = is_blank;
+        }
+        if blank && !text.is_empty() {
+            text.pop();
+        }
+        text
+    }
+}
@@ -0,0 +1,62 @@
+//! Giving exported files the dates of the notes they came from.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDateTime};
+
+use crate::Result;
+use crate::markdown::NoteDates;
+
+/// Sets the modification time of each note under `dir` to the note's
+/// modification date in Notes.
+///
+/// Notes are matched to `dates` by the id in their filename
+/// (`<title> -- <id>.html`). Notes without a parseable date keep the time
+/// they were written.
+pub(crate) fn apply_note_dates(dir: &Path, dates: &HashMap<String, NoteDates>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            apply_note_dates(&path, dates)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let updated = stem
+                .rsplit_once(" -- ")
+                .and_then(|(_, id)| dates.get(id))
+                .and_then(|note_dates| parse_date(note_dates.updated()));
+            if let Some(updated) = updated {
+                set_modified(&path, updated.into())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the modification time of the file at `path`.
+pub(crate) fn set_modified(path: &Path, time: SystemTime) -> Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(time)?;
+    Ok(())
+}
+
+/// Parses an ISO 8601 date from the script, taking dates without an offset
+/// as local time.
+pub(crate) fn parse_date(date: &str) -> Option<DateTime<Local>> {
+    let date = date.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return Some(date.with_timezone(&Local));
+    }
+
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S")
+        .ok()?
+        .and_local_timezone(Local)
+        .earliest()
+}
@@ -0,0 +1,110 @@
+//! Choosing which notes to export by title.
+
+use std::fs;
+use std::path::Path;
+
+use crate::Result;
+use crate::export_tree::note_title_from_stem;
+
+/// Glob patterns selecting notes by title.
+///
+/// A note is exported if its title matches any `include` pattern (or there
+/// are none) and no `exclude` pattern. In patterns, `*` matches any run of
+/// characters and `?` matches one character. Matching is case-sensitive and
+/// against the whole title.
+///
+/// # Example
+///
+/// ```
+/// use apple_notes_exporter_rs::TitleFilter;
+///
+/// let filter = TitleFilter {
+///     include: vec!["Meeting *".to_string()],
+///     exclude: vec!["* (draft)".to_string()],
+/// };
+///
+/// assert!(filter.matches("Meeting 2025-03-01"));
+/// assert!(!filter.matches("Meeting 2025-03-08 (draft)"));
+/// assert!(!filter.matches("Shopping List"));
+/// assert!(TitleFilter::default().matches("Shopping List"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TitleFilter {
+    /// Patterns a title must match one of. Empty matches every title.
+    pub include: Vec<String>,
+    /// Patterns a title must not match.
+    pub exclude: Vec<String>,
+}
+
+impl TitleFilter {
+    /// Returns whether a note titled `title` passes the filter.
+    pub fn matches(&self, title: &str) -> bool {
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| glob_match(pattern, title));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| glob_match(pattern, title))
+    }
+
+    /// Returns whether the filter lets every note through.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+/// Removes the notes under `dir` whose titles don't pass `filter`.
+pub(crate) fn filter_directory(dir: &Path, filter: &TitleFilter) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            filter_directory(&path, filter)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            if !filter.matches(note_title_from_stem(stem)) {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Matches `text` against a glob `pattern` with `*` and `?` wildcards.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*` if the rest fails to match
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}